@@ -0,0 +1,10 @@
+fn main() {
+    // Generate gRPC bindings only when the optional gRPC server is enabled
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("Failed to locate vendored protoc");
+        // SAFETY: build scripts are single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        tonic_build::compile_protos("proto/stress.proto").expect("Failed to compile proto/stress.proto");
+    }
+}
@@ -0,0 +1,308 @@
+// Comparative benchmark (POST /benchmark): every mode (and workload) runs for the same time, one after
+// another with a cooldown in between, and the per-second samples of each phase are summarized into one
+// report
+use crate::engine::{ExecutionMode, FieldError, RunOptions, StartCpuRequest, Workload};
+use crate::metrics::HistorySample;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_BENCHMARK_PHASE_SECS: u64 = 30;
+pub const BENCHMARK_PHASE_SECS_RANGE: std::ops::RangeInclusive<u64> = 5..=3600;
+pub const DEFAULT_BENCHMARK_COOLDOWN_SECS: u64 = 10;
+pub const MAX_BENCHMARK_COOLDOWN_SECS: u64 = 600;
+// Longest benchmark (phases and cooldowns) accepted: the whole sequence runs within one HTTP request,
+// which clients and proxies won't keep open for much longer
+pub const MAX_BENCHMARK_TOTAL_SECS: u64 = 1800;
+
+const ALL_MODES: [ExecutionMode; 4] = [
+    ExecutionMode::Threaded,
+    ExecutionMode::FreshProcess,
+    ExecutionMode::Bursty,
+    ExecutionMode::Throttled,
+];
+
+// Body of POST /benchmark
+#[derive(Debug, Clone, Default, Serialize, Deserialize, clap::Args, utoipa::ToSchema)]
+pub struct BenchmarkRequest {
+    /// Modes to compare, in this order [default: threaded, fresh-process, bursty, throttled]
+    #[arg(long, value_enum, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modes: Option<Vec<ExecutionMode>>,
+    /// Workloads to run the modes with, in this order [default: primes]; stream only runs in threaded and
+    /// throttled modes, so the other modes are left out for it
+    #[arg(long, value_enum, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workloads: Option<Vec<Workload>>,
+    /// Length of each phase in seconds (5 to 3600; all phases and cooldowns together at most 1800) [default: 30]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u64>,
+    /// Idle seconds before every phase but the first, so the CPUs cool down (at most 600) [default: 10]
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+    // Options of every phase as in POST /start-cpu, e.g. utilization for bursty and throttled
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: RunOptions,
+}
+
+impl BenchmarkRequest {
+    pub(crate) fn duration_secs(&self) -> u64 {
+        self.duration_secs.unwrap_or(DEFAULT_BENCHMARK_PHASE_SECS)
+    }
+
+    pub(crate) fn cooldown_secs(&self) -> u64 {
+        self.cooldown_secs.unwrap_or(DEFAULT_BENCHMARK_COOLDOWN_SECS)
+    }
+
+    // Start request of every phase, workload by workload; the options themselves are checked by the engine
+    pub(crate) fn phases(&self) -> Result<Vec<StartCpuRequest>, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if !BENCHMARK_PHASE_SECS_RANGE.contains(&self.duration_secs()) {
+            errors.push(FieldError::new("duration_secs", "duration_secs must be between 5 and 3600"));
+        }
+        if self.cooldown_secs() > MAX_BENCHMARK_COOLDOWN_SECS {
+            errors.push(FieldError::new("cooldown_secs", "cooldown_secs must be at most 600"));
+        }
+        let options = &self.options;
+        if options.workload.is_some() {
+            errors.push(FieldError::new("workload", "list the workloads to compare in workloads"));
+        }
+        let rejected = [
+            ("stop_if_ops_below", options.stop_if_ops_below.is_some()),
+            ("stop_if_drop_percent_exceeds", options.stop_if_drop_percent_exceeds.is_some()),
+            ("start_at", options.start_at.is_some()),
+            ("start_delay_ms", options.start_delay_ms.is_some()),
+        ];
+        for (field, _) in rejected.into_iter().filter(|(_, set)| *set) {
+            errors.push(FieldError::new(field, format!("{} doesn't apply to benchmark phases", field)));
+        }
+
+        let modes = self.modes.clone().unwrap_or_else(|| ALL_MODES.to_vec());
+        let workloads = self.workloads.clone().unwrap_or_else(|| vec![Workload::Primes]);
+        if modes.is_empty() {
+            errors.push(FieldError::new("modes", "modes must list at least one mode"));
+        }
+        if workloads.is_empty() {
+            errors.push(FieldError::new("workloads", "workloads must list at least one workload"));
+        }
+        let phases: Vec<StartCpuRequest> = workloads
+            .iter()
+            .flat_map(|&workload| modes.iter().map(move |&mode| (workload, mode)))
+            .filter(|&(workload, mode)| {
+                workload != Workload::Stream || matches!(mode, ExecutionMode::Threaded | ExecutionMode::Throttled)
+            })
+            .map(|(workload, mode)| StartCpuRequest {
+                mode,
+                options: RunOptions {
+                    workload: Some(workload),
+                    ..options.clone()
+                },
+            })
+            .collect();
+        if phases.is_empty() && errors.is_empty() {
+            let message = "the stream workload runs in threaded and throttled modes only; list one of them in modes";
+            errors.push(FieldError::new("workloads", message));
+        }
+        let total_secs = phases.len() as u64 * self.duration_secs()
+            + (phases.len() as u64).saturating_sub(1) * self.cooldown_secs();
+        if errors.is_empty() && total_secs > MAX_BENCHMARK_TOTAL_SECS {
+            let message = format!(
+                "the {} phases and their cooldowns would take {} s; a benchmark may take at most {} s",
+                phases.len(),
+                total_secs,
+                MAX_BENCHMARK_TOTAL_SECS
+            );
+            errors.push(FieldError::new("duration_secs", message));
+        }
+
+        if errors.is_empty() { Ok(phases) } else { Err(errors) }
+    }
+}
+
+// Response of POST /benchmark
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BenchmarkReport {
+    pub started_ms: u64,
+    pub finished_ms: u64,
+    pub duration_secs: u64,
+    pub cooldown_secs: u64,
+    // In the order they ran
+    pub phases: Vec<BenchmarkPhase>,
+}
+
+impl BenchmarkReport {
+    pub(crate) fn new(
+        started_ms: u64,
+        finished_ms: u64,
+        request: &BenchmarkRequest,
+        mut phases: Vec<BenchmarkPhase>,
+    ) -> Self {
+        // Ops of different workloads aren't comparable, so each phase is ranked within its workload
+        let fastest: Vec<f64> = phases
+            .iter()
+            .map(|phase| {
+                phases
+                    .iter()
+                    .filter(|other| other.workload == phase.workload)
+                    .map(|other| other.ops_per_second)
+                    .fold(0.0, f64::max)
+            })
+            .collect();
+        for (phase, fastest) in phases.iter_mut().zip(fastest) {
+            phase.relative = if fastest > 0.0 { phase.ops_per_second / fastest } else { 0.0 };
+        }
+        BenchmarkReport {
+            started_ms,
+            finished_ms,
+            duration_secs: request.duration_secs(),
+            cooldown_secs: request.cooldown_secs(),
+            phases,
+        }
+    }
+}
+
+// One mode (and workload) of a benchmark, summarized over its per-second samples; the first second and the
+// soft start (ramp_secs) are left out
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BenchmarkPhase {
+    pub mode: ExecutionMode,
+    pub workload: Workload,
+    pub started_ms: u64,
+    pub samples: usize,
+    // Mean ops/sec, its standard deviation (also in percent of the mean), and the slowest and fastest second
+    pub ops_per_second: f64,
+    pub ops_per_second_stddev: f64,
+    pub variation_percent: f64,
+    pub min_ops_per_second: u64,
+    pub max_ops_per_second: u64,
+    // Mean ops/sec relative to the fastest phase of the same workload (1 = the fastest)
+    pub relative: f64,
+    // Bursty mode: mean burst-only ops/sec; stream workload: mean memory bandwidth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_ops_per_second: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_gb_per_second: Option<f64>,
+    // Mean utilization of each worker CPU over the phase (every CPU when the workers aren't pinned), and the
+    // gap between the busiest and the idlest of them
+    pub cores: Vec<usize>,
+    pub per_core_utilization_percent: Vec<f32>,
+    pub core_spread_percent: f32,
+}
+
+impl BenchmarkPhase {
+    // Summary of a phase's samples; `cores` are the CPUs its workers were pinned to (empty if unpinned)
+    pub(crate) fn new(request: &StartCpuRequest, started_ms: u64, samples: &[HistorySample], cores: &[usize]) -> Self {
+        let ops: Vec<f64> = samples.iter().map(|sample| sample.status.ops_per_second as f64).collect();
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len().max(1) as f64;
+        let ops_per_second = mean(&ops);
+        let variance = ops.iter().map(|ops| (ops - ops_per_second).powi(2)).sum::<f64>() / ops.len().max(1) as f64;
+        let stddev = variance.sqrt();
+
+        let cores: Vec<usize> = if cores.is_empty() {
+            let cpus = samples.iter().map(|sample| sample.status.per_core_utilization_percent.len()).max().unwrap_or(0);
+            (0..cpus).collect()
+        } else {
+            cores.to_vec()
+        };
+        let per_core_utilization_percent: Vec<f32> = cores
+            .iter()
+            .map(|&cpu| {
+                let percents: Vec<f64> = samples
+                    .iter()
+                    .filter_map(|sample| sample.status.per_core_utilization_percent.get(cpu).map(|&p| p as f64))
+                    .collect();
+                mean(&percents) as f32
+            })
+            .collect();
+        let busiest = per_core_utilization_percent.iter().copied().fold(f32::MIN, f32::max);
+        let idlest = per_core_utilization_percent.iter().copied().fold(f32::MAX, f32::min);
+
+        let workload = request.options.workload.unwrap_or_default();
+        let column = |value: fn(&HistorySample) -> f64| mean(&samples.iter().map(value).collect::<Vec<f64>>());
+        BenchmarkPhase {
+            mode: request.mode,
+            workload,
+            started_ms,
+            samples: samples.len(),
+            ops_per_second,
+            ops_per_second_stddev: stddev,
+            variation_percent: if ops_per_second > 0.0 { stddev / ops_per_second * 100.0 } else { 0.0 },
+            min_ops_per_second: samples.iter().map(|sample| sample.status.ops_per_second).min().unwrap_or(0),
+            max_ops_per_second: samples.iter().map(|sample| sample.status.ops_per_second).max().unwrap_or(0),
+            relative: 0.0,
+            burst_ops_per_second: (request.mode == ExecutionMode::Bursty)
+                .then(|| column(|sample| sample.status.burst_ops_per_second as f64)),
+            memory_gb_per_second: (workload == Workload::Stream)
+                .then(|| column(|sample| sample.status.memory_gb_per_second)),
+            cores,
+            core_spread_percent: if per_core_utilization_percent.is_empty() { 0.0 } else { busiest - idlest },
+            per_core_utilization_percent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn benchmark_requests_expand_into_phases() {
+        let request: BenchmarkRequest =
+            serde_json::from_str(r#"{"modes":["bursty","threaded"],"workloads":["primes","stream"],"nice":5}"#)
+                .unwrap();
+        let phases = request.phases().unwrap();
+        let phases: Vec<(ExecutionMode, Option<Workload>, Option<i32>)> =
+            phases.iter().map(|phase| (phase.mode, phase.options.workload, phase.options.nice)).collect();
+        assert_eq!(
+            phases,
+            [
+                (ExecutionMode::Bursty, Some(Workload::Primes), Some(5)),
+                (ExecutionMode::Threaded, Some(Workload::Primes), Some(5)),
+                (ExecutionMode::Threaded, Some(Workload::Stream), Some(5)),
+            ]
+        );
+        assert_eq!(BenchmarkRequest::default().phases().unwrap().len(), 4);
+
+        let invalid = r#"{"modes":[],"duration_secs":1,"cooldown_secs":900,"start_delay_ms":5}"#;
+        let errors = serde_json::from_str::<BenchmarkRequest>(invalid).unwrap().phases().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, ["duration_secs", "cooldown_secs", "start_delay_ms", "modes"]);
+
+        // 4 × 600 s of phases and 3 × 10 s of cooldowns
+        let too_long = r#"{"duration_secs":600}"#;
+        let errors = serde_json::from_str::<BenchmarkRequest>(too_long).unwrap().phases().unwrap_err();
+        assert_eq!(errors[0].field, "duration_secs");
+        assert!(errors[0].message.contains("2430 s"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn phases_are_ranked_within_their_workload() {
+        let phase = |mode, workload, ops_per_second| BenchmarkPhase {
+            mode,
+            workload,
+            started_ms: 0,
+            samples: 1,
+            ops_per_second,
+            ops_per_second_stddev: 0.0,
+            variation_percent: 0.0,
+            min_ops_per_second: ops_per_second as u64,
+            max_ops_per_second: ops_per_second as u64,
+            relative: 0.0,
+            burst_ops_per_second: None,
+            memory_gb_per_second: None,
+            cores: Vec::new(),
+            per_core_utilization_percent: Vec::new(),
+            core_spread_percent: 0.0,
+        };
+        let phases = vec![
+            phase(ExecutionMode::Threaded, Workload::Primes, 1000.0),
+            phase(ExecutionMode::Throttled, Workload::Primes, 500.0),
+            phase(ExecutionMode::Threaded, Workload::Stream, 10.0),
+            phase(ExecutionMode::Throttled, Workload::Stream, 5.0),
+        ];
+        let report = BenchmarkReport::new(0, 0, &BenchmarkRequest::default(), phases);
+        let relative: Vec<f64> = report.phases.iter().map(|phase| phase.relative).collect();
+        assert_eq!(relative, [1.0, 0.5, 1.0, 0.5]);
+    }
+}
@@ -0,0 +1,301 @@
+// Campaigns: named fleet runs that are started, paused, resumed, and aborted as one object. The
+// coordinator (the client) keeps each campaign as a JSON file so it survives across invocations.
+use crate::engine::StartCpuRequest;
+use crate::metrics::{Histograms, MetricsSnapshot};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CampaignState {
+    Running,
+    Paused,
+    // Final: an aborted campaign can't be resumed
+    Aborted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CampaignAction {
+    Start,
+    Pause,
+    Resume,
+    Abort,
+}
+
+// One lifecycle operation and the agents it could not be applied to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignEvent {
+    pub timestamp_ms: u64,
+    pub action: CampaignAction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Campaign {
+    pub name: String,
+    pub hosts: Vec<String>,
+    // Start request sent to every agent on start and resume
+    pub request: StartCpuRequest,
+    // Shared burst schedule (--sync): offset of each successive agent's copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase_step_ms: Option<u64>,
+    pub state: CampaignState,
+    pub events: Vec<CampaignEvent>,
+}
+
+impl Campaign {
+    pub fn new(name: &str, hosts: Vec<String>, request: StartCpuRequest, phase_step_ms: Option<u64>) -> Self {
+        Campaign {
+            name: name.to_string(),
+            hosts,
+            request,
+            phase_step_ms,
+            state: CampaignState::Running,
+            events: Vec::new(),
+        }
+    }
+
+    // Start request for the agent at `index` of the campaign's hosts
+    pub fn request_for_host(&self, index: usize) -> StartCpuRequest {
+        let mut request = self.request.clone();
+        if let Some(phase_step_ms) = self.phase_step_ms {
+            let base_offset_ms = request.options.phase_offset_ms.unwrap_or(0);
+            request.options.phase_offset_ms = Some(base_offset_ms + index as u64 * phase_step_ms);
+        }
+        request
+    }
+
+    // State the campaign is in after `action`. Pause and resume may be repeated (e.g. to retry
+    // agents that failed the first time); nothing but a report is allowed after an abort.
+    pub fn next_state(&self, action: CampaignAction) -> Result<CampaignState, String> {
+        if self.state == CampaignState::Aborted {
+            return Err(format!("campaign {} was aborted", self.name));
+        }
+        Ok(match action {
+            CampaignAction::Start | CampaignAction::Resume => CampaignState::Running,
+            CampaignAction::Pause => CampaignState::Paused,
+            CampaignAction::Abort => CampaignState::Aborted,
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        action: CampaignAction,
+        timestamp_ms: u64,
+        failed_hosts: Vec<String>,
+    ) -> Result<(), String> {
+        self.state = self.next_state(action)?;
+        self.events.push(CampaignEvent {
+            timestamp_ms,
+            action,
+            failed_hosts,
+        });
+        Ok(())
+    }
+
+    pub fn started_ms(&self) -> Option<u64> {
+        self.events.first().map(|event| event.timestamp_ms)
+    }
+
+    // Time spent running (not paused) up to `now_ms`
+    pub fn active_ms(&self, now_ms: u64) -> u64 {
+        let mut active_ms = 0;
+        let mut running_since = None;
+        for event in &self.events {
+            match (event.action, running_since) {
+                (CampaignAction::Start | CampaignAction::Resume, None) => running_since = Some(event.timestamp_ms),
+                (CampaignAction::Pause | CampaignAction::Abort, Some(since)) => {
+                    active_ms += event.timestamp_ms.saturating_sub(since);
+                    running_since = None;
+                }
+                _ => {}
+            }
+        }
+        active_ms + running_since.map_or(0, |since| now_ms.saturating_sub(since))
+    }
+
+    pub fn pauses(&self) -> usize {
+        // Repeated pauses (retries) count once
+        let mut pauses = 0;
+        let mut paused = false;
+        for event in &self.events {
+            match event.action {
+                CampaignAction::Pause if !paused => {
+                    pauses += 1;
+                    paused = true;
+                }
+                CampaignAction::Start | CampaignAction::Resume => paused = false,
+                _ => {}
+            }
+        }
+        pauses
+    }
+}
+
+// One agent's part of a campaign report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentReport {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<MetricsSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Campaign-level report: lifecycle, every agent's status, and the fleet's merged histograms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignReport {
+    pub name: String,
+    pub state: CampaignState,
+    pub started_ms: Option<u64>,
+    pub active_secs: u64,
+    pub pauses: usize,
+    pub agents_running: usize,
+    pub fleet_ops_per_second: u64,
+    // Package power of the running agents that report it (RAPL); None if none do
+    #[serde(default)]
+    pub fleet_watts: Option<f64>,
+    pub agents: Vec<AgentReport>,
+    pub histograms: Histograms,
+}
+
+// Directory of campaign files (<name>.json)
+#[derive(Debug)]
+pub struct CampaignStore {
+    dir: PathBuf,
+}
+
+impl CampaignStore {
+    pub fn new(dir: &Path) -> Self {
+        CampaignStore { dir: dir.to_path_buf() }
+    }
+
+    // ~/.distributed-cpu-stress-reporter/campaigns (campaigns can run for days, so not a temp dir)
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join(".distributed-cpu-stress-reporter")
+            .join("campaigns")
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, String> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !name.starts_with('.');
+        if !valid {
+            return Err(format!(
+                "invalid campaign name \"{}\" (use letters, digits, '-', '_', and '.')",
+                name
+            ));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    // Store a new campaign, failing if one with the same name exists
+    pub fn create(&self, campaign: &Campaign) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("failed to create {}: {}", self.dir.display(), e))?;
+        let path = self.path(&campaign.name)?;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => format!("campaign {} already exists", campaign.name),
+                _ => format!("failed to create {}: {}", path.display(), e),
+            })?;
+        let json = serde_json::to_string_pretty(campaign).expect("Failed to serialize campaign");
+        file.write_all(json.as_bytes())
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn load(&self, name: &str) -> Result<Campaign, String> {
+        let path = self.path(name)?;
+        let json = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("no campaign named {}", name),
+            _ => format!("failed to read {}: {}", path.display(), e),
+        })?;
+        serde_json::from_str(&json).map_err(|e| format!("failed to parse {}: {}", path.display(), e))
+    }
+
+    // Replace a stored campaign (write to a temporary file, then rename, so it is never half-written)
+    pub fn save(&self, campaign: &Campaign) -> Result<(), String> {
+        let path = self.path(&campaign.name)?;
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(campaign).expect("Failed to serialize campaign");
+        std::fs::write(&tmp_path, json).map_err(|e| format!("failed to write {}: {}", tmp_path.display(), e))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| format!("failed to replace {}: {}", path.display(), e))
+    }
+
+    // All stored campaigns, by name
+    pub fn list(&self) -> Result<Vec<Campaign>, String> {
+        let dir = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("failed to read {}: {}", self.dir.display(), e)),
+        };
+        let mut campaigns = Vec::new();
+        for path in dir.flatten().map(|entry| entry.path()) {
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                campaigns.push(self.load(name)?);
+            }
+        }
+        campaigns.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(campaigns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign() -> Campaign {
+        let request = serde_json::from_str(r#"{"mode":"bursty","phase_offset_ms":100}"#).unwrap();
+        Campaign::new("soak", vec!["a:8080".to_string(), "b:8080".to_string()], request, Some(250))
+    }
+
+    #[test]
+    fn lifecycle_tracks_active_time_and_pauses() {
+        let mut campaign = campaign();
+        campaign.record(CampaignAction::Start, 1_000, Vec::new()).unwrap();
+        campaign.record(CampaignAction::Pause, 11_000, Vec::new()).unwrap();
+        // Retrying a pause doesn't count as another pause
+        campaign.record(CampaignAction::Pause, 12_000, vec!["b:8080".to_string()]).unwrap();
+        campaign.record(CampaignAction::Resume, 61_000, Vec::new()).unwrap();
+        assert_eq!(campaign.state, CampaignState::Running);
+        assert_eq!(campaign.active_ms(66_000), 15_000);
+        assert_eq!(campaign.pauses(), 1);
+
+        campaign.record(CampaignAction::Abort, 71_000, Vec::new()).unwrap();
+        assert_eq!(campaign.active_ms(1_000_000), 20_000);
+        assert!(campaign.record(CampaignAction::Resume, 72_000, Vec::new()).is_err());
+        assert_eq!(campaign.state, CampaignState::Aborted);
+
+        assert_eq!(campaign.request_for_host(0).options.phase_offset_ms, Some(100));
+        assert_eq!(campaign.request_for_host(1).options.phase_offset_ms, Some(350));
+    }
+
+    #[test]
+    fn store_round_trips_and_refuses_duplicates() {
+        let dir = std::env::temp_dir().join(format!("cpu-stress-campaign-test-{}", std::process::id()));
+        let store = CampaignStore::new(&dir);
+        let mut campaign = campaign();
+        store.create(&campaign).unwrap();
+        assert!(store.create(&campaign).unwrap_err().contains("already exists"));
+
+        campaign.record(CampaignAction::Start, 1_000, Vec::new()).unwrap();
+        store.save(&campaign).unwrap();
+        let loaded = store.list().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].events.len(), 1);
+        assert!(store.load("../etc/passwd").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
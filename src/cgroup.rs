@@ -0,0 +1,168 @@
+// Resource capping for spawned worker processes with a dedicated cgroup v2 (Linux only), and detection
+// of the CPUs a container actually gets
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Period written to cpu.max; the quota is a share of it per allowed CPU
+const CPU_MAX_PERIOD_US: u64 = 100_000;
+
+// A cgroup v2 directory that fresh-process and bursty worker processes are moved into, so a
+// runaway stress run is capped by cpu.max / memory.max and can't starve the control server
+#[derive(Debug)]
+pub struct WorkerCgroup {
+    path: PathBuf,
+}
+
+impl WorkerCgroup {
+    // Create (or reuse) the cgroup at `path` and apply the limits. `cpus` is the number of CPUs'
+    // worth of time workers may use in total; `memory_max` is written verbatim (bytes, or with a
+    // K/M/G suffix, or "max").
+    pub fn create(path: &Path, cpus: Option<f64>, memory_max: Option<&str>) -> Result<Self, String> {
+        std::fs::create_dir_all(path).map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+
+        // Limits only take effect if the parent delegates the controllers to its children
+        let mut controllers = Vec::new();
+        if cpus.is_some() {
+            controllers.push("+cpu");
+        }
+        if memory_max.is_some() {
+            controllers.push("+memory");
+        }
+        if !controllers.is_empty()
+            && let Some(parent) = path.parent()
+        {
+            write_file(&parent.join("cgroup.subtree_control"), &controllers.join(" "))?;
+        }
+
+        if let Some(cpus) = cpus {
+            if !cpus.is_finite() || cpus <= 0.0 {
+                return Err("the CPU limit must be a positive number of CPUs".to_string());
+            }
+            let quota_us = ((cpus * CPU_MAX_PERIOD_US as f64) as u64).max(1000);
+            write_file(&path.join("cpu.max"), &format!("{} {}", quota_us, CPU_MAX_PERIOD_US))?;
+        }
+        if let Some(memory_max) = memory_max {
+            write_file(&path.join("memory.max"), memory_max)?;
+        }
+
+        Ok(WorkerCgroup {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Move a freshly spawned worker process into the cgroup
+    pub(crate) fn add(&self, pid: u32) -> Result<(), String> {
+        write_file(&self.path.join("cgroup.procs"), &pid.to_string())
+    }
+}
+
+fn write_file(path: &Path, value: &str) -> Result<(), String> {
+    std::fs::write(path, value).map_err(|e| format!("failed to write \"{}\" to {}: {}", value, path.display(), e))
+}
+
+// CPUs this process can use: its affinity mask (a container's cpuset) and the tightest CPU quota of its
+// cgroup and their ancestors (cpu.max, or cpu.cfs_quota_us with cgroup v1). num_cpus::get() rounds the
+// quota up, so a container with 1.5 CPUs would get two workers that are throttled in turn.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CpuAllocation {
+    pub cpuset_cpus: usize,
+    // None if unlimited
+    pub quota_cpus: Option<f64>,
+    // CPUs the workers can keep busy: the cpuset, capped at the quota rounded down (at least 1)
+    pub effective_cpus: usize,
+}
+
+impl CpuAllocation {
+    pub fn new(cpuset_cpus: usize, quota_cpus: Option<f64>) -> Self {
+        let cpuset_cpus = cpuset_cpus.max(1);
+        let effective_cpus = quota_cpus.map_or(cpuset_cpus, |quota| (quota.floor() as usize).clamp(1, cpuset_cpus));
+        CpuAllocation {
+            cpuset_cpus,
+            quota_cpus,
+            effective_cpus,
+        }
+    }
+
+    pub fn detect() -> Self {
+        let proc_cgroup = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+        CpuAllocation::new(
+            crate::cores::allowed_cores().len(),
+            cpu_quota(Path::new("/sys/fs/cgroup"), &proc_cgroup),
+        )
+    }
+}
+
+// Quota of one cgroup in CPUs: cpu.max ("150000 100000", or "max 100000" if unlimited), or the v1
+// cpu.cfs_quota_us (-1 if unlimited) over cpu.cfs_period_us
+fn read_quota(dir: &Path, v2: bool) -> Option<f64> {
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+    let (quota, period) = if v2 {
+        let cpu_max = read("cpu.max")?;
+        let (quota, period) = cpu_max.trim().split_once(' ')?;
+        (quota.parse::<f64>().ok()?, period.parse::<f64>().ok()?)
+    } else {
+        (read("cpu.cfs_quota_us")?.trim().parse::<f64>().ok()?, read("cpu.cfs_period_us")?.trim().parse::<f64>().ok()?)
+    };
+    (quota > 0.0 && period > 0.0).then(|| quota / period)
+}
+
+// Tightest quota along the process's cgroups (from /proc/self/cgroup) up to the root of each hierarchy
+// mounted under `root`; None if unlimited
+fn cpu_quota(root: &Path, proc_cgroup: &str) -> Option<f64> {
+    let mut tightest: Option<f64> = None;
+    for line in proc_cgroup.lines() {
+        // "0::/path" for cgroup v2, "4:cpu,cpuacct:/path" for the v1 cpu controller
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let v2 = controllers.is_empty();
+        if !v2 && !controllers.split(',').any(|controller| controller == "cpu") {
+            continue;
+        }
+        let hierarchy = if v2 { root.to_path_buf() } else { root.join(controllers) };
+        let mut dir = hierarchy.join(path.trim_start_matches('/'));
+        loop {
+            if let Some(quota) = read_quota(&dir, v2) {
+                tightest = Some(tightest.map_or(quota, |tightest| tightest.min(quota)));
+            }
+            if dir == hierarchy || !dir.pop() {
+                break;
+            }
+        }
+    }
+    tightest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota_is_the_tightest_along_the_hierarchy() {
+        let root = std::env::temp_dir().join(format!("cpu-stress-cgroup-test-{}", std::process::id()));
+        let pod = root.join("kubepods/pod1");
+        std::fs::create_dir_all(pod.join("container")).unwrap();
+        std::fs::write(root.join("kubepods/cpu.max"), "max 100000\n").unwrap();
+        std::fs::write(pod.join("cpu.max"), "250000 100000\n").unwrap();
+        std::fs::write(pod.join("container/cpu.max"), "400000 100000\n").unwrap();
+        assert_eq!(cpu_quota(&root, "0::/kubepods/pod1/container\n"), Some(2.5));
+        assert_eq!(cpu_quota(&root, "0::/kubepods\n"), None);
+
+        let v1 = root.join("cpu,cpuacct/docker/abc");
+        std::fs::create_dir_all(&v1).unwrap();
+        std::fs::write(v1.join("cpu.cfs_quota_us"), "150000\n").unwrap();
+        std::fs::write(v1.join("cpu.cfs_period_us"), "100000\n").unwrap();
+        assert_eq!(cpu_quota(&root, "5:memory:/docker/abc\n4:cpu,cpuacct:/docker/abc\n"), Some(1.5));
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(CpuAllocation::new(8, Some(1.5)).effective_cpus, 1);
+        assert_eq!(CpuAllocation::new(8, Some(0.5)).effective_cpus, 1);
+        assert_eq!(CpuAllocation::new(4, Some(6.0)).effective_cpus, 4);
+        assert_eq!(CpuAllocation::new(4, None).effective_cpus, 4);
+    }
+}
@@ -0,0 +1,933 @@
+// Client side: the start/stop/config/calibrate/benchmark/status/watch/histogram/peers/campaign/collect subcommands
+// and replay of recorded control sessions
+use crate::benchmark::{BenchmarkReport, BenchmarkRequest};
+use crate::campaign::{AgentReport, Campaign, CampaignAction, CampaignReport, CampaignStore};
+use crate::discovery::Peer;
+use crate::encryption::{EncryptionKey, read_line};
+use crate::engine::{ConfigUpdate, ExecutionMode, StartCpuRequest, Workload, unix_time_ms};
+use crate::http::{ControlRecord, ErrorResponse, MessageResponse};
+use crate::metrics::{Baseline, Histograms, MetricsSnapshot, Percentiles, TemperatureReading, mode_name};
+use crate::sketch::Sketch;
+use crate::sync::serve_collector;
+use crate::units::{TimeZone, format_rfc3339, format_time_of_day, format_timestamp, si};
+use clap::Subcommand;
+use std::time::{Duration, Instant};
+
+// Parsed once per invocation, so the size of the start options doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+pub enum ClientCommand {
+    /// Start (or switch the mode of) the CPU stress test on agents
+    Start {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        request: StartCpuRequest,
+        /// Bursty mode: send all agents one shared burst schedule so they burst simultaneously
+        #[arg(long)]
+        sync: bool,
+        /// With --sync: offset each successive agent's bursts by this many milliseconds
+        #[arg(long, default_value = "0", requires = "sync")]
+        phase_step_ms: u64,
+        /// Bursty mode: play the burst durations in this file (seconds, one per line, # comments)
+        #[arg(long, value_name = "FILE", conflicts_with = "burst_schedule")]
+        burst_schedule_file: Option<std::path::PathBuf>,
+    },
+    /// Stop the CPU stress test on agents
+    Stop {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Change agent settings without restarting them (applies to running stress tests too)
+    Config {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        update: ConfigUpdate,
+    },
+    /// Measure the single-core baseline of idle agents; their status then includes normalized scores
+    Calibrate {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+        /// Length of the measurement in milliseconds [default: 2000]
+        #[arg(long)]
+        duration_ms: Option<u64>,
+    },
+    /// Benchmark idle agents, all at once: run each mode for the same time with cooldowns in between, and
+    /// print how they compare
+    Benchmark {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+        #[command(flatten)]
+        request: BenchmarkRequest,
+        /// Print the reports as JSON, one line per agent
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the current state and metrics of agents
+    Status {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+    },
+    /// Continuously print ops/sec of agents (Ctrl+C to exit)
+    Watch {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+        /// Seconds between updates
+        #[arg(long, default_value = "1.0")]
+        interval: f64,
+    },
+    /// Merge the batch latency and ops/sec histograms of agents into fleet-wide percentiles
+    Histogram {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        display: DisplayArgs,
+        /// Print the merged histograms as JSON instead of percentiles
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the agents that agents started with --discovery have heard on their LAN
+    Peers {
+        #[command(flatten)]
+        target: TargetArgs,
+        /// Print only the hosts, comma-separated, e.g. for another subcommand's --host
+        #[arg(long, conflicts_with = "json")]
+        hosts_only: bool,
+        /// Print the peers as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Manage campaigns: named fleet runs that are paused, resumed, aborted, and reported on as one
+    Campaign {
+        /// Directory campaigns are stored in [default: ~/.distributed-cpu-stress-reporter/campaigns]
+        #[arg(long, value_name = "DIR", global = true)]
+        campaign_dir: Option<std::path::PathBuf>,
+        #[command(flatten)]
+        display: DisplayArgs,
+        #[command(subcommand)]
+        command: CampaignCommand,
+    },
+    /// Write a tarball with this binary, a config holding the agent flags given before `package`, and a
+    /// systemd unit; deploy with `tar -xf FILE -C / && systemctl daemon-reload && systemctl enable --now
+    /// distributed-cpu-stress-reporter`
+    Package {
+        /// Tarball to write
+        #[arg(long, short, value_name = "FILE", default_value = "distributed-cpu-stress-reporter.tar")]
+        output: std::path::PathBuf,
+    },
+    /// Receive the samples that agents push with --sync-to (one <agent>.jsonl file per agent) and the reports
+    /// of --report-to http://<collector>:<port>/reports (reports.jsonl; GET /reports has each agent's latest)
+    Collect {
+        /// Port to listen on
+        #[arg(long, default_value = "9090")]
+        port: u16,
+        /// Directory to store the samples and reports in
+        #[arg(long, value_name = "DIR", default_value = "samples")]
+        dir: std::path::PathBuf,
+    },
+}
+
+// Parsed once per invocation, so the size of the start options doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+pub enum CampaignCommand {
+    /// Create a campaign and start it on its agents
+    Start {
+        /// Campaign name (letters, digits, '-', '_', and '.')
+        name: String,
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        request: StartCpuRequest,
+        /// Bursty mode: send all agents one shared burst schedule so they burst simultaneously
+        #[arg(long)]
+        sync: bool,
+        /// With --sync: offset each successive agent's bursts by this many milliseconds
+        #[arg(long, default_value = "0", requires = "sync")]
+        phase_step_ms: u64,
+        /// Bursty mode: play the burst durations in this file (seconds, one per line, # comments)
+        #[arg(long, value_name = "FILE", conflicts_with = "burst_schedule")]
+        burst_schedule_file: Option<std::path::PathBuf>,
+    },
+    /// Stop every agent of a campaign until it is resumed (repeat to retry failed agents)
+    Pause { name: String },
+    /// Restart every agent of a paused campaign with the campaign's settings
+    Resume { name: String },
+    /// Stop every agent of a campaign for good
+    Abort { name: String },
+    /// Print a campaign's lifecycle, agent status, and fleet-wide percentiles
+    Report {
+        name: String,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List stored campaigns
+    List,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TargetArgs {
+    /// Agent address (host:port); repeat or comma-separate for a fleet
+    #[arg(long = "host", value_name = "HOST:PORT", value_delimiter = ',', default_value = "localhost:8080")]
+    pub hosts: Vec<String>,
+}
+
+// How human-readable output is printed; JSON output (--json) always has the raw values
+#[derive(clap::Args, Debug, Clone, Copy)]
+pub struct DisplayArgs {
+    /// Print exact numbers instead of SI-scaled ones (1234567 instead of 1.23M)
+    #[arg(long, global = true)]
+    raw: bool,
+    /// Time zone of printed timestamps: local, utc, or an offset such as +05:30
+    #[arg(long, value_name = "ZONE", default_value = "local", value_parser = TimeZone::parse, global = true)]
+    time_zone: TimeZone,
+}
+
+impl DisplayArgs {
+    fn number(&self, value: f64) -> String {
+        if self.raw { format!("{:.1}", value) } else { si(value) }
+    }
+
+    fn ops(&self, ops: u64) -> String {
+        if self.raw { ops.to_string() } else { si(ops as f64) }
+    }
+
+    fn timestamp(&self, timestamp_ms: u64) -> String {
+        format_timestamp(timestamp_ms, self.time_zone)
+    }
+}
+
+// Replay a recorded control session against one or more agents, preserving (scaled) timing
+pub async fn replay_control(
+    path: &std::path::Path,
+    targets: &[String],
+    time_scale: f64,
+    key: Option<&EncryptionKey>,
+) {
+    let contents = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read recording {}: {}", path.display(), e));
+
+    let mut records = Vec::new();
+    for (line_no, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = match read_line(line, key) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Skipping line {} of recording: {}", line_no + 1, e);
+                continue;
+            }
+        };
+        match serde_json::from_str::<ControlRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => eprintln!("Skipping line {} of recording: {}", line_no + 1, e),
+        }
+    }
+
+    let Some(first_timestamp_ms) = records.first().map(|r| r.timestamp_ms) else {
+        println!("Recording is empty, nothing to replay");
+        return;
+    };
+
+    println!(
+        "Replaying {} control calls against {} target(s) at time scale {}",
+        records.len(),
+        targets.len(),
+        time_scale
+    );
+
+    let client = reqwest::Client::new();
+    let replay_start = Instant::now();
+
+    for record in records {
+        // Wait until this call's scaled offset from the first recorded call
+        let offset_ms = record.timestamp_ms.saturating_sub(first_timestamp_ms) as f64 * time_scale;
+        let due = Duration::from_secs_f64(offset_ms / 1000.0);
+        let elapsed = replay_start.elapsed();
+        if due > elapsed {
+            tokio::time::sleep(due - elapsed).await;
+        }
+
+        let method = match reqwest::Method::from_bytes(record.method.as_bytes()) {
+            Ok(method) => method,
+            Err(_) => {
+                eprintln!("Skipping call with invalid method: {}", record.method);
+                continue;
+            }
+        };
+
+        // Fan the call out to every target concurrently
+        let mut tasks = Vec::new();
+        for target in targets {
+            let mut builder = client.request(
+                method.clone(),
+                format!("http://{}{}", target, record.path),
+            );
+            if let Some(body) = &record.body {
+                builder = builder.json(body);
+            }
+            let target = target.clone();
+            tasks.push(tokio::spawn(async move { (target, builder.send().await) }));
+        }
+
+        for task in tasks {
+            let Ok((target, result)) = task.await else {
+                continue;
+            };
+            match result {
+                Ok(response) => println!(
+                    "[{:>8.1}s] {} {} {} -> {}",
+                    replay_start.elapsed().as_secs_f64(),
+                    target,
+                    record.method,
+                    record.path,
+                    response.status()
+                ),
+                Err(e) => eprintln!(
+                    "[{:>8.1}s] {} {} {} failed: {}",
+                    replay_start.elapsed().as_secs_f64(),
+                    target,
+                    record.method,
+                    record.path,
+                    e
+                ),
+            }
+        }
+    }
+
+    println!("Replay complete");
+}
+
+// Turn --start-delay-ms into one start_at for every agent, so they start together rather than each the
+// delay after its own request
+fn arm_fleet(request: &mut StartCpuRequest) {
+    if let Some(delay_ms) = request.options.start_delay_ms.take() {
+        request.options.start_at = Some(format_rfc3339(unix_time_ms() + delay_ms));
+    }
+}
+
+// Read a burst schedule file: one duration in seconds per line; blank lines and # comments are skipped
+fn read_burst_schedule(path: &std::path::Path) -> Vec<f64> {
+    let contents = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read burst schedule {}: {}", path.display(), e));
+
+    let mut schedule = Vec::new();
+    for (line_no, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.parse::<f64>() {
+            Ok(secs) => schedule.push(secs),
+            Err(_) => {
+                eprintln!("Line {} of {} is not a duration in seconds: {}", line_no + 1, path.display(), line);
+                std::process::exit(2);
+            }
+        }
+    }
+    schedule
+}
+
+// Built-in client: send control and query requests to one or more agents
+pub async fn run_client(command: ClientCommand) {
+    let client = reqwest::Client::new();
+
+    match command {
+        ClientCommand::Start {
+            target,
+            mut request,
+            sync,
+            phase_step_ms,
+            burst_schedule_file,
+        } => {
+            if let Some(path) = &burst_schedule_file {
+                request.options.burst_schedule = Some(read_burst_schedule(path));
+            }
+
+            // Broadcast one schedule (seed + epoch slightly in the future) to every agent
+            if sync {
+                request.options.schedule_seed.get_or_insert_with(rand::random);
+                request.options.schedule_epoch_ms.get_or_insert(unix_time_ms() + 2000);
+            }
+            arm_fleet(&mut request);
+            let base_offset_ms = request.options.phase_offset_ms.unwrap_or(0);
+
+            for (i, host) in target.hosts.iter().enumerate() {
+                if sync {
+                    request.options.phase_offset_ms = Some(base_offset_ms + i as u64 * phase_step_ms);
+                }
+                let result = client
+                    .post(format!("http://{}/start-cpu", host))
+                    .json(&request)
+                    .send()
+                    .await;
+                print_text_response(host, result).await;
+            }
+        }
+        ClientCommand::Stop { target } => {
+            for host in &target.hosts {
+                let result = client.post(format!("http://{}/end-cpu", host)).send().await;
+                print_text_response(host, result).await;
+            }
+        }
+        ClientCommand::Config { target, update } => {
+            for host in &target.hosts {
+                let result = client
+                    .patch(format!("http://{}/config", host))
+                    .json(&update)
+                    .send()
+                    .await;
+                print_text_response(host, result).await;
+            }
+        }
+        ClientCommand::Calibrate {
+            target,
+            display,
+            duration_ms,
+        } => {
+            let body = serde_json::json!({ "duration_ms": duration_ms });
+            for host in &target.hosts {
+                let result = client.post(format!("http://{}/calibrate", host)).json(&body).send().await;
+                match json_response::<Baseline>(result).await {
+                    Ok(baseline) => {
+                        println!("{}: baseline {} ops/sec on one core", host, display.ops(baseline.ops_per_second))
+                    }
+                    Err(e) => eprintln!("{}: Error: {}", host, e),
+                }
+            }
+        }
+        ClientCommand::Benchmark {
+            target,
+            display,
+            request,
+            json,
+        } => {
+            // Concurrently, so the agents' phases line up
+            let mut tasks = Vec::new();
+            for host in &target.hosts {
+                let builder = client.post(format!("http://{}/benchmark", host)).json(&request);
+                let host = host.clone();
+                tasks.push(tokio::spawn(async move {
+                    (host, json_response::<BenchmarkReport>(builder.send().await).await)
+                }));
+            }
+            for task in tasks {
+                let Ok((host, result)) = task.await else {
+                    continue;
+                };
+                match result {
+                    Ok(report) if json => {
+                        let line = serde_json::json!({ "host": host, "report": report });
+                        println!("{}", serde_json::to_string(&line).expect("Failed to serialize benchmark report"));
+                    }
+                    Ok(report) => print_benchmark(&host, &report, &display),
+                    Err(e) => eprintln!("{}: Error: {}", host, e),
+                }
+            }
+        }
+        ClientCommand::Status { target, display } => {
+            for host in &target.hosts {
+                match fetch_status(&client, host).await {
+                    Ok(status) => println!(
+                        "{}: {} mode={} utilization={}% ops/sec={}{} burst ops/sec={}{} system cpu={:.1}%\
+                         {}{}{}{}{}{}{}{}",
+                        host,
+                        if status.running { "RUNNING" } else { "STOPPED" },
+                        mode_name(status.mode),
+                        status.utilization,
+                        display.ops(status.ops_per_second),
+                        format_percentiles(&status.percentiles.ops_per_second, &display),
+                        display.ops(status.burst_ops_per_second),
+                        if status.workload == Workload::Stream {
+                            format!(" memory={:.2} GB/s", status.memory_gb_per_second)
+                        } else {
+                            String::new()
+                        },
+                        status.cpu_utilization_percent,
+                        status.nice.map(|nice| format!(" nice={}", nice)).unwrap_or_default(),
+                        status
+                            .cpu_allocation
+                            .and_then(|allocation| allocation.quota_cpus)
+                            .map(|quota| format!(" workers={} (cpu quota {:.2})", status.workers, quota))
+                            .unwrap_or_default(),
+                        max_temperature(&status.temperatures)
+                            .map(|celsius| format!(" max temp={:.1}°C", celsius))
+                            .unwrap_or_default(),
+                        status
+                            .measurement_quality
+                            .map(|q| format!(" quality={:?} (noise {:.2}%)", q.grade, q.noise_percent))
+                            .unwrap_or_default(),
+                        status
+                            .score
+                            .map(|s| format!(" score={:.2} ({:.2}/core)", s.score, s.score_per_core))
+                            .unwrap_or_default(),
+                        format_power(&status, &display),
+                        status
+                            .guard_trip
+                            .map(|trip| format!(" stopped by guard: {}", trip.reason))
+                            .unwrap_or_default(),
+                        status
+                            .scheduled_start_ms
+                            .map(|start_ms| format!(" armed: starts {}", display.timestamp(start_ms)))
+                            .unwrap_or_default()
+                    ),
+                    Err(e) => eprintln!("{}: {}", host, e),
+                }
+            }
+        }
+        ClientCommand::Histogram { target, display, json } => {
+            let mut merged = Histograms::default();
+            let mut agents = 0;
+            for host in &target.hosts {
+                let result = async {
+                    client
+                        .get(format!("http://{}/histograms", host))
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .json::<Histograms>()
+                        .await
+                }
+                .await;
+                match result.map_err(|e| e.to_string()).and_then(|h| merged.merge(&h)) {
+                    Ok(()) => agents += 1,
+                    Err(e) => eprintln!("{}: {}", host, e),
+                }
+            }
+
+            if json {
+                println!("{}", serde_json::to_string(&merged).expect("Failed to serialize histograms"));
+            } else {
+                println!("Merged histograms of {} agent(s):", agents);
+                let latency = format_sketch(&merged.batch_latency_ms, |ms| format!("{:.1}", ms));
+                println!("  batch latency (ms): {}", latency);
+                println!("  ops/sec:            {}", format_sketch(&merged.ops_per_second, |ops| display.number(ops)));
+                if merged.burst_ops_per_second.count > 0 {
+                    let bursts = format_sketch(&merged.burst_ops_per_second, |ops| display.number(ops));
+                    println!("  burst ops/sec:      {}", bursts);
+                }
+            }
+        }
+        ClientCommand::Peers { target, hosts_only, json } => {
+            // Agents on several LANs can be asked at once; an agent heard by several is listed once
+            let mut peers = std::collections::BTreeMap::<String, Peer>::new();
+            for host in &target.hosts {
+                let result = client.get(format!("http://{}/peers", host)).send().await;
+                match json_response::<Vec<Peer>>(result).await {
+                    Ok(found) => {
+                        for peer in found {
+                            let seen = peers.get(&peer.host).map_or(0, |known| known.last_seen_ms);
+                            if peer.last_seen_ms >= seen {
+                                peers.insert(peer.host.clone(), peer);
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("{}: {}", host, e),
+                }
+            }
+
+            if json {
+                let peers: Vec<&Peer> = peers.values().collect();
+                println!("{}", serde_json::to_string(&peers).expect("Failed to serialize peers"));
+            } else if hosts_only {
+                println!("{}", peers.keys().cloned().collect::<Vec<_>>().join(","));
+            } else {
+                let now_ms = unix_time_ms();
+                for peer in peers.values() {
+                    println!(
+                        "{:<24} {:<24} cores={:<4} version={} seen {}s ago",
+                        peer.host,
+                        peer.hostname,
+                        peer.cores,
+                        peer.version,
+                        now_ms.saturating_sub(peer.last_seen_ms) / 1000
+                    );
+                }
+            }
+        }
+        ClientCommand::Campaign {
+            campaign_dir,
+            display,
+            command,
+        } => {
+            let store = CampaignStore::new(&campaign_dir.unwrap_or_else(CampaignStore::default_dir));
+            if let Err(e) = run_campaign_command(&client, &store, &display, command).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        ClientCommand::Collect { port, dir } => serve_collector(&dir, port).await,
+        // Needs the agent flags; handled by the binary
+        ClientCommand::Package { .. } => unreachable!("package is handled before run_client"),
+        ClientCommand::Watch {
+            target,
+            display,
+            interval,
+        } => {
+            let mut ticker = tokio::time::interval(Duration::from_secs_f64(interval.max(0.1)));
+            loop {
+                ticker.tick().await;
+                let time = format_time_of_day(unix_time_ms(), display.time_zone);
+                for host in &target.hosts {
+                    match fetch_status(&client, host).await {
+                        Ok(status) if status.mode == ExecutionMode::Bursty => println!(
+                            "[{}] {}: {} ops/sec (burst: {} ops/sec)",
+                            time,
+                            host,
+                            display.ops(status.ops_per_second),
+                            display.ops(status.burst_ops_per_second)
+                        ),
+                        Ok(status) if status.workload == Workload::Stream => {
+                            println!("[{}] {}: {:.2} GB/s", time, host, status.memory_gb_per_second)
+                        }
+                        Ok(status) => println!("[{}] {}: {} ops/sec", time, host, display.ops(status.ops_per_second)),
+                        Err(e) => eprintln!("[{}] {}: {}", time, host, e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn run_campaign_command(
+    client: &reqwest::Client,
+    store: &CampaignStore,
+    display: &DisplayArgs,
+    command: CampaignCommand,
+) -> Result<(), String> {
+    let (name, action) = match command {
+        CampaignCommand::Start {
+            name,
+            target,
+            mut request,
+            sync,
+            phase_step_ms,
+            burst_schedule_file,
+        } => {
+            if let Some(path) = &burst_schedule_file {
+                request.options.burst_schedule = Some(read_burst_schedule(path));
+            }
+            // The schedule's epoch stays fixed, so agents are back in step after a resume
+            if sync {
+                request.options.schedule_seed.get_or_insert_with(rand::random);
+                request.options.schedule_epoch_ms.get_or_insert(unix_time_ms() + 2000);
+            }
+            arm_fleet(&mut request);
+            let campaign = Campaign::new(&name, target.hosts, request, sync.then_some(phase_step_ms));
+            store.create(&campaign)?;
+            (name, CampaignAction::Start)
+        }
+        CampaignCommand::Pause { name } => (name, CampaignAction::Pause),
+        CampaignCommand::Resume { name } => (name, CampaignAction::Resume),
+        CampaignCommand::Abort { name } => (name, CampaignAction::Abort),
+        CampaignCommand::Report { name, json } => {
+            let report = campaign_report(client, &store.load(&name)?).await;
+            if json {
+                println!("{}", serde_json::to_string(&report).expect("Failed to serialize campaign report"));
+            } else {
+                print_campaign_report(&report, display);
+            }
+            return Ok(());
+        }
+        CampaignCommand::List => {
+            let now_ms = unix_time_ms();
+            for campaign in store.list()? {
+                println!(
+                    "{:<24} {:<8} {} agent(s), mode={}, active {}",
+                    campaign.name,
+                    kebab_name(&campaign.state),
+                    campaign.hosts.len(),
+                    mode_name(campaign.request.mode),
+                    format_duration_secs(campaign.active_ms(now_ms) / 1000)
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    let mut campaign = store.load(&name)?;
+    campaign.next_state(action)?;
+    let failed_hosts = apply_campaign_action(client, &campaign, action).await;
+    if !failed_hosts.is_empty() {
+        eprintln!(
+            "{} of {} agent(s) failed; {} to retry them",
+            failed_hosts.len(),
+            campaign.hosts.len(),
+            if action == CampaignAction::Start { "resume the campaign" } else { "repeat the command" }
+        );
+    }
+    campaign.record(action, unix_time_ms(), failed_hosts)?;
+    store.save(&campaign)?;
+    println!("Campaign {} is {}", campaign.name, kebab_name(&campaign.state));
+    Ok(())
+}
+
+// Start or stop every agent of a campaign concurrently; returns the agents that failed
+async fn apply_campaign_action(client: &reqwest::Client, campaign: &Campaign, action: CampaignAction) -> Vec<String> {
+    let mut tasks = Vec::new();
+    for (i, host) in campaign.hosts.iter().enumerate() {
+        let builder = match action {
+            CampaignAction::Start | CampaignAction::Resume => client
+                .post(format!("http://{}/start-cpu", host))
+                .json(&campaign.request_for_host(i)),
+            CampaignAction::Pause | CampaignAction::Abort => client.post(format!("http://{}/end-cpu", host)),
+        };
+        let host = host.clone();
+        tasks.push(tokio::spawn(async move { (host, control_response(builder.send().await).await) }));
+    }
+
+    let mut failed_hosts = Vec::new();
+    for task in tasks {
+        let Ok((host, result)) = task.await else {
+            continue;
+        };
+        match result {
+            Ok(message) => println!("{}: {}", host, message),
+            Err(e) => {
+                eprintln!("{}: Error: {}", host, e);
+                failed_hosts.push(host);
+            }
+        }
+    }
+    failed_hosts
+}
+
+async fn campaign_report(client: &reqwest::Client, campaign: &Campaign) -> CampaignReport {
+    let mut agents = Vec::new();
+    let mut histograms = Histograms::default();
+    for host in &campaign.hosts {
+        let result = async {
+            let status = fetch_status(client, host).await?;
+            let agent_histograms = client
+                .get(format!("http://{}/histograms", host))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Histograms>()
+                .await?;
+            Ok::<_, reqwest::Error>((status, agent_histograms))
+        }
+        .await;
+        let (status, error) = match result.map_err(|e| e.to_string()) {
+            Ok((status, agent_histograms)) => match histograms.merge(&agent_histograms) {
+                Ok(()) => (Some(status), None),
+                Err(e) => (Some(status), Some(e)),
+            },
+            Err(e) => (None, Some(e)),
+        };
+        agents.push(AgentReport {
+            host: host.clone(),
+            status,
+            error,
+        });
+    }
+
+    let running: Vec<&MetricsSnapshot> = agents
+        .iter()
+        .filter_map(|agent| agent.status.as_ref())
+        .filter(|status| status.running)
+        .collect();
+    CampaignReport {
+        name: campaign.name.clone(),
+        state: campaign.state,
+        started_ms: campaign.started_ms(),
+        active_secs: campaign.active_ms(unix_time_ms()) / 1000,
+        pauses: campaign.pauses(),
+        agents_running: running.len(),
+        fleet_ops_per_second: running.iter().map(|status| status.ops_per_second).sum(),
+        fleet_watts: running
+            .iter()
+            .filter_map(|status| status.power.as_ref())
+            .map(|power| power.watts)
+            .reduce(|a, b| a + b),
+        agents,
+        histograms,
+    }
+}
+
+fn print_campaign_report(report: &CampaignReport, display: &DisplayArgs) {
+    println!(
+        "Campaign {}: {}, active {}{}",
+        report.name,
+        kebab_name(&report.state),
+        format_duration_secs(report.active_secs),
+        match report.pauses {
+            0 => String::new(),
+            pauses => format!(" (paused {} time(s))", pauses),
+        }
+    );
+    if let Some(started_ms) = report.started_ms {
+        println!(
+            "  started {} ({} ago)",
+            display.timestamp(started_ms),
+            format_duration_secs(unix_time_ms().saturating_sub(started_ms) / 1000)
+        );
+    }
+    println!(
+        "  agents running: {} of {}, fleet ops/sec: {}{}",
+        report.agents_running,
+        report.agents.len(),
+        display.ops(report.fleet_ops_per_second),
+        report.fleet_watts.map(|watts| format!(", fleet power: {:.1} W", watts)).unwrap_or_default()
+    );
+    for agent in &report.agents {
+        match (&agent.status, &agent.error) {
+            (Some(status), _) => println!(
+                "  {}: {} mode={} ops/sec={} system cpu={:.1}%{}",
+                agent.host,
+                if status.running { "RUNNING" } else { "STOPPED" },
+                mode_name(status.mode),
+                display.ops(status.ops_per_second),
+                status.cpu_utilization_percent,
+                format_power(status, display)
+            ),
+            (None, Some(e)) => println!("  {}: unreachable ({})", agent.host, e),
+            (None, None) => println!("  {}: unreachable", agent.host),
+        }
+    }
+    let latency = format_sketch(&report.histograms.batch_latency_ms, |ms| format!("{:.1}", ms));
+    println!("  batch latency (ms): {}", latency);
+    println!("  ops/sec per agent:  {}", format_sketch(&report.histograms.ops_per_second, |ops| display.number(ops)));
+    if report.histograms.burst_ops_per_second.count > 0 {
+        let bursts = format_sketch(&report.histograms.burst_ops_per_second, |ops| display.number(ops));
+        println!("  burst ops/sec:      {}", bursts);
+    }
+}
+
+// e.g. " power=84.2 W (run: 3.51k ops/J, 12.6k J)", or nothing without RAPL readings
+fn format_power(status: &MetricsSnapshot, display: &DisplayArgs) -> String {
+    let power = status.power.as_ref().map(|power| format!(" power={:.1} W", power.watts));
+    let energy = status.energy.map(|energy| {
+        format!(" (run: {} ops/J, {} J)", display.number(energy.ops_per_joule), display.number(energy.joules))
+    });
+    format!("{}{}", power.unwrap_or_default(), energy.unwrap_or_default())
+}
+
+// Serialized (kebab-case) name of a unit enum value, e.g. "running"
+fn kebab_name<T: serde::Serialize + std::fmt::Debug>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{:?}", value))
+}
+
+// e.g. 93784 as "1d 2h 3m 4s"
+fn format_duration_secs(secs: u64) -> String {
+    let (days, hours, minutes, secs) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m {}s", minutes, secs),
+        (0, _, _) => format!("{}h {}m {}s", hours, minutes, secs),
+        _ => format!("{}d {}h {}m {}s", days, hours, minutes, secs),
+    }
+}
+
+// Run percentiles for the status line, e.g. " (p50/p90/p99 251k/254k/256k)"; empty without samples
+fn format_percentiles(percentiles: &Percentiles, display: &DisplayArgs) -> String {
+    match (percentiles.p50, percentiles.p90, percentiles.p99) {
+        (Some(p50), Some(p90), Some(p99)) => format!(
+            " (p50/p90/p99 {}/{}/{})",
+            display.number(p50),
+            display.number(p90),
+            display.number(p99)
+        ),
+        _ => String::new(),
+    }
+}
+
+// Percentile summary of one histogram, e.g. "n=120 p50=15.2 p90=18.0 p99=25.1 p99.9=31.0 max=31.4"
+fn format_sketch(sketch: &Sketch, format_value: impl Fn(f64) -> String) -> String {
+    if sketch.count == 0 {
+        return "no samples".to_string();
+    }
+    let mut line = format!("n={}", sketch.count);
+    for (name, q) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("p99.9", 0.999)] {
+        line.push_str(&format!(" {}={}", name, format_value(sketch.quantile(q).unwrap_or(0.0))));
+    }
+    line.push_str(&format!(" max={}", format_value(sketch.max)));
+    line
+}
+
+async fn fetch_status(client: &reqwest::Client, host: &str) -> Result<MetricsSnapshot, reqwest::Error> {
+    client
+        .get(format!("http://{}/status", host))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<MetricsSnapshot>()
+        .await
+}
+
+// One line per phase of a benchmark report
+fn print_benchmark(host: &str, report: &BenchmarkReport, display: &DisplayArgs) {
+    println!(
+        "{}: {} phases of {} s, {} s cooldown",
+        host,
+        report.phases.len(),
+        report.duration_secs,
+        report.cooldown_secs
+    );
+    for phase in &report.phases {
+        let extra = match (phase.burst_ops_per_second, phase.memory_gb_per_second) {
+            (Some(burst), _) => format!(" burst ops/sec={}", display.number(burst)),
+            (_, Some(memory)) => format!(" memory={:.2} GB/s", memory),
+            _ => String::new(),
+        };
+        println!(
+            "  {:<13} {:<7} ops/sec={} ±{:.1}% (min {}, max {}) relative={:.2}{} core spread={:.1}%",
+            mode_name(phase.mode),
+            if phase.workload == Workload::Stream { "stream" } else { "primes" },
+            display.number(phase.ops_per_second),
+            phase.variation_percent,
+            display.ops(phase.min_ops_per_second),
+            display.ops(phase.max_ops_per_second),
+            phase.relative,
+            extra,
+            phase.core_spread_percent
+        );
+    }
+}
+
+// Body of a control API response, or its error (with the status code)
+async fn json_response<T: serde::de::DeserializeOwned>(
+    result: Result<reqwest::Response, reqwest::Error>,
+) -> Result<T, String> {
+    let response = result.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_success() {
+        response.json().await.map_err(|e| e.to_string())
+    } else {
+        match response.json::<ErrorResponse>().await {
+            Ok(body) => Err(format!("{} ({})", body.error, status)),
+            Err(_) => Err(format!("HTTP {}", status)),
+        }
+    }
+}
+
+// Message of a control API response, or its error
+async fn control_response(result: Result<reqwest::Response, reqwest::Error>) -> Result<String, String> {
+    json_response::<MessageResponse>(result).await.map(|body| body.message)
+}
+
+async fn print_text_response(host: &str, result: Result<reqwest::Response, reqwest::Error>) {
+    match control_response(result).await {
+        Ok(message) => println!("{}: {}", host, message),
+        Err(e) => eprintln!("{}: Error: {}", host, e),
+    }
+}
+
+// Hottest CPU sensor reading, if any
+fn max_temperature(temperatures: &[TemperatureReading]) -> Option<f32> {
+    temperatures.iter().map(|t| t.celsius).reduce(f32::max)
+}
@@ -0,0 +1,244 @@
+// CPU core sets and worker scheduling: parsing --cores lists, the SMT topology, pinning worker threads to
+// cores, and their nice level
+use std::path::Path;
+
+const CPU_SYSFS_DIR: &str = "/sys/devices/system/cpu";
+
+// CPU ids a core list may name: the size of a cpu_set_t, so a range is bounded before it is expanded
+#[cfg(target_os = "linux")]
+pub const CPU_ID_LIMIT: usize = libc::CPU_SETSIZE as usize;
+#[cfg(not(target_os = "linux"))]
+pub const CPU_ID_LIMIT: usize = 1024;
+
+// Parse a core list such as "0-3,8,10-11" into sorted, de-duplicated CPU ids
+pub fn parse_core_list(list: &str) -> Result<Vec<usize>, String> {
+    let mut cores = Vec::new();
+    for part in list.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        let parse = |s: &str| match s.trim().parse::<usize>() {
+            Ok(core) if core < CPU_ID_LIMIT => Ok(core),
+            Ok(core) => Err(format!("core id {} is out of range (below {})", core, CPU_ID_LIMIT)),
+            Err(_) => Err(format!("invalid core id \"{}\" in core list \"{}\"", s.trim(), list)),
+        };
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("invalid core range \"{}\" (start is after end)", part));
+                }
+                cores.extend(first..=last);
+            }
+            None => cores.push(parse(part)?),
+        }
+    }
+    if cores.is_empty() {
+        return Err("core list is empty".to_string());
+    }
+    cores.sort_unstable();
+    cores.dedup();
+    Ok(cores)
+}
+
+// Format CPU ids compactly, e.g. [0, 1, 2, 3, 8] as "0-3,8"
+pub fn format_core_list(cores: &[usize]) -> String {
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < cores.len() {
+        let start = cores[i];
+        while i + 1 < cores.len() && cores[i + 1] == cores[i] + 1 {
+            i += 1;
+        }
+        parts.push(if cores[i] == start {
+            start.to_string()
+        } else {
+            format!("{}-{}", start, cores[i])
+        });
+        i += 1;
+    }
+    parts.join(",")
+}
+
+// Which logical CPUs share a physical core (SMT siblings, e.g. Hyper-Threading), from sysfs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuTopology {
+    // Logical CPUs of each physical core, sorted; empty if the topology is unknown
+    cores: Vec<Vec<usize>>,
+}
+
+impl CpuTopology {
+    // Topology of the online CPUs of this host; unknown outside Linux
+    pub fn detect() -> Self {
+        CpuTopology::detect_in(Path::new(CPU_SYSFS_DIR))
+    }
+
+    // Topology from the logical CPUs of each physical core, e.g. [[0, 4], [1, 5]]
+    pub fn from_cores(mut cores: Vec<Vec<usize>>) -> Self {
+        for siblings in &mut cores {
+            siblings.sort_unstable();
+        }
+        cores.sort();
+        cores.dedup();
+        CpuTopology { cores }
+    }
+
+    // cpuN/topology/core_cpus_list of a sysfs CPU directory (thread_siblings_list before Linux 5.7)
+    fn detect_in(dir: &Path) -> Self {
+        let cores = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name().into_string().ok()?;
+                file_name.strip_prefix("cpu")?.parse::<usize>().ok()?;
+                let topology = entry.path().join("topology");
+                let list = std::fs::read_to_string(topology.join("core_cpus_list"))
+                    .or_else(|_| std::fs::read_to_string(topology.join("thread_siblings_list")))
+                    .ok()?;
+                parse_core_list(list.trim()).ok()
+            })
+            .collect();
+        CpuTopology::from_cores(cores)
+    }
+
+    pub fn is_known(&self) -> bool {
+        !self.cores.is_empty()
+    }
+
+    // Physical cores with more than one logical CPU
+    pub fn smt_siblings(&self) -> Vec<Vec<usize>> {
+        self.cores.iter().filter(|siblings| siblings.len() > 1).cloned().collect()
+    }
+
+    // One CPU per physical core of `cpus`: the first of each core's siblings among them. CPUs the topology
+    // doesn't know are kept.
+    pub fn physical_cores(&self, cpus: &[usize]) -> Vec<usize> {
+        let mut used = Vec::new();
+        cpus.iter()
+            .copied()
+            .filter(|cpu| match self.cores.iter().position(|siblings| siblings.contains(cpu)) {
+                Some(core) if used.contains(&core) => false,
+                Some(core) => {
+                    used.push(core);
+                    true
+                }
+                None => true,
+            })
+            .collect()
+    }
+}
+
+// CPU ids this process may run on
+#[cfg(target_os = "linux")]
+pub fn allowed_cores() -> Vec<usize> {
+    // SAFETY: cpu_set_t is plain data, and sched_getaffinity only writes within the given size
+    let set = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return (0..num_cpus::get()).collect();
+        }
+        set
+    };
+    (0..libc::CPU_SETSIZE as usize)
+        // SAFETY: the index is below CPU_SETSIZE
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cores() -> Vec<usize> {
+    (0..num_cpus::get()).collect()
+}
+
+// Restrict the calling thread (and processes it spawns afterwards) to one CPU
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpu: usize) -> Result<(), String> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(format!("core {} is out of range", cpu));
+    }
+    // SAFETY: cpu_set_t is plain data, CPU_SET is in range, and pid 0 only affects the calling thread
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result != 0 {
+        return Err(format!("failed to pin to core {}: {}", cpu, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(cpu: usize) -> Result<(), String> {
+    Err(format!("cannot pin to core {}: core pinning is only supported on Linux", cpu))
+}
+
+// Set the nice level of the calling thread; on Linux it applies per thread, and threads and processes
+// it spawns afterwards inherit it
+#[cfg(target_os = "linux")]
+pub fn set_current_thread_nice(nice: i32) -> Result<(), String> {
+    // SAFETY: setpriority only reads its arguments; the tid of the calling thread is always valid
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, nice) };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        return Err(match error.raw_os_error() {
+            Some(libc::EACCES | libc::EPERM) => {
+                format!("nice {} needs more privileges (CAP_SYS_NICE or RLIMIT_NICE) than the agent has", nice)
+            }
+            _ => format!("failed to set nice {}: {}", nice, error),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_current_thread_nice(nice: i32) -> Result<(), String> {
+    Err(format!("cannot set nice {}: per-worker nice levels are only supported on Linux", nice))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_lists_round_trip() {
+        assert_eq!(parse_core_list("0-3,8, 10-11,2").unwrap(), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(format_core_list(&[0, 1, 2, 3, 8, 10, 11]), "0-3,8,10-11");
+        assert_eq!(parse_core_list("5").unwrap(), vec![5]);
+        let huge = ["0-4000000000", "0-18446744073709551615", "4000000000"];
+        for invalid in ["", "3-1", "a", "1-b", ","].into_iter().chain(huge) {
+            assert!(parse_core_list(invalid).is_err(), "accepted {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn physical_cores_skip_smt_siblings() {
+        let dir = std::env::temp_dir().join(format!("cpu-topology-test-{}", std::process::id()));
+        for (cpu, siblings) in [(0, "0,2"), (1, "1,3"), (2, "0,2"), (3, "1,3"), (4, "4")] {
+            let topology = dir.join(format!("cpu{}/topology", cpu));
+            std::fs::create_dir_all(&topology).unwrap();
+            std::fs::write(topology.join("core_cpus_list"), format!("{}\n", siblings)).unwrap();
+        }
+        std::fs::create_dir_all(dir.join("cpufreq")).unwrap();
+        let topology = CpuTopology::detect_in(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(topology, CpuTopology::from_cores(vec![vec![2, 0], vec![1, 3], vec![4]]));
+        assert_eq!(topology.smt_siblings(), [[0, 2], [1, 3]]);
+        assert_eq!(topology.physical_cores(&[0, 1, 2, 3, 4, 7]), [0, 1, 4, 7]);
+        assert_eq!(topology.physical_cores(&[2, 3]), [2, 3]);
+        assert!(!CpuTopology::detect_in(Path::new("/nonexistent")).is_known());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn nice_applies_to_the_calling_thread_only() {
+        // SAFETY: getpriority only reads its arguments
+        let nice_of = |tid: libc::pid_t| unsafe { libc::getpriority(libc::PRIO_PROCESS, tid as libc::id_t) };
+        let own = nice_of(unsafe { libc::gettid() });
+        let worker = std::thread::spawn(move || {
+            set_current_thread_nice(19).unwrap();
+            nice_of(unsafe { libc::gettid() })
+        });
+        assert_eq!(worker.join().unwrap(), 19);
+        assert_eq!(nice_of(unsafe { libc::gettid() }), own);
+    }
+}
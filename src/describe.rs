@@ -0,0 +1,190 @@
+// Machine-readable capability description (--describe): what a given binary supports, so provisioning
+// tools can check it without starting a server. Endpoints come from the OpenAPI document and flags and
+// subcommands from the command line definition, so the description can't drift from either.
+use crate::engine::{BurstBackend, BurstDistribution, BurstSource, BurstTrigger, ExecutionMode, Workload};
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Description {
+    pub name: String,
+    pub version: &'static str,
+    pub modes: Vec<String>,
+    pub burst_distributions: Vec<String>,
+    pub burst_sources: Vec<String>,
+    pub burst_backends: Vec<String>,
+    pub burst_triggers: Vec<String>,
+    pub workloads: Vec<String>,
+    pub features: Features,
+    pub endpoints: Vec<Endpoint>,
+    // Agent flags, without the hidden internal ones
+    pub flags: Vec<Flag>,
+    // Client subcommands
+    pub subcommands: Vec<Subcommand>,
+}
+
+// Optional parts: compiled in (Cargo features) or only available on some platforms
+#[derive(Debug, Serialize)]
+pub struct Features {
+    // --grpc-port
+    pub grpc: bool,
+    // --cores and per-worker pinning
+    pub core_pinning: bool,
+    // --worker-cgroup
+    pub worker_cgroup: bool,
+    // temperatures in /status
+    pub temperature_sensors: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Endpoint {
+    pub method: String,
+    pub path: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Flag {
+    pub name: String,
+    // None for switches
+    pub value_name: Option<String>,
+    pub default: Option<String>,
+    // Accepted values, for flags with a fixed set
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    pub help: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Subcommand {
+    pub name: String,
+    pub about: String,
+}
+
+// Names of an enum's values as they appear in requests and on the command line
+fn value_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|value| value.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+fn endpoints() -> Vec<Endpoint> {
+    let mut endpoints = Vec::new();
+    for (path, item) in crate::http::openapi().paths.paths {
+        let operations = [
+            ("GET", item.get),
+            ("POST", item.post),
+            ("PUT", item.put),
+            ("PATCH", item.patch),
+            ("DELETE", item.delete),
+        ];
+        for (method, operation) in operations {
+            if let Some(operation) = operation {
+                endpoints.push(Endpoint {
+                    method: method.to_string(),
+                    path: path.clone(),
+                    description: operation.description.unwrap_or_default(),
+                });
+            }
+        }
+    }
+    endpoints.push(Endpoint {
+        method: "GET".to_string(),
+        path: "/openapi.json".to_string(),
+        description: "OpenAPI 3.1 description of this API".to_string(),
+    });
+    endpoints
+}
+
+// Describe the binary whose command line is `command` (e.g. `Args::command()`)
+pub fn describe(command: &clap::Command) -> Description {
+    let flags = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| {
+            let name = format!("--{}", arg.get_long()?);
+            let takes_value = arg.get_action().takes_values();
+            // Without an explicit value name, clap shows the upper-cased argument id
+            let value_name = takes_value.then(|| match arg.get_value_names() {
+                Some(names) => names.iter().map(|name| name.to_string()).collect::<Vec<_>>().join(" "),
+                None => arg.get_id().as_str().to_uppercase(),
+            });
+            let defaults: Vec<String> = arg
+                .get_default_values()
+                .iter()
+                .map(|value| value.to_string_lossy().into_owned())
+                .collect();
+            Some(Flag {
+                name,
+                value_name,
+                default: (takes_value && !defaults.is_empty()).then(|| defaults.join(",")),
+                values: (if takes_value { arg.get_possible_values() } else { Vec::new() })
+                    .iter()
+                    .filter(|value| !value.is_hide_set())
+                    .map(|value| value.get_name().to_string())
+                    .collect(),
+                help: arg.get_help().map(|help| help.to_string()).unwrap_or_default(),
+            })
+        })
+        .collect();
+    let subcommands = command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+        .map(|subcommand| Subcommand {
+            name: subcommand.get_name().to_string(),
+            about: subcommand.get_about().map(|about| about.to_string()).unwrap_or_default(),
+        })
+        .collect();
+
+    Description {
+        name: command.get_name().to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        modes: value_names::<ExecutionMode>(),
+        burst_distributions: value_names::<BurstDistribution>(),
+        burst_sources: value_names::<BurstSource>(),
+        burst_backends: value_names::<BurstBackend>(),
+        burst_triggers: value_names::<BurstTrigger>(),
+        workloads: value_names::<Workload>(),
+        features: Features {
+            grpc: cfg!(feature = "grpc"),
+            core_pinning: cfg!(target_os = "linux"),
+            worker_cgroup: cfg!(target_os = "linux"),
+            temperature_sensors: cfg!(target_os = "linux"),
+        },
+        endpoints: endpoints(),
+        flags,
+        subcommands,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description_lists_modes_endpoints_and_flags() {
+        let command = clap::Command::new("agent")
+            .arg(clap::arg!(--port <PORT> "HTTP port").default_value("8080"))
+            .arg(clap::arg!(--simulate "Synthetic metrics"))
+            .arg(clap::arg!(--worker "Internal").hide(true))
+            .subcommand(clap::Command::new("status").about("Show status"));
+        let description = describe(&command);
+
+        assert_eq!(description.modes, ["threaded", "fresh-process", "bursty", "throttled"]);
+        let start = description.endpoints.iter().find(|e| e.path == "/start-cpu").unwrap();
+        assert_eq!(start.method, "POST");
+        assert!(description.endpoints.iter().any(|e| e.method == "DELETE" && e.path == "/jobs/{id}"));
+
+        let json = serde_json::to_value(&description).unwrap();
+        assert_eq!(
+            json["flags"],
+            serde_json::json!([
+                {"name": "--port", "value_name": "PORT", "default": "8080", "help": "HTTP port"},
+                {"name": "--simulate", "value_name": null, "default": null, "help": "Synthetic metrics"}
+            ])
+        );
+        assert_eq!(json["subcommands"], serde_json::json!([{"name": "status", "about": "Show status"}]));
+    }
+}
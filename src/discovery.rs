@@ -0,0 +1,198 @@
+// LAN peer discovery (--discovery): every agent broadcasts a small UDP announcement every few seconds and
+// listens for the others', so GET /peers (and the `peers` client subcommand) can list the agents on the
+// network instead of a hand-maintained inventory. Broadcasts don't cross routers, so this finds the agents
+// on the local subnet (or the one given with --discovery-broadcast).
+use crate::engine::unix_time_ms;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_DISCOVERY_PORT: u16 = 48080;
+// Marks announcements of this tool; other datagrams on the port are ignored
+const SERVICE: &str = "distributed-cpu-stress-reporter";
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(5);
+// A peer is forgotten after this long without an announcement (three missed ones)
+const PEER_TIMEOUT_MS: u64 = 20_000;
+
+// The UDP datagram every agent broadcasts (JSON)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub service: String,
+    pub version: String,
+    pub hostname: String,
+    // HTTP port of the agent
+    pub port: u16,
+    // Workers per run
+    pub cores: usize,
+}
+
+impl Announcement {
+    pub fn new(hostname: &str, port: u16, cores: usize) -> Self {
+        Announcement {
+            service: SERVICE.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            hostname: hostname.to_string(),
+            port,
+            cores,
+        }
+    }
+}
+
+// One entry of GET /peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Peer {
+    // Address the announcement came from with the announced HTTP port; usable as a client --host
+    pub host: String,
+    pub hostname: String,
+    pub port: u16,
+    pub cores: usize,
+    pub version: String,
+    // When the latest announcement arrived (Unix ms)
+    pub last_seen_ms: u64,
+}
+
+// Agents heard from recently, by host
+#[derive(Debug, Default)]
+pub struct PeerTable {
+    peers: Mutex<BTreeMap<String, Peer>>,
+}
+
+impl PeerTable {
+    // Record a datagram received from `from`; returns false if it isn't an announcement
+    pub(crate) fn receive(&self, datagram: &[u8], from: SocketAddr, now_ms: u64) -> bool {
+        let Ok(announcement) = serde_json::from_slice::<Announcement>(datagram) else {
+            return false;
+        };
+        if announcement.service != SERVICE {
+            return false;
+        }
+        let host = SocketAddr::new(from.ip().to_canonical(), announcement.port).to_string();
+        let peer = Peer {
+            host: host.clone(),
+            hostname: announcement.hostname,
+            port: announcement.port,
+            cores: announcement.cores,
+            version: announcement.version,
+            last_seen_ms: now_ms,
+        };
+        self.peers.lock().unwrap().insert(host, peer);
+        true
+    }
+
+    // Peers heard from in the last PEER_TIMEOUT_MS, by host; the others are dropped
+    pub fn peers(&self, now_ms: u64) -> Vec<Peer> {
+        let mut peers = self.peers.lock().unwrap();
+        peers.retain(|_, peer| now_ms.saturating_sub(peer.last_seen_ms) < PEER_TIMEOUT_MS);
+        peers.values().cloned().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    // UDP port the agents announce on and listen to
+    pub port: u16,
+    // Where announcements are sent: the limited broadcast address, or a subnet's directed broadcast
+    pub broadcast: Ipv4Addr,
+    pub announcement: Announcement,
+}
+
+// Bind the discovery port and announce this agent and record the others' announcements in `peers` until
+// the process exits. Needs a Tokio runtime.
+pub fn spawn_discovery(peers: Arc<PeerTable>, options: DiscoveryOptions) -> std::io::Result<()> {
+    let socket = Arc::new(tokio::net::UdpSocket::from_std(bind(options.port)?)?);
+    let datagram = serde_json::to_vec(&options.announcement).expect("Failed to serialize announcement");
+    let target = SocketAddrV4::new(options.broadcast, options.port);
+
+    let sender = Arc::clone(&socket);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ANNOUNCE_INTERVAL);
+        let mut failing = false;
+        loop {
+            interval.tick().await;
+            // Log the first failure of a streak only (e.g. while the network is down)
+            match sender.send_to(&datagram, target).await {
+                Ok(_) => failing = false,
+                Err(e) if !failing => {
+                    tracing::warn!(%target, error = %e, "failed to send discovery announcement");
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut buffer = [0; 2048];
+        loop {
+            match socket.recv_from(&mut buffer).await {
+                Ok((len, from)) => {
+                    if !peers.receive(&buffer[..len], from, unix_time_ms()) {
+                        tracing::debug!(%from, "ignoring a datagram that isn't a discovery announcement");
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "failed to receive discovery announcements");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+// Several agents on one host (--cores instances) listen on the same port, so it is bound with address
+// (and port) reuse; each of them still receives every broadcast.
+fn bind(port: u16) -> std::io::Result<std::net::UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.set_broadcast(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_come_from_announcements_and_expire() {
+        let table = PeerTable::default();
+        let from: SocketAddr = "10.0.0.7:48080".parse().unwrap();
+        let announcement = serde_json::to_vec(&Announcement::new("rack1-node7", 8081, 16)).unwrap();
+        assert!(table.receive(&announcement, from, 1_000));
+        assert!(!table.receive(b"hello", from, 1_000));
+        assert!(!table.receive(br#"{"service":"other","version":"1","hostname":"x","port":1,"cores":1}"#, from, 1_000));
+
+        let peers = table.peers(5_000);
+        assert_eq!(peers.len(), 1);
+        assert_eq!((peers[0].host.as_str(), peers[0].hostname.as_str()), ("10.0.0.7:8081", "rack1-node7"));
+        assert_eq!(peers[0].cores, 16);
+        assert!(table.peers(1_000 + PEER_TIMEOUT_MS).is_empty());
+    }
+
+    #[tokio::test]
+    async fn agents_hear_their_own_announcements() {
+        let peers = Arc::new(PeerTable::default());
+        let options = DiscoveryOptions {
+            port: 48_917,
+            broadcast: Ipv4Addr::LOCALHOST,
+            announcement: Announcement::new("self", 8080, 4),
+        };
+        spawn_discovery(Arc::clone(&peers), options).unwrap();
+        for _ in 0..50 {
+            if let [peer] = &peers.peers(unix_time_ms())[..] {
+                assert_eq!(peer.host, "127.0.0.1:8080");
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("no announcement received");
+    }
+}
@@ -0,0 +1,3219 @@
+// Control plane: run settings, per-run worker lifecycle, and the state shared with the APIs
+use crate::benchmark::{BenchmarkPhase, BenchmarkReport, BenchmarkRequest};
+use crate::cgroup::{CpuAllocation, WorkerCgroup};
+use crate::cores::{
+    CpuTopology, allowed_cores, format_core_list, parse_core_list, pin_current_thread, set_current_thread_nice,
+};
+use crate::discovery::{Peer, PeerTable};
+use crate::harness::{HARNESS_OPS_PER_SEC, TestHarness};
+use crate::http::ControlRecorder;
+use crate::metrics::{
+    Baseline, BurstEvent, CoreLatency, CpuUtilization, GuardTrip, GuardWindow, Histograms, HistorySample,
+    LatencyReport, LifecycleEvent, MeasurementQuality, MetricsSnapshot, NormalizedScore, OpsDropDetector,
+    RunPercentiles, TemperatureReading,
+    burst_sampler, emit_lifecycle_event, latency_probe, measure_baseline, measure_noise_floor, mode_name,
+    textfile_writer,
+};
+use crate::persist::{SavedJob, SavedSchedule, SavedState, StateFile};
+use crate::power::{EnergyTotals, PowerReading};
+use crate::process_stats::{ProcessStats, ProcessStatsReport};
+use crate::schedule::{Schedule, ScheduleAction, ScheduleInfo, ScheduleRequest};
+use crate::units::{format_rfc3339, parse_rfc3339};
+use crate::workloads::{
+    BurstConfig, FleetSync, burst_coordinator, cpu_worker, process_spawner, simulated_worker, stream_worker,
+    throttled_worker,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExecutionMode {
+    Threaded,
+    FreshProcess,
+    Bursty,
+    Throttled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BurstDistribution {
+    Exponential,
+    Uniform,
+    Fixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BurstSource {
+    // Independent random draws from the burst distribution
+    Random,
+    // Base-2 van der Corput sequence mapped through the burst distribution, covering its range evenly
+    LowDiscrepancy,
+    // The durations given in burst_schedule, in order, repeating
+    Schedule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BurstBackend {
+    // Every batch of a burst is a fresh worker process (includes process creation cost)
+    Process,
+    // The core's long-lived coordinator thread computes the batches itself (warm worker)
+    Thread,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Workload {
+    // Count primes by trial division: CPU-bound and barely touches memory, measured in ops/sec
+    #[default]
+    Primes,
+    // STREAM-style copy, scale, add, and triad passes over large per-worker arrays, measured in GB/s
+    Stream,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BurstTrigger {
+    // Bursts follow the random (or fleet-aligned) schedule
+    Internal,
+    // Bursts only happen when triggered via POST /trigger-burst or a trigger input
+    External,
+}
+
+// Body of POST /start-cpu; also the options of the `start` client subcommand
+#[derive(Debug, Clone, Serialize, Deserialize, clap::Args, utoipa::ToSchema)]
+pub struct StartCpuRequest {
+    /// Execution mode
+    #[arg(long, value_enum)]
+    pub mode: ExecutionMode,
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: RunOptions,
+}
+
+// Optional settings of a run; anything left out uses the defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize, clap::Args, utoipa::ToSchema)]
+pub struct RunOptions {
+    /// Target utilization percentage (bursty and throttled modes)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utilization: Option<u64>,
+    /// Burst duration distribution (bursty mode)
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_distribution: Option<BurstDistribution>,
+    /// Mean (or fixed) burst duration in seconds (bursty mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_mean_secs: Option<f64>,
+    /// Minimum burst duration in seconds (bursty mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_min_secs: Option<f64>,
+    /// Maximum burst duration in seconds (bursty mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_max_secs: Option<f64>,
+    /// Where burst durations come from (bursty mode; default "schedule" if burst_schedule is given, else "random")
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_source: Option<BurstSource>,
+    /// Burst durations in seconds, played in order and repeated (bursty mode, burst_source "schedule")
+    #[arg(long, value_delimiter = ',', num_args = 1..)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_schedule: Option<Vec<f64>>,
+    /// Run bursts as fresh processes or on pre-created threads (bursty mode)
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_backend: Option<BurstBackend>,
+    /// Operations per worker process, and the most per burst batch (fresh-process and bursty modes;
+    /// default: the agent's worker_ops, see PATCH /config)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker_ops: Option<u64>,
+    /// Compute/sleep cycle length in milliseconds (throttled mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duty_cycle_ms: Option<u64>,
+    /// Seed of a shared burst schedule; agents with the same seed and epoch burst together (bursty mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_seed: Option<u64>,
+    /// Unix time in milliseconds at which the shared burst schedule begins (bursty mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_epoch_ms: Option<u64>,
+    /// Delay this agent's copy of the shared burst schedule by this many milliseconds (bursty mode)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase_offset_ms: Option<u64>,
+    /// What starts a burst: the internal schedule or external triggers (bursty mode)
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_trigger: Option<BurstTrigger>,
+    /// Soft start: bring the workers up one after another over this many seconds instead of all at once
+    /// (default: the agent's --ramp-secs)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ramp_secs: Option<f64>,
+    /// Nice level of the worker threads and the processes they spawn, from -20 (highest priority; needs
+    /// CAP_SYS_NICE) to 19 (yields to everything else) (default: the agent's own)
+    #[arg(long, allow_hyphen_values = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// Measure scheduling latency under the load: one probe thread per worker core sleeps this many
+    /// microseconds at a time (10 to 100000) and records how late it wakes up (GET /latency)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_probe_us: Option<u64>,
+    /// What the workers compute: primes (ops/sec) or stream, a memory-bandwidth kernel reported in GB/s
+    /// (stream: threaded and throttled modes)
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workload: Option<Workload>,
+    /// Size in MiB of each of the three arrays every stream worker allocates (1 to 1024, default 32)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_array_mb: Option<u64>,
+    /// Auto-stop guard: stop the run when its ops/sec, averaged over guard_window_secs, falls below this
+    /// (bursty mode: the burst-only rate)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_if_ops_below: Option<u64>,
+    /// Auto-stop guard: stop the run when its ops/sec, averaged over guard_window_secs, falls more than this
+    /// many percent below the highest such average of the run (e.g. thermal throttling or a wedged worker)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_if_drop_percent_exceeds: Option<f64>,
+    /// Rolling window of the auto-stop guards in seconds (1 to 3600, default 10)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guard_window_secs: Option<u64>,
+    /// Arm the run and start it at this time (RFC 3339, e.g. 2025-10-09T14:03:21Z), so agents armed one by one
+    /// all start at the same instant
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<String>,
+    /// Arm the run and start it this many milliseconds after the request (at most 86400000)
+    #[arg(long, conflicts_with = "start_at")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_delay_ms: Option<u64>,
+    /// Run one worker per physical core: leave the SMT siblings (hyperthreads) of the worker cores idle,
+    /// so co-scheduled siblings don't skew the ops/sec
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub use_physical_cores_only: bool,
+}
+
+// Body of PATCH /config: agent settings that can change without a restart; anything left out is kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, clap::Args, utoipa::ToSchema)]
+pub struct ConfigUpdate {
+    /// Operations per worker process for runs that don't set their own (also applied to the current run)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker_ops: Option<u64>,
+}
+
+// Body of POST /jobs: a run on a subset of cores that can run alongside other jobs
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JobRequest {
+    // Defaults to "job-<id>"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // CPU ids to run on, e.g. "0-3,8"; one worker per core, pinned to it
+    pub cores: String,
+    // Stop the job after this long (default: run until DELETE /jobs/{id})
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    #[serde(flatten)]
+    pub start: StartCpuRequest,
+}
+
+// Body of POST /mixed: several modes at once, each on its own share of the agent's cores (e.g. steady
+// threaded load plus bursty traffic). Every component becomes a job, with its own metrics in GET /jobs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MixedRequest {
+    // Prefix of the jobs' names ("<name>-<mode>"); defaults to "mixed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // Stop every component after this long (default: run until DELETE /jobs/{id})
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threaded: Option<MixedComponent>,
+    #[serde(rename = "fresh-process", skip_serializing_if = "Option::is_none")]
+    pub fresh_process: Option<MixedComponent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bursty: Option<MixedComponent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttled: Option<MixedComponent>,
+}
+
+impl MixedRequest {
+    fn components(&self) -> Vec<(ExecutionMode, &MixedComponent)> {
+        [
+            (ExecutionMode::Threaded, &self.threaded),
+            (ExecutionMode::FreshProcess, &self.fresh_process),
+            (ExecutionMode::Bursty, &self.bursty),
+            (ExecutionMode::Throttled, &self.throttled),
+        ]
+        .into_iter()
+        .filter_map(|(mode, component)| Some((mode, component.as_ref()?)))
+        .collect()
+    }
+}
+
+// One mode of a mixed run: how many cores it gets, and its options as in POST /start-cpu
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MixedComponent {
+    pub cores: usize,
+    #[serde(flatten)]
+    pub options: RunOptions,
+}
+
+// One entry of GET /jobs
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JobInfo {
+    pub id: u64,
+    pub name: String,
+    pub mode: ExecutionMode,
+    pub cores: Vec<usize>,
+    pub started_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    // Operations completed by the job so far, and their average rate
+    pub ops: u64,
+    pub ops_per_second: u64,
+}
+
+// A component of a mixed run, checked and ready to start as a job
+type MixedJob = (JobRequest, RunConfig, Vec<usize>);
+
+// A running job: its own settings channel and workers, on its own cores
+pub(crate) struct Job {
+    id: u64,
+    name: String,
+    mode: ExecutionMode,
+    cores: Vec<usize>,
+    started_at: Instant,
+    started_ms: u64,
+    duration_secs: Option<f64>,
+    config: watch::Sender<RunConfig>,
+    run: ActiveRun,
+}
+
+impl Job {
+    fn info(&self) -> JobInfo {
+        let ops = self.run.signal.ops();
+        JobInfo {
+            id: self.id,
+            name: self.name.clone(),
+            mode: self.mode,
+            cores: self.cores.clone(),
+            started_ms: self.started_ms,
+            duration_secs: self.duration_secs,
+            ops,
+            ops_per_second: (ops as f64 / self.started_at.elapsed().as_secs_f64().max(0.001)) as u64,
+        }
+    }
+}
+
+// Default operations per worker process (--worker-ops)
+pub const DEFAULT_WORKER_OPS: u64 = 20_000;
+
+// Largest worker_ops accepted; a single worker process of this size already takes tens of seconds
+pub const MAX_WORKER_OPS: u64 = 10_000_000;
+
+// Per-worker array size of the stream workload; three arrays this size keep it well out of the caches
+pub const DEFAULT_STREAM_ARRAY_MB: u64 = 32;
+pub const STREAM_ARRAY_MB_RANGE: std::ops::RangeInclusive<u64> = 1..=1024;
+
+// Rolling window of the auto-stop guards, and the range a request may ask for
+pub const DEFAULT_GUARD_WINDOW_SECS: u64 = 10;
+pub const GUARD_WINDOW_SECS_RANGE: std::ops::RangeInclusive<u64> = 1..=3600;
+
+// How far ahead a run can be armed (start_at, start_delay_ms): 24 hours
+pub const MAX_START_DELAY_MS: u64 = 86_400_000;
+
+// How far from now a shared burst schedule may begin (schedule_epoch_ms + phase_offset_ms): 30 days
+pub const MAX_SCHEDULE_EPOCH_DRIFT_MS: u64 = 30 * 86_400_000;
+
+// Default for --webhook-ops-drop-percent
+pub const DEFAULT_OPS_DROP_ALERT_PERCENT: f64 = 20.0;
+
+// Length of a POST /calibrate measurement, and the range a request may ask for
+pub const DEFAULT_CALIBRATION_MS: u64 = 2_000;
+pub const CALIBRATION_MS_RANGE: std::ops::RangeInclusive<u64> = 100..=60_000;
+
+// One problem with one field of a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FieldError {
+    // Name of the field in the request body, e.g. "utilization"
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+// Why a control request was refused; the HTTP API maps these to 400, 409, and 404
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineError {
+    // The request itself is invalid (bad option values)
+    Invalid(String),
+    // A start or job request with invalid fields: every problem found, not just the first
+    Validation(Vec<FieldError>),
+    // The request is valid but conflicts with the current state (e.g. jobs occupy the cores)
+    Conflict(String),
+    // The request refers to something that doesn't exist (e.g. a stopped job)
+    NotFound(String),
+}
+
+impl EngineError {
+    pub fn message(&self) -> String {
+        match self {
+            EngineError::Invalid(message) | EngineError::Conflict(message) | EngineError::NotFound(message) => {
+                message.clone()
+            }
+            EngineError::Validation(errors) => {
+                errors.iter().map(|error| error.message.as_str()).collect::<Vec<_>>().join("; ")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message())
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+fn validate_worker_ops(worker_ops: u64) -> Result<u64, String> {
+    if !(1..=MAX_WORKER_OPS).contains(&worker_ops) {
+        return Err(format!("worker_ops must be between 1 and {}", MAX_WORKER_OPS));
+    }
+    Ok(worker_ops)
+}
+
+// Shared state of the stress engine: run control, workers, and the metrics they feed
+pub struct StressEngine {
+    pub(crate) operations_per_second: AtomicU64,
+    pub(crate) current_counter: Arc<AtomicU64>,
+    // Bytes read and written by stream workers: this sampling interval's count, and the last interval's
+    pub(crate) memory_counter: AtomicU64,
+    pub(crate) memory_bytes_per_second: AtomicU64,
+    pub(crate) is_running: AtomicBool,
+    // Settings of the current (or most recent) run; workers read them as they change
+    pub(crate) config: watch::Sender<RunConfig>,
+    // Worker threads of the current run (None while stopped)
+    pub(crate) run: Mutex<Option<ActiveRun>>,
+    // Jobs (POST /jobs) running on core subsets; never alongside the run above
+    pub(crate) jobs: Mutex<Vec<Job>>,
+    pub(crate) next_job_id: AtomicU64,
+    pub(crate) num_cores: usize,
+    // Operations per worker process for runs that don't set their own (changed by PATCH /config)
+    pub(crate) worker_ops: AtomicU64,
+    // Resource caps for worker processes (--worker-cgroup)
+    pub(crate) worker_cgroup: Option<WorkerCgroup>,
+    // Core set of this instance (--cores); worker N is pinned to cores[N]
+    pub(crate) cores: Option<Vec<usize>>,
+    // CPUs detected for this process (cpuset and cgroup quota)
+    pub(crate) cpu_allocation: Option<CpuAllocation>,
+    // SMT siblings of the host's CPUs, for runs on physical cores only
+    pub(crate) topology: CpuTopology,
+    // Per-core ops/sec baseline of a simulated agent (--simulate); None runs real workers
+    pub(crate) simulated_ops_per_sec: Option<f64>,
+    // Virtual-time engine that replaces the worker and sampler threads (--test-harness)
+    pub(crate) harness: Option<TestHarness>,
+    // Burst-specific metrics
+    pub(crate) burst_operations: Arc<AtomicU64>,
+    pub(crate) burst_time_ms: Arc<AtomicU64>,
+    pub(crate) burst_ops_per_second: AtomicU64,
+    // Control API recording (--record)
+    pub(crate) control_recorder: Option<ControlRecorder>,
+    // Measured system CPU utilization
+    pub(crate) cpu_utilization: Mutex<CpuUtilization>,
+    // Latest CPU temperatures
+    pub(crate) temperatures: Mutex<Vec<TemperatureReading>>,
+    // Noise floor of the environment, measured before each run
+    pub(crate) measurement_quality: Mutex<Option<MeasurementQuality>>,
+    // Soft start of runs that don't set their own ramp_secs (--ramp-secs)
+    pub(crate) default_ramp: Duration,
+    // Agents found by LAN discovery (--discovery)
+    pub(crate) peers: Option<Arc<PeerTable>>,
+    // Single-core reference throughput (POST /calibrate); run metrics are also reported relative to it
+    pub(crate) baseline: Mutex<Option<Baseline>>,
+    // Per-second samples for GET /history
+    pub(crate) history: Mutex<std::collections::VecDeque<HistorySample>>,
+    pub(crate) history_capacity: usize,
+    // Batch latency and per-second ops histograms of the current run
+    pub(crate) histograms: Mutex<Histograms>,
+    // Spawn latency, CPU time, and memory of the worker processes of the current run and the jobs
+    pub(crate) process_stats: Mutex<ProcessStats>,
+    // Wakeup lateness recorded by the latency probes, by core id
+    pub(crate) latency: Mutex<std::collections::BTreeMap<usize, CoreLatency>>,
+    // Per-burst start/end events
+    pub(crate) burst_events: tokio::sync::broadcast::Sender<BurstEvent>,
+    pub(crate) log_burst_events: bool,
+    // Each sampling interval's status, as appended to the history (GET /events)
+    pub(crate) sample_events: tokio::sync::broadcast::Sender<HistorySample>,
+    // Run/job lifecycle and threshold events (--webhook)
+    pub(crate) lifecycle_events: tokio::sync::broadcast::Sender<LifecycleEvent>,
+    // Percentage below the run's peak ops/sec that raises an ops-drop event; None disables the check
+    pub(crate) ops_drop_alert_percent: Option<f64>,
+    pub(crate) ops_drop_detector: Mutex<OpsDropDetector>,
+    // Samples seen by the current run's auto-stop guards, and the guard that stopped the current (or most
+    // recent) run, if any
+    pub(crate) guard_window: Mutex<GuardWindow>,
+    pub(crate) guard_trip: Mutex<Option<GuardTrip>>,
+    // Package power of the last sampling interval, and the energy of the current (or most recent) run
+    pub(crate) power: Mutex<Option<PowerReading>>,
+    pub(crate) energy: Mutex<EnergyTotals>,
+    // Run armed to start at a set time, if any
+    pub(crate) pending_start: Mutex<Option<PendingStart>>,
+    // Stress windows (POST /schedules), checked every second by the scheduler thread once there is one
+    pub(crate) schedules: Mutex<Vec<Schedule>>,
+    pub(crate) next_schedule_id: AtomicU64,
+    pub(crate) scheduler_started: AtomicBool,
+    // Signal of the benchmark (POST /benchmark) running its phases, if any; POST /end-cpu stops it
+    pub(crate) benchmark: Mutex<Option<Arc<RunSignal>>>,
+    // What to resume after a restart (--state-file), written on every change; taken after the other locks
+    pub(crate) state_file: Option<StateFile>,
+    pub(crate) saved_state: Mutex<SavedState>,
+    // Background thread liveness, and how often panicked threads were restarted (also of threads that have
+    // exited since, e.g. the workers of earlier runs)
+    pub(crate) started_at: Instant,
+    pub(crate) heartbeats: Mutex<Vec<Arc<Heartbeat>>>,
+    pub(crate) thread_restarts: AtomicU64,
+}
+
+// Settings of a run, built from a start request
+#[derive(Debug, Clone)]
+pub(crate) struct RunConfig {
+    pub(crate) mode: ExecutionMode,
+    // Bursty and throttled modes
+    pub(crate) utilization: u64,
+    pub(crate) burst_config: BurstConfig,
+    pub(crate) burst_backend: BurstBackend,
+    // Fresh-process and bursty modes: operations per worker process (the cap per burst batch)
+    pub(crate) worker_ops: u64,
+    // Throttled mode duty cycle window
+    pub(crate) duty_cycle_ms: u64,
+    // Bursty mode fleet-aligned schedule
+    pub(crate) fleet_sync: Option<FleetSync>,
+    pub(crate) burst_trigger: BurstTrigger,
+    // Soft start: workers of a new run start spread over this interval
+    pub(crate) ramp: Duration,
+    // Nice level of the workers; None keeps the agent's
+    pub(crate) nice: Option<i32>,
+    // Sleep interval of the wakeup latency probes; None runs without probes
+    pub(crate) latency_probe: Option<Duration>,
+    pub(crate) workload: Workload,
+    // Stream workload: size of each of a worker's three arrays
+    pub(crate) stream_array_mb: u64,
+    pub(crate) guards: RunGuards,
+    // When an armed run starts (start_at, start_delay_ms); None starts it right away
+    pub(crate) start: Option<ScheduledStart>,
+    // One worker per physical core (use_physical_cores_only)
+    pub(crate) physical_cores_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ScheduledStart {
+    // Unix ms
+    At(u64),
+    // After the request
+    After(Duration),
+}
+
+// A run armed to start at start_ms (Unix ms, or the test harness's virtual clock)
+pub(crate) struct PendingStart {
+    start_ms: u64,
+    config: RunConfig,
+    // Saved to the state file once it starts
+    request: StartCpuRequest,
+    // Stopped when the start is cancelled or replaced; wakes the timer thread
+    signal: Arc<RunSignal>,
+}
+
+// Auto-stop guards of a run, judged on its ops/sec averaged over the last window_secs samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RunGuards {
+    pub(crate) ops_floor: Option<u64>,
+    pub(crate) max_drop_percent: Option<f64>,
+    pub(crate) window_secs: u64,
+}
+
+impl Default for RunGuards {
+    fn default() -> Self {
+        RunGuards {
+            ops_floor: None,
+            max_drop_percent: None,
+            window_secs: DEFAULT_GUARD_WINDOW_SECS,
+        }
+    }
+}
+
+impl RunGuards {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ops_floor.is_none() && self.max_drop_percent.is_none()
+    }
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            mode: ExecutionMode::FreshProcess,
+            utilization: 50,
+            burst_config: BurstConfig::default(),
+            burst_backend: BurstBackend::Process,
+            worker_ops: DEFAULT_WORKER_OPS,
+            duty_cycle_ms: 100,
+            fleet_sync: None,
+            burst_trigger: BurstTrigger::Internal,
+            ramp: Duration::ZERO,
+            nice: None,
+            latency_probe: None,
+            workload: Workload::Primes,
+            stream_array_mb: DEFAULT_STREAM_ARRAY_MB,
+            guards: RunGuards::default(),
+            start: None,
+            physical_cores_only: false,
+        }
+    }
+}
+
+impl RunConfig {
+    // Validate a start request and fill in defaults for the options it leaves out. Every invalid option
+    // is reported, not just the first.
+    fn from_request(request: &StartCpuRequest, default_worker_ops: u64) -> Result<Self, Vec<FieldError>> {
+        let options = &request.options;
+        let mut errors = Vec::new();
+        let mut config = RunConfig {
+            mode: request.mode,
+            physical_cores_only: options.use_physical_cores_only,
+            ..RunConfig::default()
+        };
+
+        match validate_worker_ops(options.worker_ops.unwrap_or(default_worker_ops)) {
+            Ok(worker_ops) => config.worker_ops = worker_ops,
+            Err(e) => errors.push(FieldError::new("worker_ops", e)),
+        }
+
+        if request.mode == ExecutionMode::Bursty {
+            match BurstConfig::from_options(options) {
+                Ok(burst_config) => config.burst_config = burst_config,
+                Err(burst_errors) => errors.extend(burst_errors),
+            }
+            config.burst_backend = options.burst_backend.unwrap_or(BurstBackend::Process);
+            config.fleet_sync = match (options.schedule_seed, options.schedule_epoch_ms) {
+                (Some(seed), Some(epoch_ms)) => {
+                    let phase_offset_ms = options.phase_offset_ms.unwrap_or(0);
+                    let now_ms = unix_time_ms();
+                    match epoch_ms.checked_add(phase_offset_ms) {
+                        Some(start_ms) if start_ms.abs_diff(now_ms) <= MAX_SCHEDULE_EPOCH_DRIFT_MS => Some(FleetSync {
+                            seed,
+                            epoch_ms,
+                            phase_offset_ms,
+                        }),
+                        _ => {
+                            let message = "schedule_epoch_ms plus phase_offset_ms must be within 30 days of now";
+                            errors.push(FieldError::new("schedule_epoch_ms", message));
+                            None
+                        }
+                    }
+                }
+                (None, None) => None,
+                (seed, _) => {
+                    let field = if seed.is_none() { "schedule_seed" } else { "schedule_epoch_ms" };
+                    errors.push(FieldError::new(field, "schedule_seed and schedule_epoch_ms must be given together"));
+                    None
+                }
+            };
+            config.burst_trigger = options.burst_trigger.unwrap_or(BurstTrigger::Internal);
+            if config.burst_trigger == BurstTrigger::External && config.fleet_sync.is_some() {
+                let message = "an external burst_trigger cannot be combined with a shared schedule";
+                errors.push(FieldError::new("burst_trigger", message));
+            }
+        }
+
+        if request.mode == ExecutionMode::Throttled {
+            config.duty_cycle_ms = options.duty_cycle_ms.unwrap_or(100);
+            if !(10..=10_000).contains(&config.duty_cycle_ms) {
+                errors.push(FieldError::new("duty_cycle_ms", "duty_cycle_ms must be between 10 and 10000"));
+            }
+        }
+
+        // Checked in every mode, so a mistake shows up before the request is switched to a mode that uses it
+        if let Some(utilization) = options.utilization
+            && utilization > 100
+        {
+            errors.push(FieldError::new("utilization", "utilization must be between 0 and 100"));
+        }
+        if matches!(request.mode, ExecutionMode::Bursty | ExecutionMode::Throttled) {
+            config.utilization = options.utilization.unwrap_or(50).min(100);
+        }
+
+        if let Some(ramp_secs) = options.ramp_secs {
+            match validate_ramp_secs(ramp_secs) {
+                Ok(ramp) => config.ramp = ramp,
+                Err(e) => errors.push(FieldError::new("ramp_secs", e)),
+            }
+        }
+
+        if let Some(nice) = options.nice {
+            if !(-20..=19).contains(&nice) {
+                errors.push(FieldError::new("nice", "nice must be between -20 and 19"));
+            }
+            config.nice = Some(nice);
+        }
+
+        if let Some(interval_us) = options.latency_probe_us {
+            if !LATENCY_PROBE_US_RANGE.contains(&interval_us) {
+                errors.push(FieldError::new("latency_probe_us", "latency_probe_us must be between 10 and 100000"));
+            }
+            config.latency_probe = Some(Duration::from_micros(interval_us));
+        }
+
+        config.workload = options.workload.unwrap_or_default();
+        if config.workload == Workload::Stream
+            && !matches!(request.mode, ExecutionMode::Threaded | ExecutionMode::Throttled)
+        {
+            errors.push(FieldError::new("workload", "the stream workload runs in threaded and throttled modes only"));
+        }
+        if let Some(array_mb) = options.stream_array_mb {
+            if !STREAM_ARRAY_MB_RANGE.contains(&array_mb) {
+                errors.push(FieldError::new("stream_array_mb", "stream_array_mb must be between 1 and 1024"));
+            }
+            config.stream_array_mb = array_mb;
+        }
+
+        if let Some(ops_floor) = options.stop_if_ops_below {
+            if ops_floor == 0 {
+                errors.push(FieldError::new("stop_if_ops_below", "stop_if_ops_below must be positive"));
+            }
+            config.guards.ops_floor = Some(ops_floor);
+        }
+        if let Some(percent) = options.stop_if_drop_percent_exceeds {
+            if !(percent > 0.0 && percent < 100.0) {
+                let message = "stop_if_drop_percent_exceeds must be between 0 and 100 (exclusive)";
+                errors.push(FieldError::new("stop_if_drop_percent_exceeds", message));
+            }
+            config.guards.max_drop_percent = Some(percent);
+        }
+        if let Some(window_secs) = options.guard_window_secs {
+            if !GUARD_WINDOW_SECS_RANGE.contains(&window_secs) {
+                errors.push(FieldError::new("guard_window_secs", "guard_window_secs must be between 1 and 3600"));
+            }
+            config.guards.window_secs = window_secs;
+        }
+
+        match (&options.start_at, options.start_delay_ms) {
+            (Some(_), Some(_)) => {
+                errors.push(FieldError::new("start_at", "set either start_at or start_delay_ms, not both"));
+            }
+            (Some(start_at), None) => match parse_rfc3339(start_at) {
+                Ok(start_ms) => config.start = Some(ScheduledStart::At(start_ms)),
+                Err(e) => errors.push(FieldError::new("start_at", format!("start_at: {}", e))),
+            },
+            (None, Some(delay_ms)) if delay_ms > MAX_START_DELAY_MS => {
+                let message = format!("start_delay_ms must be at most {} (24 hours)", MAX_START_DELAY_MS);
+                errors.push(FieldError::new("start_delay_ms", message));
+            }
+            (None, Some(delay_ms)) => config.start = Some(ScheduledStart::After(Duration::from_millis(delay_ms))),
+            (None, None) => {}
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Whether switching from this config to `next` needs new workers rather than handing the running ones
+    // the new settings: workers take their mode, cores, nice level, latency probes, and workload (with its
+    // arrays) when they start
+    fn needs_new_workers(&self, next: &RunConfig) -> bool {
+        self.mode != next.mode
+            || self.physical_cores_only != next.physical_cores_only
+            || self.nice != next.nice
+            || self.latency_probe != next.latency_probe
+            || self.workload != next.workload
+            || (next.workload == Workload::Stream && self.stream_array_mb != next.stream_array_mb)
+    }
+
+    // How long worker `index` of `workers` waits before starting: workers start evenly spread over the
+    // ramp, the first one right away
+    pub(crate) fn ramp_delay(&self, index: usize, workers: usize) -> Duration {
+        self.ramp.mul_f64(index as f64 / workers.max(1) as f64)
+    }
+}
+
+pub const MAX_RAMP_SECS: f64 = 3600.0;
+
+// Sleep intervals a latency probe may use, in microseconds
+pub const LATENCY_PROBE_US_RANGE: std::ops::RangeInclusive<u64> = 10..=100_000;
+
+pub fn validate_ramp_secs(ramp_secs: f64) -> Result<Duration, String> {
+    if !(0.0..=MAX_RAMP_SECS).contains(&ramp_secs) {
+        return Err(format!("ramp_secs must be between 0 and {}", MAX_RAMP_SECS));
+    }
+    Ok(Duration::from_secs_f64(ramp_secs))
+}
+
+// Sleeping workers wake at least this often to beat their heartbeat
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+// Wakes a run's sleeping workers when the run is stopped, reconfigured, or externally triggered, and
+// totals the run's operations
+#[derive(Default)]
+pub(crate) struct RunSignal {
+    // Mirrors events.stopped so compute loops can check it without locking
+    stopped: AtomicBool,
+    // Operations completed by the run's workers (reported per job)
+    ops: AtomicU64,
+    events: Mutex<RunEvents>,
+    condvar: std::sync::Condvar,
+}
+
+#[derive(Debug, Default)]
+struct RunEvents {
+    stopped: bool,
+    // Bumped whenever the run's settings change
+    reconfigurations: u64,
+    trigger: TriggerState,
+}
+
+impl RunSignal {
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    // Count completed operations toward the agent's ops/sec and the run's total
+    pub(crate) fn count_ops(&self, state: &StressEngine, ops: u64) {
+        state.current_counter.fetch_add(ops, Ordering::Relaxed);
+        self.ops.fetch_add(ops, Ordering::Relaxed);
+    }
+
+    // Count bytes moved by the stream workload toward the agent's memory bandwidth
+    pub(crate) fn count_bytes(&self, state: &StressEngine, bytes: u64) {
+        state.memory_counter.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn ops(&self) -> u64 {
+        self.ops.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.events.lock().unwrap().stopped = true;
+        self.condvar.notify_all();
+    }
+
+    pub(crate) fn reconfigured(&self) {
+        self.events.lock().unwrap().reconfigurations += 1;
+        self.condvar.notify_all();
+    }
+
+    // Fire one burst on every coordinator of the run; returns the trigger's generation
+    pub(crate) fn trigger(&self, duration_ms: Option<u64>) -> u64 {
+        let mut events = self.events.lock().unwrap();
+        events.trigger.generation += 1;
+        events.trigger.duration_ms = duration_ms;
+        self.condvar.notify_all();
+        events.trigger.generation
+    }
+
+    pub(crate) fn last_trigger(&self) -> TriggerState {
+        self.events.lock().unwrap().trigger
+    }
+
+    // Sleep for `duration` even if the run is reconfigured meanwhile. Returns false if the run has stopped.
+    pub(crate) fn sleep_through(&self, heartbeat: &Heartbeat, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+            if !self.sleep(heartbeat, remaining) {
+                return false;
+            }
+        }
+    }
+
+    // Sleep for `duration`, returning early if the run is stopped or reconfigured.
+    // Returns false if the run has stopped.
+    pub(crate) fn sleep(&self, heartbeat: &Heartbeat, duration: Duration) -> bool {
+        let deadline = Instant::now().checked_add(duration);
+        let mut events = self.events.lock().unwrap();
+        let reconfigurations = events.reconfigurations;
+        loop {
+            heartbeat.beat();
+            if events.stopped {
+                return false;
+            }
+            if events.reconfigurations != reconfigurations {
+                return true;
+            }
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => remaining.min(HEARTBEAT_INTERVAL),
+                    _ => return true,
+                },
+                // Too long to represent; only a stop or reconfiguration ends it
+                None => HEARTBEAT_INTERVAL,
+            };
+            events = self.condvar.wait_timeout(events, timeout).unwrap().0;
+        }
+    }
+
+    // Block until a trigger newer than `seen_generation` fires. Returns None if the run was
+    // stopped or reconfigured first.
+    pub(crate) fn wait_for_trigger(&self, heartbeat: &Heartbeat, seen_generation: u64) -> Option<TriggerState> {
+        let mut events = self.events.lock().unwrap();
+        let reconfigurations = events.reconfigurations;
+        loop {
+            heartbeat.beat();
+            if events.stopped || events.reconfigurations != reconfigurations {
+                return None;
+            }
+            if events.trigger.generation != seen_generation {
+                return Some(events.trigger);
+            }
+            events = self.condvar.wait_timeout(events, HEARTBEAT_INTERVAL).unwrap().0;
+        }
+    }
+}
+
+// Worker threads of a running stress test
+pub(crate) struct ActiveRun {
+    pub(crate) signal: Arc<RunSignal>,
+    pub(crate) workers: Vec<(Arc<Heartbeat>, thread::JoinHandle<()>)>,
+}
+
+impl ActiveRun {
+    // Spawn the worker threads for a mode: one per (core id, CPU to pin to) entry of `cores`, or a
+    // single simulated worker. Workers read the run's settings from `config`.
+    pub(crate) fn spawn(
+        state: &Arc<StressEngine>,
+        mode: ExecutionMode,
+        config: &watch::Sender<RunConfig>,
+        cores: &[(usize, Option<usize>)],
+        name_prefix: &str,
+    ) -> Self {
+        let signal = Arc::new(RunSignal::default());
+        let mut workers = Vec::new();
+
+        // The test harness advances the simulation itself
+        if state.harness.is_some() {
+            return ActiveRun { signal, workers };
+        }
+
+        let ramp = config.borrow().ramp;
+        if !ramp.is_zero() {
+            info!(ramp_secs = ramp.as_secs_f64(), workers = cores.len(), "soft start: ramping up the workers");
+        }
+
+        if let Some(per_core_ops_per_sec) = state.simulated_ops_per_sec {
+            let state_clone = Arc::clone(state);
+            let signal_clone = Arc::clone(&signal);
+            let heartbeat = state.register_heartbeat(format!("{}simulated-worker", name_prefix));
+            let heartbeat_clone = Arc::clone(&heartbeat);
+            let config_rx = config.subscribe();
+            let num_cores = cores.len();
+            let handle = thread::spawn(move || {
+                supervise(&state_clone, &heartbeat_clone, Some(&signal_clone), || {
+                    simulated_worker(
+                        Arc::clone(&state_clone),
+                        Arc::clone(&signal_clone),
+                        Arc::clone(&heartbeat_clone),
+                        config_rx.clone(),
+                        num_cores,
+                        per_core_ops_per_sec,
+                    )
+                });
+            });
+            workers.push((heartbeat, handle));
+            return ActiveRun { signal, workers };
+        }
+
+        let name = match mode {
+            ExecutionMode::Threaded => "threaded-worker",
+            ExecutionMode::FreshProcess => "fresh-process-spawner",
+            ExecutionMode::Bursty => "burst-coordinator",
+            ExecutionMode::Throttled => "throttled-worker",
+        };
+        for (index, &(core_id, pin_to)) in cores.iter().enumerate() {
+            let state_clone = Arc::clone(state);
+            let signal_clone = Arc::clone(&signal);
+            let heartbeat = state.register_heartbeat(format!("{}{}-{}", name_prefix, name, core_id));
+            let heartbeat_clone = Arc::clone(&heartbeat);
+            let config_rx = config.subscribe();
+            let delay = config.borrow().ramp_delay(index, cores.len());
+            let nice = config.borrow().nice;
+            let (workload, stream_array_mb) = (config.borrow().workload, config.borrow().stream_array_mb);
+            let handle = thread::spawn(move || {
+                // Worker processes spawned from this thread inherit the pinning and nice level
+                if let Some(cpu) = pin_to
+                    && let Err(e) = pin_current_thread(cpu)
+                {
+                    warn!(core_id, cpu, "failed to pin worker: {}", e);
+                }
+                if let Some(nice) = nice
+                    && let Err(e) = set_current_thread_nice(nice)
+                {
+                    warn!(core_id, nice, "failed to set the worker's nice level: {}", e);
+                }
+                if !signal_clone.sleep_through(&heartbeat_clone, delay) {
+                    return;
+                }
+                supervise(&state_clone, &heartbeat_clone, Some(&signal_clone), || {
+                    let (state, signal, heartbeat) =
+                        (Arc::clone(&state_clone), Arc::clone(&signal_clone), Arc::clone(&heartbeat_clone));
+                    match mode {
+                        ExecutionMode::Threaded if workload == Workload::Stream => {
+                            stream_worker(state, signal, heartbeat, core_id, stream_array_mb)
+                        }
+                        ExecutionMode::Threaded => cpu_worker(state, signal, heartbeat),
+                        ExecutionMode::FreshProcess => {
+                            process_spawner(state, signal, heartbeat, config_rx.clone(), core_id)
+                        }
+                        ExecutionMode::Bursty => {
+                            burst_coordinator(state, signal, heartbeat, config_rx.clone(), core_id)
+                        }
+                        ExecutionMode::Throttled => {
+                            throttled_worker(state, signal, heartbeat, config_rx.clone(), core_id)
+                        }
+                    }
+                });
+            });
+            workers.push((heartbeat, handle));
+        }
+
+        if let Some(interval) = config.borrow().latency_probe {
+            for &(core_id, pin_to) in cores {
+                state.latency.lock().unwrap().insert(core_id, CoreLatency::new(interval));
+                let state_clone = Arc::clone(state);
+                let signal_clone = Arc::clone(&signal);
+                let heartbeat = state.register_heartbeat(format!("{}latency-probe-{}", name_prefix, core_id));
+                let heartbeat_clone = Arc::clone(&heartbeat);
+                let handle = thread::spawn(move || {
+                    // On the worker's CPU, at the agent's own priority
+                    if let Some(cpu) = pin_to
+                        && let Err(e) = pin_current_thread(cpu)
+                    {
+                        warn!(core_id, cpu, "failed to pin latency probe: {}", e);
+                    }
+                    supervise(&state_clone, &heartbeat_clone, Some(&signal_clone), || {
+                        let (state, signal) = (Arc::clone(&state_clone), Arc::clone(&signal_clone));
+                        latency_probe(state, signal, Arc::clone(&heartbeat_clone), core_id, interval)
+                    });
+                });
+                workers.push((heartbeat, handle));
+            }
+        }
+
+        ActiveRun { signal, workers }
+    }
+
+    // Signal every worker to stop and wait for all of them to exit
+    fn stop(self, state: &StressEngine) {
+        self.signal.stop();
+        for (heartbeat, handle) in self.workers {
+            if handle.join().is_err() {
+                error!(thread = %heartbeat.name, "worker thread panicked");
+            }
+            state.unregister_heartbeat(&heartbeat);
+        }
+    }
+}
+
+// Liveness marker for a background thread; the thread beats on every loop iteration
+pub(crate) struct Heartbeat {
+    pub(crate) name: String,
+    pub(crate) started_at: Instant,
+    // Milliseconds since started_at at the last beat (0 = never beaten)
+    pub(crate) last_beat_ms: AtomicU64,
+    // Times the thread panicked and was restarted by `supervise`, and the last panic's message
+    pub(crate) restarts: AtomicU64,
+    pub(crate) last_panic: Mutex<Option<String>>,
+}
+
+// Pause before the first restart of a panicked thread; it doubles with every restart, up to a minute
+const FIRST_RESTART_DELAY: Duration = Duration::from_millis(100);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+// Run the loop of a background thread, restarting it when it panics instead of letting the thread die and
+// its metrics quietly flatline. Returns when `body` does, or when `signal` (the run of a worker thread) is
+// stopped. Restarts are counted on the thread's heartbeat and the engine (/healthz, /status).
+pub(crate) fn supervise(
+    state: &StressEngine,
+    heartbeat: &Heartbeat,
+    signal: Option<&RunSignal>,
+    mut body: impl FnMut(),
+) {
+    loop {
+        let Err(panic) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(&mut body)) else {
+            return;
+        };
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        *heartbeat.last_panic.lock().unwrap() = Some(message.clone());
+        if signal.is_some_and(RunSignal::is_stopped) {
+            error!(thread = %heartbeat.name, "thread panicked while stopping: {}", message);
+            return;
+        }
+        let restarts = heartbeat.restarts.fetch_add(1, Ordering::Relaxed) + 1;
+        state.thread_restarts.fetch_add(1, Ordering::Relaxed);
+        let delay = FIRST_RESTART_DELAY.saturating_mul(1 << (restarts - 1).min(10)).min(MAX_RESTART_DELAY);
+        error!(thread = %heartbeat.name, restarts, "thread panicked: {}; restarting in {:?}", message, delay);
+        match signal {
+            Some(signal) => {
+                if !signal.sleep_through(heartbeat, delay) {
+                    return;
+                }
+            }
+            None => thread::sleep(delay),
+        }
+    }
+}
+
+impl Heartbeat {
+    pub(crate) fn beat(&self) {
+        let now_ms = (self.started_at.elapsed().as_millis() as u64).max(1);
+        self.last_beat_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    // Milliseconds since the last beat, or None if the thread has never beaten
+    fn age_ms(&self) -> Option<u64> {
+        match self.last_beat_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last => Some((self.started_at.elapsed().as_millis() as u64).saturating_sub(last)),
+        }
+    }
+}
+
+// Settings of a StressEngine that stay fixed for its lifetime
+#[derive(Debug)]
+pub struct EngineOptions {
+    // Worker threads/processes per run (one per core)
+    pub num_cores: usize,
+    // Operations per worker process for runs that don't set their own (until changed by PATCH /config)
+    pub worker_ops: u64,
+    // Per-second samples kept for the history
+    pub history_secs: usize,
+    // Print every burst start/end event to stdout as a JSON line
+    pub log_burst_events: bool,
+    // Raise an ops-drop event when the run's ops/sec falls this many percent below its peak
+    pub ops_drop_alert_percent: Option<f64>,
+    // Feed synthetic metrics instead of running real workers
+    pub simulate: bool,
+    // Record every control API call (HTTP only)
+    pub control_recorder: Option<ControlRecorder>,
+    // Cgroup that fresh-process and bursty worker processes are confined to
+    pub worker_cgroup: Option<WorkerCgroup>,
+    // CPU ids to pin the workers to, one per worker (num_cores of them); None leaves them unpinned
+    pub cores: Option<Vec<usize>>,
+    // CPUs available to this process, reported in /status
+    pub cpu_allocation: Option<CpuAllocation>,
+    // Which CPUs share a physical core (use_physical_cores_only, smt_siblings in /status)
+    pub topology: CpuTopology,
+    // Soft start of runs and jobs that don't set their own ramp_secs
+    pub default_ramp: Duration,
+    // Agents found by LAN discovery (--discovery); None when discovery is off
+    pub peers: Option<Arc<PeerTable>>,
+    // Save the run and jobs to this file on every change (--state-file)
+    pub state_file: Option<StateFile>,
+}
+
+impl Default for EngineOptions {
+    // One worker per CPU this process can use, counting container limits
+    fn default() -> Self {
+        let cpu_allocation = CpuAllocation::detect();
+        EngineOptions {
+            num_cores: cpu_allocation.effective_cpus,
+            worker_ops: DEFAULT_WORKER_OPS,
+            history_secs: 3600,
+            log_burst_events: false,
+            ops_drop_alert_percent: Some(DEFAULT_OPS_DROP_ALERT_PERCENT),
+            simulate: false,
+            control_recorder: None,
+            worker_cgroup: None,
+            cores: None,
+            cpu_allocation: Some(cpu_allocation),
+            topology: CpuTopology::detect(),
+            default_ramp: Duration::ZERO,
+            peers: None,
+            state_file: None,
+        }
+    }
+}
+
+impl StressEngine {
+    // Stopped engine; workers are spawned when a run starts. Call spawn_sampler to get metrics.
+    pub fn new(options: EngineOptions) -> Self {
+        // Each simulated agent gets its own per-core baseline so a fleet of them looks heterogeneous
+        let simulated_ops_per_sec = options.simulate.then(|| {
+            use rand::Rng;
+            rand::thread_rng().gen_range(200_000.0..500_000.0)
+        });
+
+        StressEngine {
+            operations_per_second: AtomicU64::new(0),
+            current_counter: Arc::new(AtomicU64::new(0)),
+            memory_counter: AtomicU64::new(0),
+            memory_bytes_per_second: AtomicU64::new(0),
+            is_running: AtomicBool::new(false),
+            config: watch::Sender::new(RunConfig::default()),
+            run: Mutex::new(None),
+            jobs: Mutex::new(Vec::new()),
+            next_job_id: AtomicU64::new(1),
+            num_cores: options.num_cores,
+            worker_ops: AtomicU64::new(options.worker_ops),
+            simulated_ops_per_sec,
+            harness: None,
+            burst_operations: Arc::new(AtomicU64::new(0)),
+            burst_time_ms: Arc::new(AtomicU64::new(0)),
+            burst_ops_per_second: AtomicU64::new(0),
+            control_recorder: options.control_recorder,
+            worker_cgroup: options.worker_cgroup,
+            cores: options.cores,
+            cpu_allocation: options.cpu_allocation,
+            topology: options.topology,
+            default_ramp: options.default_ramp,
+            peers: options.peers,
+            cpu_utilization: Mutex::new(CpuUtilization::default()),
+            temperatures: Mutex::new(Vec::new()),
+            measurement_quality: Mutex::new(None),
+            baseline: Mutex::new(None),
+            history: Mutex::new(std::collections::VecDeque::new()),
+            history_capacity: options.history_secs.max(1),
+            histograms: Mutex::new(Histograms::default()),
+            process_stats: Mutex::new(ProcessStats::default()),
+            latency: Mutex::new(std::collections::BTreeMap::new()),
+            burst_events: tokio::sync::broadcast::channel(1024).0,
+            log_burst_events: options.log_burst_events,
+            sample_events: tokio::sync::broadcast::channel(16).0,
+            lifecycle_events: tokio::sync::broadcast::channel(1024).0,
+            ops_drop_alert_percent: options.ops_drop_alert_percent,
+            ops_drop_detector: Mutex::new(OpsDropDetector::default()),
+            guard_window: Mutex::new(GuardWindow::default()),
+            guard_trip: Mutex::new(None),
+            power: Mutex::new(None),
+            energy: Mutex::new(EnergyTotals::default()),
+            pending_start: Mutex::new(None),
+            schedules: Mutex::new(Vec::new()),
+            next_schedule_id: AtomicU64::new(1),
+            scheduler_started: AtomicBool::new(false),
+            benchmark: Mutex::new(None),
+            state_file: options.state_file,
+            saved_state: Mutex::new(SavedState {
+                workers: options.num_cores,
+                worker_ops: options.worker_ops,
+                ..SavedState::default()
+            }),
+            started_at: Instant::now(),
+            heartbeats: Mutex::new(Vec::new()),
+            thread_restarts: AtomicU64::new(0),
+        }
+    }
+
+    // Simulated engine on a virtual clock (--test-harness): no threads, no real CPU load, and
+    // time only moves on advance_clock (POST /harness/advance). Serve it in-process with build_router.
+    pub fn test_harness(options: EngineOptions) -> Self {
+        let engine = StressEngine::new(options);
+        StressEngine {
+            simulated_ops_per_sec: Some(HARNESS_OPS_PER_SEC),
+            harness: Some(TestHarness::new(engine.config.subscribe(), unix_time_ms())),
+            ..engine
+        }
+    }
+
+    // Start a stress test on every core with the default engine options and a running sampler
+    pub fn start(mode: ExecutionMode, options: RunOptions) -> Result<Arc<Self>, EngineError> {
+        let engine = Arc::new(StressEngine::new(EngineOptions::default()));
+        engine.spawn_sampler();
+        engine.start_run(&StartCpuRequest { mode, options })?;
+        Ok(engine)
+    }
+
+    // Apply a start request: validate options, then start, restart, or reconfigure the current run, or arm
+    // it to do so at the requested time. Blocks until the workers of a replaced run have exited.
+    pub fn start_run(self: &Arc<Self>, request: &StartCpuRequest) -> Result<String, EngineError> {
+        self.start_run_at(request, self.clock_ms().unwrap_or_else(unix_time_ms))
+    }
+
+    // start_run at now_ms (Unix ms, or the test harness's virtual clock, which is locked while it advances)
+    fn start_run_at(self: &Arc<Self>, request: &StartCpuRequest, now_ms: u64) -> Result<String, EngineError> {
+        let mut config = self.run_config(request).map_err(EngineError::Validation)?;
+        let start_ms = match config.start.take() {
+            Some(ScheduledStart::At(start_ms)) => Some(start_ms),
+            Some(ScheduledStart::After(delay)) => Some(now_ms + delay.as_millis() as u64),
+            None => None,
+        };
+        // The saved run starts right away when resumed
+        let mut saved = request.clone();
+        saved.options.start_at = None;
+        saved.options.start_delay_ms = None;
+        // A start_at that has already passed (e.g. a request that arrived late) starts the run right away
+        if let Some(start_ms) = start_ms.filter(|&start_ms| start_ms > now_ms) {
+            return self.arm_start(config, saved, start_ms, now_ms);
+        }
+
+        // Holding the run lock serializes concurrent start/stop requests
+        let mut run = self.run.lock().unwrap();
+        self.check_no_benchmark()?;
+        self.check_no_jobs()?;
+        if self.cancel_pending_start() {
+            info!("armed start replaced by an immediate one");
+        }
+        let message = self.start_locked(&mut run, config, true);
+        self.save_state(|state| state.run = Some(saved));
+        Ok(message)
+    }
+
+    // Runs, jobs, and calibrations wait for a benchmark to finish, so they don't skew its numbers
+    fn check_no_benchmark(&self) -> Result<(), EngineError> {
+        if self.benchmark.lock().unwrap().is_some() {
+            return Err(EngineError::Conflict(
+                "a benchmark is running on this agent; wait for its report or stop it with POST /end-cpu".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_no_jobs(&self) -> Result<(), EngineError> {
+        let jobs = self.jobs.lock().unwrap().len();
+        if jobs > 0 {
+            return Err(EngineError::Conflict(format!(
+                "{} job(s) are running on this agent's cores; stop them with DELETE /jobs/{{id}} first",
+                jobs
+            )));
+        }
+        Ok(())
+    }
+
+    // Start, restart, or reconfigure the run with the run lock held. An armed run was calibrated when it was
+    // armed, so its start isn't delayed by `calibrate`.
+    fn start_locked(self: &Arc<Self>, run: &mut Option<ActiveRun>, config: RunConfig, calibrate: bool) -> String {
+        let requested_mode = config.mode;
+        let (current_mode, restart) = {
+            let current = self.config.borrow();
+            (current.mode, current.needs_new_workers(&config))
+        };
+
+        match run.take() {
+            Some(active) if !restart => {
+                // Already running with the requested mode; hand the new options to the running workers
+                self.reset_guards(Duration::ZERO);
+                self.config.send_replace(config);
+                active.signal.reconfigured();
+                *run = Some(active);
+                format!("CPU stress test already running with mode: {:?}", current_mode)
+            }
+            Some(active) => {
+                // Running with a different mode, nice level, or latency probe: tear down the old workers before
+                // starting new ones
+                info!(from = ?current_mode, to = ?requested_mode, nice = config.nice, "restarting the workers");
+                active.stop(self);
+                if calibrate {
+                    self.calibrate();
+                }
+
+                // Reset counters
+                self.current_counter.store(0, Ordering::Relaxed);
+                self.operations_per_second.store(0, Ordering::Relaxed);
+                self.memory_counter.store(0, Ordering::Relaxed);
+                self.memory_bytes_per_second.store(0, Ordering::Relaxed);
+                *self.histograms.lock().unwrap() = Histograms::default();
+                *self.process_stats.lock().unwrap() = ProcessStats::default();
+                *self.energy.lock().unwrap() = EnergyTotals::default();
+                self.latency.lock().unwrap().clear();
+
+                *self.ops_drop_detector.lock().unwrap() = OpsDropDetector::default();
+                self.reset_guards(config.ramp);
+                emit_lifecycle_event(
+                    self,
+                    LifecycleEvent::Stopped {
+                        timestamp_ms: unix_time_ms(),
+                        mode: current_mode,
+                    },
+                );
+
+                self.config.send_replace(config);
+                let cores = self.worker_cores(&self.config.borrow());
+                *run = Some(ActiveRun::spawn(self, requested_mode, &self.config, &cores, ""));
+                emit_lifecycle_event(
+                    self,
+                    LifecycleEvent::Started {
+                        timestamp_ms: unix_time_ms(),
+                        mode: requested_mode,
+                    },
+                );
+
+                info!(mode = ?requested_mode, "CPU stress test restarted");
+                format!("CPU stress test restarted with mode: {:?}", requested_mode)
+            }
+            None => {
+                if calibrate {
+                    self.calibrate();
+                }
+                *self.histograms.lock().unwrap() = Histograms::default();
+                *self.process_stats.lock().unwrap() = ProcessStats::default();
+                *self.energy.lock().unwrap() = EnergyTotals::default();
+                self.latency.lock().unwrap().clear();
+                *self.ops_drop_detector.lock().unwrap() = OpsDropDetector::default();
+                self.reset_guards(config.ramp);
+                self.config.send_replace(config);
+                let cores = self.worker_cores(&self.config.borrow());
+                *run = Some(ActiveRun::spawn(self, requested_mode, &self.config, &cores, ""));
+                self.is_running.store(true, Ordering::Relaxed);
+                emit_lifecycle_event(
+                    self,
+                    LifecycleEvent::Started {
+                        timestamp_ms: unix_time_ms(),
+                        mode: requested_mode,
+                    },
+                );
+
+                info!(mode = ?requested_mode, "CPU stress test started");
+                format!("CPU stress test started with mode: {:?}", requested_mode)
+            }
+        }
+    }
+
+    // Arm a run to start at start_ms, replacing any run armed before. The noise floor is measured now if
+    // the agent is idle, so every agent of a fleet starts its workers right at start_ms.
+    fn arm_start(
+        self: &Arc<Self>,
+        config: RunConfig,
+        request: StartCpuRequest,
+        start_ms: u64,
+        now_ms: u64,
+    ) -> Result<String, EngineError> {
+        let delay_ms = start_ms - now_ms;
+        if delay_ms > MAX_START_DELAY_MS {
+            let message = format!("start_at must be at most {} ms (24 hours) ahead", MAX_START_DELAY_MS);
+            return Err(EngineError::Validation(vec![FieldError::new("start_at", message)]));
+        }
+
+        let run = self.run.lock().unwrap();
+        self.check_no_benchmark()?;
+        self.check_no_jobs()?;
+        if run.is_none() {
+            self.calibrate();
+        }
+        let mode = config.mode;
+        let signal = Arc::new(RunSignal::default());
+        let pending = PendingStart {
+            start_ms,
+            config,
+            request,
+            signal: Arc::clone(&signal),
+        };
+        if let Some(replaced) = self.pending_start.lock().unwrap().replace(pending) {
+            replaced.signal.stop();
+        }
+        // The test harness starts it on its virtual clock instead
+        if self.harness.is_none() {
+            self.spawn_start_timer(signal, Duration::from_millis(delay_ms));
+        }
+        info!(?mode, start_at = %format_rfc3339(start_ms), delay_ms, "CPU stress test armed");
+        Ok(format!(
+            "CPU stress test armed with mode: {:?}; starts at {} (in {} ms)",
+            mode,
+            format_rfc3339(start_ms),
+            delay_ms
+        ))
+    }
+
+    fn spawn_start_timer(self: &Arc<Self>, signal: Arc<RunSignal>, delay: Duration) {
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat("armed-start-timer".to_string());
+        thread::spawn(move || {
+            let due = signal.sleep_through(&heartbeat, delay);
+            engine.unregister_heartbeat(&heartbeat);
+            if due {
+                engine.start_armed(|pending| Arc::ptr_eq(&pending.signal, &signal));
+            }
+        });
+    }
+
+    // Start the armed run if `due` says so (and a stop or another start hasn't cancelled it meanwhile)
+    fn start_armed(self: &Arc<Self>, due: impl FnOnce(&PendingStart) -> bool) {
+        let mut run = self.run.lock().unwrap();
+        let pending = {
+            let mut pending = self.pending_start.lock().unwrap();
+            match pending.take() {
+                Some(armed) if due(&armed) => armed,
+                armed => {
+                    *pending = armed;
+                    return;
+                }
+            }
+        };
+        if !self.jobs.lock().unwrap().is_empty() {
+            error!("armed start skipped: jobs were started on this agent's cores meanwhile");
+            return;
+        }
+        if self.benchmark.lock().unwrap().is_some() {
+            error!("armed start skipped: a benchmark is running");
+            return;
+        }
+        let message = self.start_locked(&mut run, pending.config, false);
+        self.save_state(|state| state.run = Some(pending.request));
+        info!(start_at = %format_rfc3339(pending.start_ms), "armed start: {}", message);
+    }
+
+    // Start the armed run once the test harness's virtual clock reaches its start time
+    pub(crate) fn start_armed_if_due(self: &Arc<Self>, now_ms: u64) {
+        self.start_armed(|pending| pending.start_ms <= now_ms);
+    }
+
+    // Cancel the armed run, if any; returns whether there was one. Called with the run lock held.
+    fn cancel_pending_start(&self) -> bool {
+        let cancelled = self.pending_start.lock().unwrap().take();
+        if let Some(pending) = &cancelled {
+            pending.signal.stop();
+        }
+        cancelled.is_some()
+    }
+
+    // Start the auto-stop guards of a new run (or of new settings) from scratch; samples during the soft
+    // start (`warmup`) are skipped. Called with the run lock held.
+    fn reset_guards(&self, warmup: Duration) {
+        *self.guard_window.lock().unwrap() = GuardWindow::new(warmup);
+        *self.guard_trip.lock().unwrap() = None;
+    }
+
+    // Settings of a run or job, with the agent's defaults for the options the request leaves out
+    fn run_config(&self, request: &StartCpuRequest) -> Result<RunConfig, Vec<FieldError>> {
+        let mut config = RunConfig::from_request(request, self.worker_ops.load(Ordering::Relaxed))?;
+        if request.options.ramp_secs.is_none() {
+            config.ramp = self.default_ramp;
+        }
+        // Check the nice level on a throwaway thread, so a level the agent isn't allowed to set fails the
+        // request instead of every worker
+        if let Some(nice) = config.nice
+            && self.simulated_ops_per_sec.is_none()
+        {
+            thread::scope(|scope| scope.spawn(|| set_current_thread_nice(nice)).join().expect("Nice check panicked"))
+                .map_err(|e| vec![FieldError::new("nice", e)])?;
+        }
+        if config.physical_cores_only && !self.topology.is_known() {
+            let message = "the CPU topology of this host is unknown, so physical cores can't be told from SMT siblings";
+            return Err(vec![FieldError::new("use_physical_cores_only", message)]);
+        }
+        Ok(config)
+    }
+
+    // Check a start request without starting anything; reports every invalid field
+    pub fn validate_start(&self, request: &StartCpuRequest) -> Result<(), EngineError> {
+        self.run_config(request).map(|_| ()).map_err(EngineError::Validation)
+    }
+
+    // Check a job request against this agent's cores and the running jobs. Returns the job's settings and
+    // cores, or every problem found: cores used by other jobs are a conflict, but are listed with the
+    // invalid fields when there are any, so one response has everything that needs fixing.
+    fn check_job(&self, request: &JobRequest, jobs: &[Job]) -> Result<(RunConfig, Vec<usize>), EngineError> {
+        let mut errors = Vec::new();
+        let config = self.run_config(&request.start).map_err(|e| errors.extend(e)).ok();
+        let cores = parse_core_list(&request.cores).unwrap_or_else(|e| {
+            errors.push(FieldError::new("cores", format!("cores: {}", e)));
+            Vec::new()
+        });
+        let available = self.cores.clone().unwrap_or_else(allowed_cores);
+        let unavailable: Vec<usize> = cores.iter().copied().filter(|core| !available.contains(core)).collect();
+        match unavailable[..] {
+            [] => {}
+            [core] => errors.push(FieldError::new("cores", format!("core {} is not available to this agent", core))),
+            _ => errors.push(FieldError::new(
+                "cores",
+                format!("cores {} are not available to this agent", format_core_list(&unavailable)),
+            )),
+        }
+        if let Some(duration_secs) = request.duration_secs
+            && !(duration_secs.is_finite() && duration_secs > 0.0)
+        {
+            errors.push(FieldError::new("duration_secs", "duration_secs must be positive"));
+        }
+        let options = &request.start.options;
+        let guard_field = if options.stop_if_ops_below.is_some() {
+            Some("stop_if_ops_below")
+        } else if options.stop_if_drop_percent_exceeds.is_some() {
+            Some("stop_if_drop_percent_exceeds")
+        } else {
+            None
+        };
+        if let Some(field) = guard_field {
+            errors.push(FieldError::new(field, "auto-stop guards apply to runs (POST /start-cpu), not jobs"));
+        }
+        let start_field = if options.start_at.is_some() {
+            Some("start_at")
+        } else if options.start_delay_ms.is_some() {
+            Some("start_delay_ms")
+        } else {
+            None
+        };
+        if let Some(field) = start_field {
+            errors.push(FieldError::new(field, "scheduled starts apply to runs (POST /start-cpu), not jobs"));
+        }
+
+        let conflicts: Vec<String> = jobs
+            .iter()
+            .filter_map(|job| {
+                let shared: Vec<usize> = cores.iter().copied().filter(|core| job.cores.contains(core)).collect();
+                (!shared.is_empty()).then(|| {
+                    format!("cores {} are already used by job {} ({})", format_core_list(&shared), job.id, job.name)
+                })
+            })
+            .collect();
+        match config {
+            Some(config) if errors.is_empty() => {
+                if conflicts.is_empty() {
+                    Ok((config, cores))
+                } else {
+                    Err(EngineError::Conflict(conflicts.join("; ")))
+                }
+            }
+            _ => {
+                errors.extend(conflicts.into_iter().map(|conflict| FieldError::new("cores", conflict)));
+                Err(EngineError::Validation(errors))
+            }
+        }
+    }
+
+    // Check a job request without starting it; reports every invalid field
+    pub fn validate_job(&self, request: &JobRequest) -> Result<(), EngineError> {
+        self.check_job(request, &self.jobs.lock().unwrap()).map(|_| ())
+    }
+
+    // Turn a mixed request into one checked job per component. The components get consecutive cores from
+    // those no running job uses, in the order threaded, fresh-process, bursty, throttled; problems with a
+    // component's options are reported as "<mode>.<field>".
+    fn check_mixed(&self, request: &MixedRequest, jobs: &[Job]) -> Result<Vec<MixedJob>, EngineError> {
+        let components = request.components();
+        let mut errors = Vec::new();
+        if components.is_empty() {
+            let message = "a mixed run needs at least one of threaded, fresh-process, bursty, and throttled";
+            errors.push(FieldError::new("threaded", message));
+        }
+        for (mode, component) in &components {
+            if component.cores == 0 {
+                let field = format!("{}.cores", mode_name(*mode));
+                errors.push(FieldError::new(&field, format!("{} must be positive", field)));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(EngineError::Validation(errors));
+        }
+
+        let used: Vec<usize> = jobs.iter().flat_map(|job| job.cores.iter().copied()).collect();
+        let mut free: Vec<usize> = self.cores.clone().unwrap_or_else(allowed_cores);
+        free.retain(|core| !used.contains(core));
+        let needed: usize = components.iter().map(|(_, component)| component.cores).sum();
+        if needed > free.len() {
+            return Err(EngineError::Conflict(format!(
+                "the mixed run needs {} cores, but only {} of this agent's cores are free",
+                needed,
+                free.len()
+            )));
+        }
+
+        let prefix = request.name.as_deref().unwrap_or("mixed");
+        let mut free = free.into_iter();
+        let mut checked = Vec::new();
+        for (mode, component) in components {
+            let cores: Vec<usize> = free.by_ref().take(component.cores).collect();
+            let job = JobRequest {
+                name: Some(format!("{}-{}", prefix, mode_name(mode))),
+                cores: format_core_list(&cores),
+                duration_secs: request.duration_secs,
+                start: StartCpuRequest {
+                    mode,
+                    options: component.options.clone(),
+                },
+            };
+            match self.check_job(&job, jobs) {
+                Ok((config, cores)) => checked.push((job, config, cores)),
+                Err(EngineError::Validation(invalid)) => {
+                    errors.extend(invalid.into_iter().map(|error| {
+                        // duration_secs is the mixed request's own field
+                        let field = match error.field.as_str() {
+                            "duration_secs" => error.field,
+                            field => format!("{}.{}", mode_name(mode), field),
+                        };
+                        FieldError::new(&field, error.message)
+                    }));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(checked)
+        } else {
+            errors.dedup();
+            Err(EngineError::Validation(errors))
+        }
+    }
+
+    // Check a mixed request without starting it; reports every invalid field
+    pub fn validate_mixed(&self, request: &MixedRequest) -> Result<(), EngineError> {
+        self.check_mixed(request, &self.jobs.lock().unwrap()).map(|_| ())
+    }
+
+    // (core id, CPU to pin to) of each worker of a run: worker N is pinned to the Nth --cores entry. On
+    // physical cores only, the workers are pinned to one CPU of each physical core of the agent's cores,
+    // at most one worker per physical core.
+    pub(crate) fn worker_cores(&self, config: &RunConfig) -> Vec<(usize, Option<usize>)> {
+        if config.physical_cores_only {
+            let cpus = self.cores.clone().unwrap_or_else(allowed_cores);
+            return self
+                .topology
+                .physical_cores(&cpus)
+                .into_iter()
+                .take(self.num_cores)
+                .enumerate()
+                .map(|(core_id, cpu)| (core_id, Some(cpu)))
+                .collect();
+        }
+        (0..self.num_cores)
+            .map(|core_id| (core_id, self.cores.as_ref().and_then(|cores| cores.get(core_id).copied())))
+            .collect()
+    }
+
+    // Start a job on its own cores, alongside any other jobs; returns it as listed by GET /jobs
+    pub fn create_job(self: &Arc<Self>, request: &JobRequest) -> Result<JobInfo, EngineError> {
+        if self.harness.is_some() {
+            return Err(EngineError::Invalid("jobs are not supported by the test harness".to_string()));
+        }
+
+        // Lock order: run, then jobs (as in start_run)
+        let run = self.run.lock().unwrap();
+        self.check_no_benchmark()?;
+        let mut jobs = self.jobs.lock().unwrap();
+        let (config, cores) = self.check_job(request, &jobs)?;
+        if run.is_some() {
+            return Err(EngineError::Conflict(
+                "a stress test is running on all cores; stop it with POST /end-cpu first".to_string(),
+            ));
+        }
+        Ok(self.spawn_job(&mut jobs, request, config, cores))
+    }
+
+    // Start every component of a mixed run as a job, or none of them; returns the jobs as listed by GET /jobs
+    pub fn create_mixed(self: &Arc<Self>, request: &MixedRequest) -> Result<Vec<JobInfo>, EngineError> {
+        if self.harness.is_some() {
+            return Err(EngineError::Invalid("mixed runs are not supported by the test harness".to_string()));
+        }
+
+        let run = self.run.lock().unwrap();
+        self.check_no_benchmark()?;
+        let mut jobs = self.jobs.lock().unwrap();
+        let checked = self.check_mixed(request, &jobs)?;
+        if run.is_some() {
+            return Err(EngineError::Conflict(
+                "a stress test is running on all cores; stop it with POST /end-cpu first".to_string(),
+            ));
+        }
+        Ok(checked
+            .into_iter()
+            .map(|(job, config, cores)| self.spawn_job(&mut jobs, &job, config, cores))
+            .collect())
+    }
+
+    // Start the workers of a checked job; called with the run and jobs locks held
+    fn spawn_job(
+        self: &Arc<Self>,
+        jobs: &mut Vec<Job>,
+        request: &JobRequest,
+        config: RunConfig,
+        cores: Vec<usize>,
+    ) -> JobInfo {
+        let id = self.next_job_id.fetch_add(1, Ordering::Relaxed);
+        let mode = config.mode;
+        // On physical cores only, the job keeps its cores' SMT siblings (so no other job gets them) but
+        // leaves them idle
+        let worker_cpus = if config.physical_cores_only { self.topology.physical_cores(&cores) } else { cores.clone() };
+        let config = watch::Sender::new(config);
+        let workers: Vec<(usize, Option<usize>)> = worker_cpus.iter().map(|&cpu| (cpu, Some(cpu))).collect();
+        let job = Job {
+            id,
+            name: request.name.clone().unwrap_or_else(|| format!("job-{}", id)),
+            mode,
+            started_at: Instant::now(),
+            started_ms: unix_time_ms(),
+            duration_secs: request.duration_secs,
+            run: ActiveRun::spawn(self, mode, &config, &workers, &format!("job-{}-", id)),
+            config,
+            cores,
+        };
+        if let Some(duration_secs) = job.duration_secs {
+            self.spawn_job_timer(id, Arc::clone(&job.run.signal), Duration::from_secs_f64(duration_secs));
+        }
+        let saved = SavedJob {
+            id,
+            started_ms: job.started_ms,
+            request: request.clone(),
+        };
+        self.save_state(|state| state.jobs.push(saved));
+        let info = job.info();
+        info!(job = id, name = %info.name, ?mode, cores = %format_core_list(&info.cores), "job started");
+        jobs.push(job);
+        emit_lifecycle_event(
+            self,
+            LifecycleEvent::JobStarted {
+                timestamp_ms: unix_time_ms(),
+                job: info.clone(),
+            },
+        );
+        info
+    }
+
+    // Stop a job once its duration has elapsed (unless it is stopped first)
+    fn spawn_job_timer(self: &Arc<Self>, id: u64, signal: Arc<RunSignal>, duration: Duration) {
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat(format!("job-{}-timer", id));
+        thread::spawn(move || {
+            let deadline = Instant::now() + duration;
+            let mut expired = false;
+            while !signal.is_stopped() {
+                match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => {
+                        signal.sleep(&heartbeat, remaining);
+                    }
+                    _ => {
+                        expired = true;
+                        break;
+                    }
+                }
+            }
+            engine.unregister_heartbeat(&heartbeat);
+            if expired {
+                let _ = engine.finish_job(id, true);
+            }
+        });
+    }
+
+    // Running jobs, oldest first
+    pub fn jobs(&self) -> Vec<JobInfo> {
+        self.jobs.lock().unwrap().iter().map(Job::info).collect()
+    }
+
+    // Stop a job; blocks until its workers have exited
+    pub fn stop_job(&self, id: u64) -> Result<String, EngineError> {
+        self.finish_job(id, false)
+    }
+
+    // Stop a job that was stopped on request or `completed` its duration
+    fn finish_job(&self, id: u64, completed: bool) -> Result<String, EngineError> {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let index = jobs
+                .iter()
+                .position(|job| job.id == id)
+                .ok_or_else(|| EngineError::NotFound(format!("no running job with id {}", id)))?;
+            jobs.remove(index)
+        };
+        self.save_state(|state| state.jobs.retain(|saved| saved.id != id));
+        let signal = Arc::clone(&job.run.signal);
+        let mut info = job.info();
+        job.run.stop(self);
+        // Include the ops of the workers' last batches
+        info.ops = signal.ops();
+        info!(job = id, name = %info.name, ops = info.ops, completed, "job stopped");
+        let timestamp_ms = unix_time_ms();
+        emit_lifecycle_event(
+            self,
+            if completed {
+                LifecycleEvent::JobCompleted { timestamp_ms, job: info }
+            } else {
+                LifecycleEvent::JobStopped { timestamp_ms, job: info }
+            },
+        );
+        Ok(format!("Job {} stopped", id))
+    }
+
+    // Apply a change to the saved state and write it to the state file, if there is one. Failures are
+    // logged: the run goes on either way.
+    fn save_state(&self, change: impl FnOnce(&mut SavedState)) {
+        let Some(file) = &self.state_file else {
+            return;
+        };
+        let mut state = self.saved_state.lock().unwrap();
+        change(&mut state);
+        state.saved_ms = unix_time_ms();
+        if let Err(e) = file.save(&state) {
+            warn!(path = %file.path().display(), "failed to save the run state: {}", e);
+        }
+    }
+
+    // Write the state file as it is now, e.g. right after startup so it stops describing the last agent's run
+    pub fn write_state_file(&self) {
+        self.save_state(|_| {});
+    }
+
+    // Pick up the run, jobs, and schedules of a state file written before a restart (--resume). A job with a
+    // duration_secs only runs for what is left of it; jobs that would have completed are skipped. Returns
+    // what couldn't be resumed.
+    pub fn resume(self: &Arc<Self>, saved: SavedState) -> Vec<String> {
+        let mut failed = Vec::new();
+        if saved.workers != self.num_cores {
+            warn!(saved = saved.workers, workers = self.num_cores, "resuming with a different worker count");
+        }
+        let update = ConfigUpdate {
+            worker_ops: Some(saved.worker_ops),
+        };
+        if let Err(e) = self.update_config(&update) {
+            failed.push(format!("worker_ops {}: {}", saved.worker_ops, e));
+        }
+        if let Some(request) = saved.run {
+            match self.start_run(&request) {
+                Ok(message) => info!("resumed the saved run: {}", message),
+                Err(e) => failed.push(format!("run ({:?}): {}", request.mode, e)),
+            }
+        }
+        let now_ms = unix_time_ms();
+        for job in saved.jobs {
+            let mut request = job.request;
+            let name = request.name.clone().unwrap_or_else(|| format!("job-{}", job.id));
+            if let Some(duration_secs) = request.duration_secs {
+                let remaining = duration_secs - now_ms.saturating_sub(job.started_ms) as f64 / 1000.0;
+                if remaining <= 0.0 {
+                    info!(job = %name, "saved job would have completed by now; not resumed");
+                    continue;
+                }
+                request.duration_secs = Some(remaining);
+            }
+            // Keep the name it had, rather than the default of the new id
+            request.name = Some(name.clone());
+            match self.create_job(&request) {
+                Ok(info) => info!(job = info.id, name = %info.name, "resumed the saved job"),
+                Err(e) => failed.push(format!("job {}: {}", name, e)),
+            }
+        }
+        for schedule in saved.schedules {
+            if let Err(e) = self.create_schedule(&schedule.request) {
+                failed.push(format!("schedule {}: {}", schedule.id, e));
+            }
+        }
+        failed
+    }
+
+    fn check_schedule(&self, request: &ScheduleRequest) -> Result<Schedule, EngineError> {
+        let windows = request.windows();
+        let mut errors = windows.as_ref().err().cloned().unwrap_or_default();
+        if let Err(invalid) = self.run_config(&request.start) {
+            errors.extend(invalid);
+        }
+        for (field, set) in [
+            ("start_at", request.start.options.start_at.is_some()),
+            ("start_delay_ms", request.start.options.start_delay_ms.is_some()),
+        ] {
+            if set {
+                let message = format!("{} doesn't apply to schedules; runs start with their windows", field);
+                errors.push(FieldError::new(field, message));
+            }
+        }
+        match windows {
+            Ok((cron, length_ms)) if errors.is_empty() => {
+                let id = self.next_schedule_id.load(Ordering::Relaxed);
+                Ok(Schedule::new(id, request.clone(), cron, length_ms))
+            }
+            _ => Err(EngineError::Validation(errors)),
+        }
+    }
+
+    // Check a schedule request without adding it; reports every invalid field
+    pub fn validate_schedule(&self, request: &ScheduleRequest) -> Result<(), EngineError> {
+        self.check_schedule(request).map(|_| ())
+    }
+
+    // Add a schedule. Its run starts with the next window (right away, within a second, if one is in progress).
+    pub fn create_schedule(self: &Arc<Self>, request: &ScheduleRequest) -> Result<ScheduleInfo, EngineError> {
+        let mut schedule = self.check_schedule(request)?;
+        schedule.id = self.next_schedule_id.fetch_add(1, Ordering::Relaxed);
+        let info = schedule.info(self.clock_ms().unwrap_or_else(unix_time_ms));
+        info!(schedule = info.id, name = %info.name, mode = ?info.mode, "schedule added");
+        let saved = SavedSchedule {
+            id: schedule.id,
+            request: request.clone(),
+        };
+        self.schedules.lock().unwrap().push(schedule);
+        self.save_state(|state| state.schedules.push(saved));
+        // The test harness runs the schedules on its virtual clock instead
+        if self.harness.is_none() && !self.scheduler_started.swap(true, Ordering::Relaxed) {
+            self.spawn_scheduler();
+        }
+        Ok(info)
+    }
+
+    // Schedules, oldest first
+    pub fn schedules(&self) -> Vec<ScheduleInfo> {
+        let now_ms = self.clock_ms().unwrap_or_else(unix_time_ms);
+        self.schedules.lock().unwrap().iter().map(|schedule| schedule.info(now_ms)).collect()
+    }
+
+    // Remove a schedule, stopping the run of its window in progress; blocks until the workers have exited
+    pub fn delete_schedule(&self, id: u64) -> Result<String, EngineError> {
+        let schedule = {
+            let mut schedules = self.schedules.lock().unwrap();
+            let index = schedules
+                .iter()
+                .position(|schedule| schedule.id == id)
+                .ok_or_else(|| EngineError::NotFound(format!("no schedule with id {}", id)))?;
+            schedules.remove(index)
+        };
+        self.save_state(|state| state.schedules.retain(|saved| saved.id != id));
+        info!(schedule = id, "schedule deleted");
+        match schedule.active_run() {
+            Some(signal) if self.stop_scheduled_run(&signal) => {
+                Ok(format!("Schedule {} deleted; its run was stopped", id))
+            }
+            _ => Ok(format!("Schedule {} deleted", id)),
+        }
+    }
+
+    // Start and stop the runs of the windows that begin or end at now_ms. The schedules lock isn't held while
+    // a run starts (which calibrates first) or stops.
+    pub(crate) fn run_schedules(self: &Arc<Self>, now_ms: u64) {
+        let actions: Vec<ScheduleAction> =
+            self.schedules.lock().unwrap().iter_mut().filter_map(|schedule| schedule.due(now_ms)).collect();
+        for action in actions {
+            match action {
+                ScheduleAction::Start(id, request) => {
+                    let result = self.start_run_at(&request, now_ms).map_err(|e| e.message()).and_then(|message| {
+                        info!(schedule = id, "scheduled window: {}", message);
+                        let run = self.run.lock().unwrap();
+                        let signal = run.as_ref().map(|run| Arc::clone(&run.signal));
+                        signal.ok_or_else(|| "the run stopped".to_string())
+                    });
+                    if let Err(e) = &result {
+                        warn!(schedule = id, "scheduled window skipped: {}", e);
+                    }
+                    let mut schedules = self.schedules.lock().unwrap();
+                    if let Some(schedule) = schedules.iter_mut().find(|schedule| schedule.id == id) {
+                        schedule.started(result);
+                    }
+                }
+                ScheduleAction::Stop(signal) => {
+                    if self.stop_scheduled_run(&signal) {
+                        info!("scheduled window ended; run stopped");
+                    }
+                }
+            }
+        }
+    }
+
+    // Stop the run if it is still the one a window started (same mode; a run restarted by hand is left alone)
+    fn stop_scheduled_run(&self, signal: &Arc<RunSignal>) -> bool {
+        let mut run = self.run.lock().unwrap();
+        if !run.as_ref().is_some_and(|active| Arc::ptr_eq(&active.signal, signal)) {
+            return false;
+        }
+        self.stop_run(&mut run);
+        true
+    }
+
+    fn spawn_scheduler(self: &Arc<Self>) {
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat("scheduler".to_string());
+        thread::spawn(move || {
+            supervise(&engine, &heartbeat, None, || {
+                loop {
+                    heartbeat.beat();
+                    engine.run_schedules(unix_time_ms());
+                    thread::sleep(Duration::from_secs(1));
+                }
+            })
+        });
+    }
+
+    // Whether any workers are running: the stress test or a job
+    pub(crate) fn is_busy(&self) -> bool {
+        self.is_running.load(Ordering::Relaxed) || !self.jobs.lock().unwrap().is_empty()
+    }
+
+    // Measure the noise floor before a run starts (while no workers are loading the CPU)
+    fn calibrate(&self) {
+        if self.simulated_ops_per_sec.is_some() {
+            return;
+        }
+        let quality = measure_noise_floor();
+        info!(
+            grade = ?quality.grade,
+            noise_percent = quality.noise_percent,
+            calibration_ops_per_sec = quality.calibration_ops_per_sec,
+            "measured the noise floor"
+        );
+        *self.measurement_quality.lock().unwrap() = Some(quality);
+    }
+
+    // Measure the single-core baseline (POST /calibrate) on an idle agent and keep it for normalized
+    // scores. Blocks for the length of the measurement; a simulated agent reports its per-core rate.
+    pub fn measure_baseline(&self, duration_ms: Option<u64>) -> Result<Baseline, EngineError> {
+        let duration_ms = duration_ms.unwrap_or(DEFAULT_CALIBRATION_MS);
+        if !CALIBRATION_MS_RANGE.contains(&duration_ms) {
+            return Err(EngineError::Invalid(format!(
+                "duration_ms must be between {} and {}",
+                CALIBRATION_MS_RANGE.start(),
+                CALIBRATION_MS_RANGE.end()
+            )));
+        }
+
+        // Holding the run lock keeps runs from starting during the measurement
+        let run = self.run.lock().unwrap();
+        self.check_no_benchmark()?;
+        if run.is_some() || !self.jobs.lock().unwrap().is_empty() {
+            return Err(EngineError::Conflict(
+                "calibration needs an idle agent; stop the stress test and any jobs first".to_string(),
+            ));
+        }
+
+        let ops_per_second = match self.simulated_ops_per_sec {
+            Some(per_core_ops_per_sec) => per_core_ops_per_sec as u64,
+            None => {
+                let cpu = self.cores.as_ref().and_then(|cores| cores.first().copied());
+                let duration = Duration::from_millis(duration_ms);
+                thread::scope(|scope| {
+                    scope
+                        .spawn(|| {
+                            if let Some(cpu) = cpu
+                                && let Err(e) = pin_current_thread(cpu)
+                            {
+                                warn!(cpu, "calibration: failed to pin: {}", e);
+                            }
+                            measure_baseline(duration)
+                        })
+                        .join()
+                        .expect("Calibration thread panicked")
+                })
+            }
+        };
+        let baseline = Baseline {
+            ops_per_second,
+            measured_ms: self.clock_ms().unwrap_or_else(unix_time_ms),
+            duration_ms,
+        };
+        info!(ops_per_second, duration_ms, "measured the single-core baseline");
+        *self.baseline.lock().unwrap() = Some(baseline);
+        Ok(baseline)
+    }
+
+    // Check a benchmark request: its phases, and the start request of each; reports every invalid field
+    fn check_benchmark(&self, request: &BenchmarkRequest) -> Result<Vec<(StartCpuRequest, RunConfig)>, EngineError> {
+        let phases = request.phases().map_err(EngineError::Validation)?;
+        let mut errors: Vec<FieldError> = Vec::new();
+        let mut checked = Vec::new();
+        for phase in phases {
+            match self.run_config(&phase) {
+                Ok(config) => checked.push((phase, config)),
+                // The phases share their options, so they share most of their errors
+                Err(invalid) => {
+                    for error in invalid {
+                        if !errors.contains(&error) {
+                            errors.push(error);
+                        }
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(checked)
+        } else {
+            Err(EngineError::Validation(errors))
+        }
+    }
+
+    // Check a benchmark request without running it; reports every invalid field
+    pub fn validate_benchmark(&self, request: &BenchmarkRequest) -> Result<(), EngineError> {
+        self.check_benchmark(request).map(|_| ())
+    }
+
+    // Run the phases of a benchmark one after another on an idle agent and summarize them; blocks until the
+    // report is ready. Runs, jobs, and calibrations are refused meanwhile, and POST /end-cpu aborts it.
+    pub fn run_benchmark(self: &Arc<Self>, request: &BenchmarkRequest) -> Result<BenchmarkReport, EngineError> {
+        let phases = self.check_benchmark(request)?;
+        let signal = Arc::new(RunSignal::default());
+        {
+            // Lock order: run, then jobs and pending_start (as in start_run), then benchmark
+            let run = self.run.lock().unwrap();
+            let busy = run.is_some()
+                || !self.jobs.lock().unwrap().is_empty()
+                || self.pending_start.lock().unwrap().is_some();
+            let mut benchmark = self.benchmark.lock().unwrap();
+            if benchmark.is_some() {
+                return Err(EngineError::Conflict("a benchmark is already running on this agent".to_string()));
+            }
+            if busy {
+                return Err(EngineError::Conflict(
+                    "a benchmark needs an idle agent; stop the stress test (or armed start) and any jobs first"
+                        .to_string(),
+                ));
+            }
+            *benchmark = Some(Arc::clone(&signal));
+        }
+
+        let heartbeat = self.register_heartbeat("benchmark".to_string());
+        let report = self.benchmark_phases(request, phases, &signal, &heartbeat);
+        self.unregister_heartbeat(&heartbeat);
+        *self.benchmark.lock().unwrap() = None;
+        report
+    }
+
+    fn benchmark_phases(
+        self: &Arc<Self>,
+        request: &BenchmarkRequest,
+        phases: Vec<(StartCpuRequest, RunConfig)>,
+        signal: &RunSignal,
+        heartbeat: &Heartbeat,
+    ) -> Result<BenchmarkReport, EngineError> {
+        let aborted = || EngineError::Conflict("the benchmark was stopped by POST /end-cpu".to_string());
+        let duration = Duration::from_secs(request.duration_secs());
+        let cooldown = Duration::from_secs(request.cooldown_secs());
+        let (phase_secs, cooldown_secs) = (duration.as_secs(), cooldown.as_secs());
+        info!(phases = phases.len(), phase_secs, cooldown_secs, "benchmark started");
+        let started_ms = self.now_ms();
+        let mut results = Vec::new();
+        for (index, (phase, config)) in phases.into_iter().enumerate() {
+            if index > 0 && !self.benchmark_wait(signal, heartbeat, cooldown) {
+                return Err(aborted());
+            }
+            // The first sampling interval and the soft start don't show the phase's steady state
+            let skip_ms = config.ramp.as_millis() as u64 + 1000;
+            let cores: Vec<usize> = self.worker_cores(&config).into_iter().filter_map(|(_, cpu)| cpu).collect();
+            info!(mode = ?phase.mode, workload = ?config.workload, "benchmark phase started");
+            {
+                let mut run = self.run.lock().unwrap();
+                if signal.is_stopped() {
+                    return Err(aborted());
+                }
+                self.start_locked(&mut run, config, true);
+            }
+            let phase_start_ms = self.now_ms();
+            if !self.benchmark_wait(signal, heartbeat, duration) {
+                return Err(aborted());
+            }
+            let end_ms = self.now_ms();
+            self.stop_run(&mut self.run.lock().unwrap());
+
+            let samples: Vec<HistorySample> = self
+                .history(Some(duration.as_secs() as usize + 2))
+                .into_iter()
+                .filter(|sample| sample.timestamp_ms > phase_start_ms + skip_ms && sample.timestamp_ms <= end_ms)
+                .collect();
+            results.push(BenchmarkPhase::new(&phase, phase_start_ms, &samples, &cores));
+        }
+        info!("benchmark finished");
+        Ok(BenchmarkReport::new(started_ms, self.now_ms(), request, results))
+    }
+
+    // Wait out a benchmark phase or cooldown (the test harness advances its virtual clock instead); false if
+    // the benchmark was stopped
+    fn benchmark_wait(self: &Arc<Self>, signal: &RunSignal, heartbeat: &Heartbeat, duration: Duration) -> bool {
+        if self.advance_clock(duration.as_millis() as u64).is_some() {
+            return !signal.is_stopped();
+        }
+        signal.sleep_through(heartbeat, duration)
+    }
+
+    // Unix ms, on the virtual clock of a test harness engine
+    fn now_ms(&self) -> u64 {
+        self.clock_ms().unwrap_or_else(unix_time_ms)
+    }
+
+    // Stop the CPU stress test and cancel an armed one (idempotent); blocks until every worker has exited
+    pub fn stop(&self) -> String {
+        let mut run = self.run.lock().unwrap();
+        if let Some(benchmark) = self.benchmark.lock().unwrap().as_ref() {
+            info!("benchmark stopped");
+            benchmark.stop();
+        }
+        if self.cancel_pending_start() {
+            info!("armed start cancelled");
+            if run.is_none() {
+                return "Armed CPU stress test cancelled".to_string();
+            }
+            return format!("{}; armed start cancelled", self.stop_run(&mut run));
+        }
+        self.stop_run(&mut run)
+    }
+
+    // Stop the run because one of its auto-stop guards tripped, unless a start request has replaced it
+    // (and reset the guards) since
+    pub(crate) fn stop_by_guard(&self, trip: GuardTrip) {
+        let mut run = self.run.lock().unwrap();
+        if run.is_none() || !self.guard_window.lock().unwrap().is_tripped() {
+            return;
+        }
+        warn!(rule = ?trip.rule, reason = %trip.reason, "auto-stop guard tripped; stopping the run");
+        *self.guard_trip.lock().unwrap() = Some(trip.clone());
+        emit_lifecycle_event(self, LifecycleEvent::GuardTripped(trip));
+        self.stop_run(&mut run);
+    }
+
+    fn stop_run(&self, run: &mut Option<ActiveRun>) -> String {
+        if let Some(active) = run.take() {
+            active.stop(self);
+            let mode = self.config.borrow().mode;
+            emit_lifecycle_event(self, LifecycleEvent::Stopped { timestamp_ms: unix_time_ms(), mode });
+        }
+        self.is_running.store(false, Ordering::Relaxed);
+        self.save_state(|state| state.run = None);
+        // Reset all counters when stopping
+        self.current_counter.store(0, Ordering::Relaxed);
+        self.operations_per_second.store(0, Ordering::Relaxed);
+        self.memory_counter.store(0, Ordering::Relaxed);
+        self.memory_bytes_per_second.store(0, Ordering::Relaxed);
+        info!("CPU stress test stopped");
+        "CPU stress test stopped".to_string()
+    }
+
+    // Fire one burst on every core (bursty mode with burst_trigger = "external")
+    pub fn trigger_burst(&self, duration_ms: Option<u64>) -> Result<String, EngineError> {
+        let run = self.run.lock().unwrap();
+        let config = self.config.borrow().clone();
+        let run = run
+            .as_ref()
+            .filter(|_| config.mode == ExecutionMode::Bursty && config.burst_trigger == BurstTrigger::External)
+            .ok_or_else(|| {
+                let message = "bursts can only be triggered while running bursty mode with burst_trigger \"external\"";
+                EngineError::Conflict(message.to_string())
+            })?;
+        if duration_ms == Some(0) {
+            return Err(EngineError::Invalid("duration_ms must be positive".to_string()));
+        }
+
+        let generation = run.signal.trigger(duration_ms);
+        Ok(format!("Burst {} triggered", generation))
+    }
+
+    // Apply a PATCH /config update. A new worker_ops becomes the default for later runs and jobs and is
+    // handed to the running workers, which use it from their next worker process (or burst) on.
+    pub fn update_config(&self, update: &ConfigUpdate) -> Result<String, EngineError> {
+        let Some(worker_ops) = update.worker_ops else {
+            return Ok("Nothing to update".to_string());
+        };
+        validate_worker_ops(worker_ops).map_err(EngineError::Invalid)?;
+
+        let run = self.run.lock().unwrap();
+        self.worker_ops.store(worker_ops, Ordering::Relaxed);
+        self.save_state(|state| state.worker_ops = worker_ops);
+        let jobs = self.jobs.lock().unwrap();
+        for job in jobs.iter() {
+            job.config.send_modify(|config| config.worker_ops = worker_ops);
+        }
+        if run.is_some() {
+            self.config.send_modify(|config| config.worker_ops = worker_ops);
+            Ok(format!("worker_ops set to {} (applied to the current run)", worker_ops))
+        } else if !jobs.is_empty() {
+            Ok(format!("worker_ops set to {} (applied to {} running job(s))", worker_ops, jobs.len()))
+        } else {
+            Ok(format!("worker_ops set to {}", worker_ops))
+        }
+    }
+
+    // Current run state and latest metrics, shared by the HTTP and gRPC status endpoints
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let cpu_utilization = self.cpu_utilization.lock().unwrap().clone();
+        let config = self.config.borrow().clone();
+        let baseline = *self.baseline.lock().unwrap();
+        let mut snapshot = MetricsSnapshot {
+            running: self.is_running.load(Ordering::Relaxed),
+            mode: config.mode,
+            utilization: config.utilization,
+            ops_per_second: self.operations_per_second.load(Ordering::Relaxed),
+            burst_ops_per_second: self.burst_ops_per_second.load(Ordering::Relaxed),
+            cpu_utilization_percent: cpu_utilization.overall_percent,
+            per_core_utilization_percent: cpu_utilization.per_core_percent,
+            temperatures: self.temperatures.lock().unwrap().clone(),
+            measurement_quality: *self.measurement_quality.lock().unwrap(),
+            baseline,
+            score: None,
+            workers: self.worker_cores(&config).len(),
+            physical_cores_only: config.physical_cores_only,
+            smt_siblings: self.topology.smt_siblings(),
+            cpu_allocation: self.cpu_allocation,
+            nice: config.nice,
+            workload: config.workload,
+            memory_gb_per_second: self.memory_bytes_per_second.load(Ordering::Relaxed) as f64 / 1e9,
+            percentiles: RunPercentiles::new(&self.histograms.lock().unwrap()),
+            guard_trip: self.guard_trip.lock().unwrap().clone(),
+            scheduled_start_ms: self.pending_start.lock().unwrap().as_ref().map(|pending| pending.start_ms),
+            power: self.power.lock().unwrap().clone(),
+            energy: self.energy.lock().unwrap().report(),
+            thread_restarts: self.thread_restarts.load(Ordering::Relaxed),
+        };
+        snapshot.score = baseline.and_then(|baseline| NormalizedScore::new(&baseline, &snapshot, snapshot.workers));
+        snapshot
+    }
+
+    // Per-second samples, oldest first; `last` keeps only the most recent N
+    pub fn history(&self, last: Option<usize>) -> Vec<HistorySample> {
+        let history = self.history.lock().unwrap();
+        let skip = last.map_or(0, |last| history.len().saturating_sub(last));
+        history.iter().skip(skip).cloned().collect()
+    }
+
+    // Histograms since the current (or most recent) run started
+    pub fn histograms(&self) -> Histograms {
+        self.histograms.lock().unwrap().clone()
+    }
+
+    // Worker processes since the current (or most recent) run started, with the most recent `last` records
+    pub fn process_stats(&self, last: usize) -> ProcessStatsReport {
+        self.process_stats.lock().unwrap().report(last)
+    }
+
+    // Wakeup latency measured by the latency probes of the current (or most recent) run and of the jobs
+    pub fn latency(&self) -> LatencyReport {
+        LatencyReport::new(&self.latency.lock().unwrap())
+    }
+
+    // Burst start/end events from now on; slow receivers miss events rather than block workers
+    pub fn subscribe_burst_events(&self) -> tokio::sync::broadcast::Receiver<BurstEvent> {
+        self.burst_events.subscribe()
+    }
+
+    // Agents on the LAN that announced themselves recently, this one included
+    pub fn peers(&self) -> Result<Vec<Peer>, EngineError> {
+        let peers = self.peers.as_ref().ok_or_else(|| {
+            EngineError::Conflict("peer discovery is off; start the agent with --discovery".to_string())
+        })?;
+        Ok(peers.peers(unix_time_ms()))
+    }
+
+    // Status of every sampling interval from now on
+    pub fn subscribe_samples(&self) -> tokio::sync::broadcast::Receiver<HistorySample> {
+        self.sample_events.subscribe()
+    }
+
+    // Run/job lifecycle and ops-drop events from now on
+    pub fn subscribe_lifecycle_events(&self) -> tokio::sync::broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle_events.subscribe()
+    }
+
+    // Fast-forward the virtual clock of a test harness engine; returns the new time (Unix ms)
+    pub fn advance_clock(self: &Arc<Self>, ms: u64) -> Option<u64> {
+        self.harness.as_ref().map(|harness| harness.advance(self, ms))
+    }
+
+    // Current time of a test harness engine's virtual clock (Unix ms)
+    pub fn clock_ms(&self) -> Option<u64> {
+        self.harness.as_ref().map(TestHarness::now_ms)
+    }
+
+    // Check background thread heartbeats; `require_started` also fails threads that haven't beaten yet
+    pub fn health(&self, require_started: bool) -> HealthReport {
+        let heartbeats = self.heartbeats.lock().unwrap();
+        let uptime_ms = self.started_at.elapsed().as_millis() as u64;
+
+        let failing: Vec<ThreadHealth> = heartbeats
+            .iter()
+            .filter(|heartbeat| match heartbeat.age_ms() {
+                Some(age_ms) => age_ms > HEARTBEAT_TIMEOUT_MS,
+                None => require_started || uptime_ms > HEARTBEAT_TIMEOUT_MS,
+            })
+            .map(|heartbeat| ThreadHealth {
+                name: heartbeat.name.clone(),
+                last_heartbeat_ms_ago: heartbeat.age_ms(),
+            })
+            .collect();
+        let restarted: Vec<RestartedThread> = heartbeats
+            .iter()
+            .filter_map(|heartbeat| {
+                let restarts = heartbeat.restarts.load(Ordering::Relaxed);
+                (restarts > 0).then(|| RestartedThread {
+                    name: heartbeat.name.clone(),
+                    restarts,
+                    last_panic: heartbeat.last_panic.lock().unwrap().clone(),
+                })
+            })
+            .collect();
+
+        HealthReport {
+            status: if failing.is_empty() { "ok" } else { "unhealthy" },
+            threads: heartbeats.len(),
+            failing,
+            restarts: self.thread_restarts.load(Ordering::Relaxed),
+            restarted,
+        }
+    }
+
+    // Sample metrics once per second in a background thread (the test harness samples on its
+    // virtual clock instead)
+    pub fn spawn_sampler(self: &Arc<Self>) {
+        if self.harness.is_some() {
+            return;
+        }
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat("sampler".to_string());
+        thread::spawn(move || {
+            supervise(&engine, &heartbeat, None, || burst_sampler(Arc::clone(&engine), Arc::clone(&heartbeat)));
+        });
+    }
+
+    // Fire a burst for every line read from a serial device (or FIFO)
+    pub fn spawn_serial_trigger(self: &Arc<Self>, path: std::path::PathBuf) {
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat("serial-trigger".to_string());
+        thread::spawn(move || {
+            supervise(&engine, &heartbeat, None, || {
+                serial_trigger_reader(Arc::clone(&engine), Arc::clone(&heartbeat), path.clone())
+            });
+        });
+    }
+
+    // Fire a burst on every rising edge of a GPIO value file
+    pub fn spawn_gpio_trigger(self: &Arc<Self>, path: std::path::PathBuf) {
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat("gpio-trigger".to_string());
+        thread::spawn(move || {
+            supervise(&engine, &heartbeat, None, || {
+                gpio_trigger_poller(Arc::clone(&engine), Arc::clone(&heartbeat), path.clone())
+            });
+        });
+    }
+
+    // Rewrite a Prometheus textfile with the latest metrics every second
+    pub fn spawn_textfile_writer(self: &Arc<Self>, path: std::path::PathBuf) {
+        let engine = Arc::clone(self);
+        let heartbeat = self.register_heartbeat("textfile-writer".to_string());
+        thread::spawn(move || {
+            supervise(&engine, &heartbeat, None, || {
+                textfile_writer(Arc::clone(&engine), Arc::clone(&heartbeat), path.clone())
+            });
+        });
+    }
+
+    // Register a background thread for /healthz and /readyz checks
+    pub(crate) fn register_heartbeat(&self, name: String) -> Arc<Heartbeat> {
+        let heartbeat = Arc::new(Heartbeat {
+            name,
+            started_at: self.started_at,
+            last_beat_ms: AtomicU64::new(0),
+            restarts: AtomicU64::new(0),
+            last_panic: Mutex::new(None),
+        });
+        self.heartbeats.lock().unwrap().push(Arc::clone(&heartbeat));
+        heartbeat
+    }
+
+    fn unregister_heartbeat(&self, heartbeat: &Arc<Heartbeat>) {
+        self.heartbeats
+            .lock()
+            .unwrap()
+            .retain(|registered| !Arc::ptr_eq(registered, heartbeat));
+    }
+}
+
+// A background thread is considered dead if it hasn't beaten for this long
+const HEARTBEAT_TIMEOUT_MS: u64 = 30_000;
+
+// Background thread health; body of GET /healthz and GET /readyz
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct HealthReport {
+    pub status: &'static str,
+    pub threads: usize,
+    pub failing: Vec<ThreadHealth>,
+    // Panicked threads restarted since the agent started (a restarted thread is healthy again), and the
+    // current threads that were restarted
+    pub restarts: u64,
+    pub restarted: Vec<RestartedThread>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ThreadHealth {
+    pub name: String,
+    // None if the thread has never reported in
+    pub last_heartbeat_ms_ago: Option<u64>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct RestartedThread {
+    pub name: String,
+    pub restarts: u64,
+    pub last_panic: Option<String>,
+}
+
+// External burst triggers: bumping the generation wakes every burst coordinator for one burst
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TriggerState {
+    pub(crate) generation: u64,
+    // Burst length requested by the trigger, None = sample from the burst distribution
+    pub(crate) duration_ms: Option<u64>,
+}
+
+// Longest a trigger input blocks waiting for input before its thread beats its heartbeat again
+const TRIGGER_POLL_MS: u64 = 500;
+
+// Longest serial line kept while waiting for its newline; longer input is dropped
+const MAX_TRIGGER_LINE: usize = 4096;
+
+// Open a trigger input without blocking (a FIFO without a writer, a tty without carrier)
+#[cfg(unix)]
+fn open_trigger(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, libc::O_NONBLOCK);
+    options.open(path)
+}
+
+// Wait up to TRIGGER_POLL_MS for `events` on `file`; the events that occurred, empty on a timeout
+#[cfg(unix)]
+fn poll_trigger(file: &std::fs::File, events: libc::c_short) -> std::io::Result<libc::c_short> {
+    use std::os::fd::AsRawFd;
+
+    let mut fd = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events,
+        revents: 0,
+    };
+    // SAFETY: one valid pollfd, for a descriptor that stays open during the call
+    match unsafe { libc::poll(&mut fd, 1, TRIGGER_POLL_MS as libc::c_int) } {
+        0 => Ok(0),
+        result if result > 0 => Ok(fd.revents),
+        _ => match std::io::Error::last_os_error() {
+            e if e.kind() == std::io::ErrorKind::Interrupted => Ok(0),
+            e => Err(e),
+        },
+    }
+}
+
+// Serial trigger input: every line received fires a burst (a numeric line sets its duration in ms). Reads
+// wait in poll(2) with a timeout, so the heartbeat keeps beating while no input arrives.
+#[cfg(unix)]
+fn serial_trigger_reader(state: Arc<StressEngine>, heartbeat: Arc<Heartbeat>, path: std::path::PathBuf) {
+    use std::io::Read;
+
+    loop {
+        heartbeat.beat();
+
+        let mut file = match open_trigger(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!(path = %path.display(), "failed to open serial trigger: {}", e);
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+
+        let mut pending = Vec::new();
+        let mut buffer = [0u8; 512];
+        loop {
+            heartbeat.beat();
+            match poll_trigger(&file, libc::POLLIN) {
+                Ok(0) => continue,
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(path = %path.display(), "failed to wait for serial trigger: {}", e);
+                    break;
+                }
+            }
+            let read = match file.read(&mut buffer) {
+                // Device closed or disconnected
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted) => {
+                    continue;
+                }
+                Err(_) => break,
+            };
+            pending.extend_from_slice(&buffer[..read]);
+            while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let duration_ms = String::from_utf8_lossy(&line).trim().parse::<u64>().ok();
+                if let Err(e) = state.trigger_burst(duration_ms) {
+                    warn!("ignoring serial trigger: {}", e);
+                }
+            }
+            if pending.len() > MAX_TRIGGER_LINE {
+                pending.clear();
+            }
+        }
+
+        // Reopen after a short pause
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[cfg(not(unix))]
+fn serial_trigger_reader(_state: Arc<StressEngine>, heartbeat: Arc<Heartbeat>, path: std::path::PathBuf) {
+    warn!(path = %path.display(), "serial triggers are only supported on Unix");
+    loop {
+        heartbeat.beat();
+        thread::sleep(Duration::from_millis(TRIGGER_POLL_MS));
+    }
+}
+
+// Interval of reading a GPIO value file that doesn't support edge interrupts
+const GPIO_FALLBACK_POLL: Duration = Duration::from_millis(10);
+
+// GPIO trigger input: fire a burst on each rising edge (0 -> 1) of a sysfs GPIO value file. With `edge`
+// set to "rising", the kernel wakes poll(2) with POLLPRI on each edge; a pin without interrupt support is
+// read every GPIO_FALLBACK_POLL instead.
+#[cfg(unix)]
+fn gpio_trigger_poller(state: Arc<StressEngine>, heartbeat: Arc<Heartbeat>, path: std::path::PathBuf) {
+    use std::io::{Read, Seek};
+
+    let edge = path.with_file_name("edge");
+    let interrupts = match std::fs::write(&edge, "rising") {
+        Ok(()) => true,
+        Err(e) => {
+            warn!(path = %edge.display(), "GPIO edge interrupts unavailable ({}), reading the value instead", e);
+            false
+        }
+    };
+
+    let mut last_high = false;
+    let mut last_error_reported = false;
+    loop {
+        heartbeat.beat();
+
+        let mut file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                if !last_error_reported {
+                    warn!(path = %path.display(), "failed to read GPIO trigger: {}", e);
+                    last_error_reported = true;
+                }
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+        last_error_reported = false;
+
+        loop {
+            heartbeat.beat();
+            // The value is read (from the start) before every wait, which also clears the pending edge
+            let mut value = String::new();
+            if file.rewind().and_then(|()| file.read_to_string(&mut value)).is_err() {
+                break;
+            }
+            let high = value.trim() == "1";
+
+            if interrupts {
+                match poll_trigger(&file, libc::POLLPRI | libc::POLLERR) {
+                    Ok(0) => {}
+                    Ok(_) => {
+                        // Edges are rising only; a short pulse may already be low again when read
+                        if let Err(e) = state.trigger_burst(None) {
+                            warn!("ignoring GPIO trigger: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        warn!(path = %path.display(), "failed to wait for GPIO trigger: {}", e);
+                        break;
+                    }
+                }
+            } else {
+                if high
+                    && !last_high
+                    && let Err(e) = state.trigger_burst(None)
+                {
+                    warn!("ignoring GPIO trigger: {}", e);
+                }
+                last_high = high;
+                thread::sleep(GPIO_FALLBACK_POLL);
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(not(unix))]
+fn gpio_trigger_poller(_state: Arc<StressEngine>, heartbeat: Arc<Heartbeat>, path: std::path::PathBuf) {
+    warn!(path = %path.display(), "GPIO triggers are only supported on Unix");
+    loop {
+        heartbeat.beat();
+        thread::sleep(Duration::from_millis(TRIGGER_POLL_MS));
+    }
+}
+
+pub fn unix_time_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::GuardRule;
+
+    fn harness_engine(num_cores: usize) -> Arc<StressEngine> {
+        Arc::new(StressEngine::test_harness(EngineOptions {
+            num_cores,
+            ..EngineOptions::default()
+        }))
+    }
+
+    fn request(json: &str) -> StartCpuRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn config_updates_change_the_default_and_the_current_run() {
+        let engine = harness_engine(1);
+        let update = |worker_ops| engine.update_config(&ConfigUpdate { worker_ops: Some(worker_ops) });
+
+        update(1000).unwrap();
+        engine.start_run(&request(r#"{"mode":"fresh-process"}"#)).unwrap();
+        assert_eq!(engine.config.borrow().worker_ops, 1000);
+
+        assert!(update(0).is_err());
+        assert!(update(5000).unwrap().contains("applied to the current run"));
+        assert_eq!(engine.config.borrow().worker_ops, 5000);
+
+        // A run's own worker_ops wins over the agent default
+        engine.start_run(&request(r#"{"mode":"bursty","worker_ops":300}"#)).unwrap();
+        assert_eq!(engine.config.borrow().worker_ops, 300);
+        assert_eq!(engine.worker_ops.load(Ordering::Relaxed), 5000);
+    }
+
+    #[test]
+    fn start_request_options_are_flattened() {
+        let parsed = request(r#"{"mode":"bursty","utilization":60,"burst_distribution":"fixed"}"#);
+        assert_eq!(parsed.mode, ExecutionMode::Bursty);
+        assert_eq!(parsed.options.utilization, Some(60));
+        assert_eq!(parsed.options.burst_distribution, Some(BurstDistribution::Fixed));
+
+        // Unset options are left out so the client can forward requests verbatim
+        let json = serde_json::to_string(&parsed).unwrap();
+        assert_eq!(json, r#"{"mode":"bursty","utilization":60,"burst_distribution":"fixed"}"#);
+    }
+
+    #[test]
+    fn run_config_validates_and_fills_defaults() {
+        let config = RunConfig::from_request(&request(r#"{"mode":"throttled"}"#), 20_000).unwrap();
+        assert_eq!(config.utilization, 50);
+        assert_eq!(config.duty_cycle_ms, 100);
+        assert_eq!(config.worker_ops, 20_000);
+        let config = RunConfig::from_request(&request(r#"{"mode":"bursty","worker_ops":500}"#), 20_000).unwrap();
+        assert_eq!(config.worker_ops, 500);
+        let config = RunConfig::from_request(&request(r#"{"mode":"threaded","ramp_secs":30}"#), 20_000).unwrap();
+        let delays: Vec<u64> = (0..4).map(|worker| config.ramp_delay(worker, 4).as_secs()).collect();
+        assert_eq!(delays, [0, 7, 15, 22]);
+        let stream = RunConfig::from_request(&request(r#"{"mode":"throttled","workload":"stream"}"#), 20_000).unwrap();
+        assert_eq!((stream.workload, stream.stream_array_mb), (Workload::Stream, DEFAULT_STREAM_ARRAY_MB));
+        assert!(config.needs_new_workers(&stream) && !stream.needs_new_workers(&stream));
+
+        for invalid in [
+            r#"{"mode":"fresh-process","worker_ops":0}"#,
+            r#"{"mode":"throttled","duty_cycle_ms":5}"#,
+            r#"{"mode":"bursty","schedule_seed":1}"#,
+            r#"{"mode":"bursty","schedule_seed":1,"schedule_epoch_ms":18446744073709551615}"#,
+            r#"{"mode":"bursty","schedule_seed":1,"schedule_epoch_ms":0}"#,
+            r#"{"mode":"bursty","burst_trigger":"external","schedule_seed":1,"schedule_epoch_ms":0}"#,
+            r#"{"mode":"bursty","burst_min_secs":2,"burst_max_secs":1}"#,
+            r#"{"mode":"threaded","ramp_secs":-1}"#,
+            r#"{"mode":"threaded","nice":20}"#,
+            r#"{"mode":"throttled","utilization":250}"#,
+            r#"{"mode":"threaded","latency_probe_us":5}"#,
+            r#"{"mode":"bursty","workload":"stream"}"#,
+            r#"{"mode":"threaded","workload":"stream","stream_array_mb":0}"#,
+            r#"{"mode":"threaded","stop_if_ops_below":0}"#,
+            r#"{"mode":"threaded","stop_if_drop_percent_exceeds":100}"#,
+            r#"{"mode":"threaded","stop_if_drop_percent_exceeds":10,"guard_window_secs":0}"#,
+            r#"{"mode":"threaded","start_at":"tomorrow"}"#,
+            r#"{"mode":"threaded","start_at":"2030-01-01T00:00:00Z","start_delay_ms":10}"#,
+            r#"{"mode":"threaded","start_delay_ms":86400001}"#,
+        ] {
+            assert!(RunConfig::from_request(&request(invalid), 20_000).is_err(), "accepted {}", invalid);
+        }
+
+        // Every invalid option is reported, not just the first
+        let invalid = r#"{"mode":"bursty","utilization":101,"burst_min_secs":0,"schedule_seed":1,"nice":-21}"#;
+        let fields: Vec<String> = RunConfig::from_request(&request(invalid), 20_000)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.field)
+            .collect();
+        assert_eq!(fields, ["burst_min_secs", "schedule_epoch_ms", "utilization", "nice"]);
+    }
+
+    #[test]
+    fn harness_engine_reports_ops_on_virtual_time() {
+        let engine = harness_engine(2);
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        engine.advance_clock(3000);
+
+        let status = engine.snapshot();
+        assert!(status.running);
+        assert_eq!(status.ops_per_second, 2 * HARNESS_OPS_PER_SEC as u64);
+        assert_eq!(status.cpu_utilization_percent, 100.0);
+        assert_eq!(engine.history(None).len(), 3);
+        assert_eq!(engine.history(Some(1)).len(), 1);
+        let ops = status.percentiles.ops_per_second;
+        let expected = Some(2.0 * HARNESS_OPS_PER_SEC);
+        assert_eq!((ops.samples, ops.min, ops.p50, ops.p99), (3, expected, expected, expected));
+
+        engine.stop();
+        engine.advance_clock(1000);
+        let status = engine.snapshot();
+        assert!(!status.running);
+        assert_eq!(status.ops_per_second, 0);
+    }
+
+    #[test]
+    fn auto_stop_guards_stop_the_run_and_record_why() {
+        let engine = harness_engine(1);
+        let guarded = r#"{"mode":"throttled","utilization":50,"stop_if_ops_below":200000,"guard_window_secs":2}"#;
+        engine.start_run(&request(guarded)).unwrap();
+        engine.advance_clock(1000);
+        assert!(engine.snapshot().running);
+        engine.advance_clock(1000);
+        let status = engine.snapshot();
+        assert!(!status.running);
+        let trip = status.guard_trip.unwrap();
+        assert_eq!((trip.rule, trip.window_ops_per_second), (GuardRule::OpsFloor, 150_000));
+
+        // A new run clears the reason, and new settings for a running run start the window over
+        let drop_guard = r#"{"mode":"throttled","utilization":100,"stop_if_drop_percent_exceeds":20}"#;
+        engine.start_run(&request(drop_guard)).unwrap();
+        assert!(engine.snapshot().guard_trip.is_none());
+        engine.advance_clock(15_000);
+        engine.start_run(&request(&drop_guard.replace("100", "50"))).unwrap();
+        engine.advance_clock(15_000);
+        assert!(engine.snapshot().running);
+    }
+
+    #[test]
+    fn armed_runs_start_at_their_start_time() {
+        let engine = harness_engine(1);
+        let start_ms = engine.clock_ms().unwrap() + 5000;
+        let armed = format!(r#"{{"mode":"threaded","start_at":"{}"}}"#, crate::units::format_rfc3339(start_ms));
+        assert!(engine.start_run(&request(&armed)).unwrap().contains("armed"));
+        engine.advance_clock(4000);
+        let status = engine.snapshot();
+        assert_eq!((status.running, status.scheduled_start_ms), (false, Some(start_ms)));
+        engine.advance_clock(1000);
+        let status = engine.snapshot();
+        assert_eq!((status.running, status.scheduled_start_ms), (true, None));
+
+        // A stop cancels an armed run; a past start_at starts right away
+        engine.stop();
+        engine.start_run(&request(r#"{"mode":"threaded","start_delay_ms":1000}"#)).unwrap();
+        assert_eq!(engine.stop(), "Armed CPU stress test cancelled");
+        engine.advance_clock(2000);
+        assert!(!engine.snapshot().running);
+        engine.start_run(&request(r#"{"mode":"threaded","start_at":"2020-01-01T00:00:00Z"}"#)).unwrap();
+        assert!(engine.snapshot().running);
+    }
+
+    #[test]
+    fn saved_runs_and_jobs_resume_after_a_restart() {
+        let path = std::env::temp_dir().join(format!("resume-test-{}.json", std::process::id()));
+        let state_file = StateFile::new(&path);
+        let engine = |simulate| {
+            let options = EngineOptions {
+                num_cores: 4,
+                simulate,
+                cores: simulate.then(|| vec![0, 1, 2, 3]),
+                state_file: Some(StateFile::new(&path)),
+                ..EngineOptions::default()
+            };
+            Arc::new(if simulate { StressEngine::new(options) } else { StressEngine::test_harness(options) })
+        };
+
+        // The run, with the start time dropped and the agent's worker_ops
+        let first = engine(false);
+        first.update_config(&ConfigUpdate { worker_ops: Some(5000) }).unwrap();
+        let start = r#"{"mode":"throttled","utilization":30,"start_at":"2020-01-01T00:00:00Z"}"#;
+        first.start_run(&request(start)).unwrap();
+        let saved = state_file.load().unwrap().unwrap();
+        let run = saved.run.as_ref().unwrap();
+        assert_eq!((run.mode, run.options.utilization), (ExecutionMode::Throttled, Some(30)));
+        assert_eq!(run.options.start_at, None);
+        let resumed = engine(false);
+        assert!(resumed.resume(saved).is_empty());
+        let status = resumed.snapshot();
+        assert_eq!((status.running, status.mode, status.utilization), (true, ExecutionMode::Throttled, 30));
+        assert_eq!(resumed.worker_ops.load(Ordering::Relaxed), 5000);
+        resumed.stop();
+        assert!(state_file.load().unwrap().unwrap().run.is_none());
+
+        // Jobs, with what is left of their duration
+        let first = engine(true);
+        let job = |json| serde_json::from_str::<JobRequest>(json).unwrap();
+        first.create_job(&job(r#"{"name":"steady","mode":"threaded","cores":"0-1"}"#)).unwrap();
+        first.create_job(&job(r#"{"mode":"bursty","cores":"2-3","duration_secs":600}"#)).unwrap();
+        let saved = state_file.load().unwrap().unwrap();
+        for job in first.jobs() {
+            first.stop_job(job.id).unwrap();
+        }
+        assert!(state_file.load().unwrap().unwrap().jobs.is_empty());
+        let resumed = engine(true);
+        assert!(resumed.resume(saved).is_empty());
+        let jobs = resumed.jobs();
+        let names: Vec<&str> = jobs.iter().map(|job| job.name.as_str()).collect();
+        assert_eq!(names, ["steady", "job-2"]);
+        assert!((599.0..=600.0).contains(&jobs[1].duration_secs.unwrap()));
+        for job in jobs {
+            resumed.stop_job(job.id).unwrap();
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn schedules_run_stress_in_their_windows() {
+        let engine = harness_engine(1);
+        let json = r#"{"name":"nightly","cron":"*/5 * * * *","duration_secs":60,"mode":"throttled","utilization":50}"#;
+        let info = engine.create_schedule(&serde_json::from_str(json).unwrap()).unwrap();
+        let start_ms = info.next_start_ms.unwrap();
+        // The clock may start inside a window, which ends before the next one begins
+        engine.advance_clock(start_ms - engine.clock_ms().unwrap() - 1);
+        assert!(!engine.snapshot().running);
+        engine.advance_clock(1);
+        let status = engine.snapshot();
+        assert_eq!((status.running, status.mode, status.utilization), (true, ExecutionMode::Throttled, 50));
+        assert_eq!(engine.schedules()[0].active_until_ms, Some(start_ms + 60_000));
+        engine.advance_clock(59_999);
+        assert!(engine.snapshot().running);
+        engine.advance_clock(1);
+        assert!(!engine.snapshot().running);
+        assert_eq!(engine.schedules()[0].next_start_ms, Some(start_ms + 300_000));
+
+        // A run restarted by hand during a window outlasts it
+        engine.advance_clock(300_000);
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        engine.advance_clock(60_000);
+        assert!(engine.snapshot().running);
+        assert_eq!(engine.delete_schedule(1).unwrap(), "Schedule 1 deleted");
+        assert!(engine.schedules().is_empty());
+        assert!(matches!(engine.delete_schedule(1), Err(EngineError::NotFound(_))));
+
+        let invalid = r#"{"window":"02:00-04:00","mode":"bursty","utilization":250,"start_delay_ms":10}"#;
+        match engine.create_schedule(&serde_json::from_str(invalid).unwrap()) {
+            Err(EngineError::Validation(errors)) => {
+                let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+                assert_eq!(fields, ["utilization", "start_delay_ms"]);
+            }
+            other => panic!("accepted an invalid schedule: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn runs_report_their_energy() {
+        let engine = harness_engine(1);
+        engine.advance_clock(1000);
+        assert_eq!(engine.snapshot().power.unwrap().watts, 20.0);
+        assert!(engine.snapshot().energy.is_none());
+
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        engine.advance_clock(2000);
+        let status = engine.snapshot();
+        assert_eq!(status.power.unwrap().watts, 30.0);
+        let energy = status.energy.unwrap();
+        assert_eq!((energy.joules, energy.average_watts), (60.0, 30.0));
+        assert_eq!(energy.ops_per_joule, HARNESS_OPS_PER_SEC / 30.0);
+    }
+
+    #[test]
+    fn scores_are_normalized_to_the_calibrated_baseline() {
+        let engine = harness_engine(2);
+        assert!(engine.snapshot().score.is_none());
+        assert!(matches!(engine.measure_baseline(Some(10)), Err(EngineError::Invalid(_))));
+        let baseline = engine.measure_baseline(None).unwrap();
+        assert_eq!(baseline.ops_per_second, HARNESS_OPS_PER_SEC as u64);
+
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        engine.advance_clock(2000);
+        let score = engine.snapshot().score.unwrap();
+        assert_eq!(score.score, 2.0);
+        assert_eq!(score.score_per_core, 1.0);
+        assert!(matches!(engine.measure_baseline(None), Err(EngineError::Conflict(_))));
+    }
+
+    #[test]
+    fn throttled_ops_scale_with_utilization() {
+        let engine = harness_engine(1);
+        engine.start_run(&request(r#"{"mode":"throttled","utilization":30}"#)).unwrap();
+        engine.advance_clock(1000);
+        assert_eq!(engine.snapshot().ops_per_second, (HARNESS_OPS_PER_SEC * 0.3) as u64);
+    }
+
+    #[test]
+    fn trigger_burst_requires_external_trigger() {
+        let engine = harness_engine(1);
+        assert!(engine.trigger_burst(None).is_err());
+
+        engine.start_run(&request(r#"{"mode":"bursty"}"#)).unwrap();
+        assert!(engine.trigger_burst(None).is_err());
+
+        engine.start_run(&request(r#"{"mode":"bursty","burst_trigger":"external"}"#)).unwrap();
+        assert!(engine.trigger_burst(Some(0)).is_err());
+        assert_eq!(engine.trigger_burst(Some(200)).unwrap(), "Burst 1 triggered");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn idle_serial_trigger_stays_healthy() {
+        let fifo = std::env::temp_dir().join(format!("cpu-stress-serial-test-{}", std::process::id()));
+        let _ = std::fs::remove_file(&fifo);
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        // SAFETY: a valid NUL-terminated path
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        let engine = harness_engine(1);
+        engine.start_run(&request(r#"{"mode":"bursty","burst_trigger":"external"}"#)).unwrap();
+        engine.spawn_serial_trigger(fifo.clone());
+        // No writer and no input: the reader still beats while it waits
+        thread::sleep(Duration::from_millis(3 * TRIGGER_POLL_MS));
+        let heartbeat = engine.heartbeats.lock().unwrap().iter().find(|h| h.name == "serial-trigger").cloned();
+        let age_ms = heartbeat.unwrap().age_ms().expect("serial trigger never beat");
+        assert!(age_ms <= 2 * TRIGGER_POLL_MS, "last beat {} ms ago", age_ms);
+        assert_eq!(engine.health(true).status, "ok");
+
+        let mut writer = std::fs::OpenOptions::new().write(true).open(&fifo).unwrap();
+        std::io::Write::write_all(&mut writer, b"250\n").unwrap();
+        let signal = Arc::clone(&engine.run.lock().unwrap().as_ref().unwrap().signal);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while signal.last_trigger().generation == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(signal.last_trigger().duration_ms, Some(250));
+        std::fs::remove_file(&fifo).unwrap();
+    }
+
+    #[test]
+    fn lifecycle_events_follow_the_run() {
+        let engine = harness_engine(1);
+        let mut events = engine.subscribe_lifecycle_events();
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        // Reconfiguring the same mode isn't a new run
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        engine.start_run(&request(r#"{"mode":"bursty"}"#)).unwrap();
+        engine.stop();
+        engine.stop();
+
+        let mut names = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            names.push(serde_json::to_value(&event).unwrap()["event"].as_str().unwrap().to_string());
+        }
+        assert_eq!(names, ["started", "stopped", "started", "stopped"]);
+    }
+
+    #[test]
+    fn only_harness_engines_have_a_virtual_clock() {
+        let engine = Arc::new(StressEngine::new(EngineOptions::default()));
+        assert_eq!(engine.advance_clock(1000), None);
+        assert_eq!(engine.clock_ms(), None);
+
+        let engine = harness_engine(1);
+        let now_ms = engine.clock_ms().unwrap();
+        assert_eq!(engine.advance_clock(1500), Some(now_ms + 1500));
+    }
+
+    #[test]
+    fn panicked_threads_are_restarted() {
+        let engine = harness_engine(1);
+        let heartbeat = engine.register_heartbeat("flaky".to_string());
+        let mut calls = 0;
+        supervise(&engine, &heartbeat, None, || {
+            calls += 1;
+            if calls < 3 {
+                panic!("flaky thread failed {} times", calls);
+            }
+        });
+        assert_eq!(calls, 3);
+        assert_eq!(engine.snapshot().thread_restarts, 2);
+        let health = engine.health(false);
+        assert_eq!((health.status, health.restarts), ("ok", 2));
+        assert_eq!(health.restarted[0].name, "flaky");
+        assert_eq!(health.restarted[0].last_panic.as_deref(), Some("flaky thread failed 2 times"));
+
+        // A worker that panics while its run is stopping isn't restarted
+        let signal = RunSignal::default();
+        signal.stop();
+        supervise(&engine, &heartbeat, Some(&signal), || panic!("stopping"));
+        assert_eq!(heartbeat.restarts.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn physical_core_runs_leave_smt_siblings_idle() {
+        let smt_engine = |topology| {
+            Arc::new(StressEngine::new(EngineOptions {
+                num_cores: 4,
+                simulate: true,
+                cores: Some(vec![0, 1, 2, 3]),
+                topology,
+                ..EngineOptions::default()
+            }))
+        };
+        let engine = smt_engine(CpuTopology::from_cores(vec![vec![0, 2], vec![1, 3]]));
+        engine.start_run(&request(r#"{"mode":"threaded","use_physical_cores_only":true}"#)).unwrap();
+        assert_eq!(engine.worker_cores(&engine.config.borrow()), [(0, Some(0)), (1, Some(1))]);
+        let status = engine.snapshot();
+        assert_eq!((status.workers, status.physical_cores_only), (2, true));
+        assert_eq!(status.smt_siblings, [[0, 2], [1, 3]]);
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        assert_eq!(engine.snapshot().workers, 4);
+        engine.stop();
+
+        let job = r#"{"mode":"threaded","cores":"2-3","use_physical_cores_only":true}"#;
+        let job = engine.create_job(&serde_json::from_str(job).unwrap()).unwrap();
+        assert_eq!(job.cores, [2, 3]);
+        let other = serde_json::from_str(r#"{"mode":"threaded","cores":"3"}"#).unwrap();
+        assert!(matches!(engine.create_job(&other), Err(EngineError::Conflict(_))));
+        engine.stop_job(job.id).unwrap();
+
+        let unknown = smt_engine(CpuTopology::default());
+        match unknown.start_run(&request(r#"{"mode":"threaded","use_physical_cores_only":true}"#)) {
+            Err(EngineError::Validation(errors)) => assert_eq!(errors[0].field, "use_physical_cores_only"),
+            other => panic!("started without a topology: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn benchmarks_compare_the_modes() {
+        let engine = harness_engine(2);
+        let benchmark = |json| serde_json::from_str::<BenchmarkRequest>(json).unwrap();
+        let report = engine.run_benchmark(&benchmark(r#"{"duration_secs":10,"cooldown_secs":5}"#)).unwrap();
+        assert_eq!(report.finished_ms - report.started_ms, 4 * 10_000 + 3 * 5_000);
+        let modes: Vec<ExecutionMode> = report.phases.iter().map(|phase| phase.mode).collect();
+        assert_eq!(
+            modes,
+            [ExecutionMode::Threaded, ExecutionMode::FreshProcess, ExecutionMode::Bursty, ExecutionMode::Throttled]
+        );
+        let threaded = &report.phases[0];
+        assert_eq!(threaded.samples, 9);
+        assert_eq!(threaded.ops_per_second, 2.0 * HARNESS_OPS_PER_SEC);
+        assert_eq!((threaded.relative, threaded.variation_percent), (1.0, 0.0));
+        assert_eq!((threaded.cores.len(), threaded.core_spread_percent), (2, 0.0));
+        assert!((report.phases[1].relative - 0.9).abs() < 0.01);
+        assert!(report.phases[2].burst_ops_per_second.is_some());
+        assert!(report.phases[3].relative < 1.0);
+        assert!(!engine.snapshot().running);
+
+        engine.start_run(&request(r#"{"mode":"threaded"}"#)).unwrap();
+        assert!(matches!(engine.run_benchmark(&BenchmarkRequest::default()), Err(EngineError::Conflict(_))));
+        engine.stop();
+        match engine.run_benchmark(&benchmark(r#"{"modes":["bursty"],"workloads":["stream"],"nice":40}"#)) {
+            Err(EngineError::Validation(errors)) => assert_eq!(errors[0].field, "workloads"),
+            other => panic!("accepted an invalid benchmark: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// gRPC control and metrics API (enabled with the "grpc" Cargo feature)
+use crate::engine::{
+    BurstBackend, BurstDistribution, BurstSource, BurstTrigger, ConfigUpdate, EngineError,
+    ExecutionMode, RunOptions, StartCpuRequest, StressEngine, Workload, unix_time_ms,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("stress");
+}
+
+use proto::stress_reporter_server::{StressReporter, StressReporterServer};
+
+struct StressReporterService {
+    state: Arc<StressEngine>,
+}
+
+impl From<ExecutionMode> for proto::ExecutionMode {
+    fn from(mode: ExecutionMode) -> Self {
+        match mode {
+            ExecutionMode::Threaded => proto::ExecutionMode::Threaded,
+            ExecutionMode::FreshProcess => proto::ExecutionMode::FreshProcess,
+            ExecutionMode::Bursty => proto::ExecutionMode::Bursty,
+            ExecutionMode::Throttled => proto::ExecutionMode::Throttled,
+        }
+    }
+}
+
+// Convert a gRPC start request into the shared request type used by the HTTP API
+fn to_start_request(request: proto::StartCpuRequest) -> Result<StartCpuRequest, String> {
+    let mode = match request.mode() {
+        proto::ExecutionMode::Threaded => ExecutionMode::Threaded,
+        proto::ExecutionMode::FreshProcess => ExecutionMode::FreshProcess,
+        proto::ExecutionMode::Bursty => ExecutionMode::Bursty,
+        proto::ExecutionMode::Throttled => ExecutionMode::Throttled,
+        proto::ExecutionMode::Unspecified => {
+            return Err("mode is required".to_string());
+        }
+    };
+    let burst_distribution = match request.burst_distribution() {
+        proto::BurstDistribution::Unspecified => None,
+        proto::BurstDistribution::Exponential => Some(BurstDistribution::Exponential),
+        proto::BurstDistribution::Uniform => Some(BurstDistribution::Uniform),
+        proto::BurstDistribution::Fixed => Some(BurstDistribution::Fixed),
+    };
+    let burst_source = match request.burst_source() {
+        proto::BurstSource::Unspecified => None,
+        proto::BurstSource::Random => Some(BurstSource::Random),
+        proto::BurstSource::LowDiscrepancy => Some(BurstSource::LowDiscrepancy),
+        proto::BurstSource::Schedule => Some(BurstSource::Schedule),
+    };
+    let burst_backend = match request.burst_backend() {
+        proto::BurstBackend::Unspecified => None,
+        proto::BurstBackend::Process => Some(BurstBackend::Process),
+        proto::BurstBackend::Thread => Some(BurstBackend::Thread),
+    };
+    let burst_trigger = match request.burst_trigger() {
+        proto::BurstTrigger::Unspecified => None,
+        proto::BurstTrigger::Internal => Some(BurstTrigger::Internal),
+        proto::BurstTrigger::External => Some(BurstTrigger::External),
+    };
+    let workload = match request.workload() {
+        proto::Workload::Unspecified => None,
+        proto::Workload::Primes => Some(Workload::Primes),
+        proto::Workload::Stream => Some(Workload::Stream),
+    };
+    let burst_schedule = (!request.burst_schedule.is_empty()).then_some(request.burst_schedule);
+
+    Ok(StartCpuRequest {
+        mode,
+        options: RunOptions {
+            utilization: request.utilization,
+            burst_distribution,
+            burst_mean_secs: request.burst_mean_secs,
+            burst_min_secs: request.burst_min_secs,
+            burst_max_secs: request.burst_max_secs,
+            burst_source,
+            burst_schedule,
+            burst_backend,
+            worker_ops: request.worker_ops,
+            duty_cycle_ms: request.duty_cycle_ms,
+            schedule_seed: request.schedule_seed,
+            schedule_epoch_ms: request.schedule_epoch_ms,
+            phase_offset_ms: request.phase_offset_ms,
+            burst_trigger,
+            ramp_secs: request.ramp_secs,
+            nice: request.nice,
+            latency_probe_us: request.latency_probe_us,
+            workload,
+            stream_array_mb: request.stream_array_mb,
+            stop_if_ops_below: request.stop_if_ops_below,
+            stop_if_drop_percent_exceeds: request.stop_if_drop_percent_exceeds,
+            guard_window_secs: request.guard_window_secs,
+            start_at: request.start_at,
+            start_delay_ms: request.start_delay_ms,
+            use_physical_cores_only: request.use_physical_cores_only,
+        },
+    })
+}
+
+// Same classes as the HTTP status codes (400, 409, 404)
+fn engine_status(error: EngineError) -> Status {
+    match error {
+        EngineError::Invalid(_) | EngineError::Validation(_) => Status::invalid_argument(error.to_string()),
+        EngineError::Conflict(message) => Status::failed_precondition(message),
+        EngineError::NotFound(message) => Status::not_found(message),
+    }
+}
+
+fn current_status(state: &StressEngine) -> proto::Status {
+    let status = state.snapshot();
+    proto::Status {
+        running: status.running,
+        mode: proto::ExecutionMode::from(status.mode).into(),
+        utilization: status.utilization,
+        ops_per_second: status.ops_per_second,
+        burst_ops_per_second: status.burst_ops_per_second,
+        cpu_utilization_percent: status.cpu_utilization_percent,
+        per_core_utilization_percent: status.per_core_utilization_percent,
+        temperatures: status
+            .temperatures
+            .into_iter()
+            .map(|t| proto::Temperature {
+                sensor: t.sensor,
+                label: t.label,
+                celsius: t.celsius,
+            })
+            .collect(),
+        measurement_quality: status
+            .measurement_quality
+            .map(|q| proto::MeasurementQuality {
+                grade: format!("{:?}", q.grade),
+                noise_percent: q.noise_percent,
+                calibration_ops_per_sec: q.calibration_ops_per_sec,
+            }),
+    }
+}
+
+#[tonic::async_trait]
+impl StressReporter for StressReporterService {
+    async fn start_cpu(
+        &self,
+        request: Request<proto::StartCpuRequest>,
+    ) -> Result<Response<proto::StartCpuResponse>, Status> {
+        let request = to_start_request(request.into_inner()).map_err(Status::invalid_argument)?;
+        let state = Arc::clone(&self.state);
+        let message = tokio::task::spawn_blocking(move || state.start_run(&request))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(engine_status)?;
+        Ok(Response::new(proto::StartCpuResponse { message }))
+    }
+
+    async fn end_cpu(
+        &self,
+        _request: Request<proto::EndCpuRequest>,
+    ) -> Result<Response<proto::EndCpuResponse>, Status> {
+        let state = Arc::clone(&self.state);
+        let message = tokio::task::spawn_blocking(move || state.stop())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(proto::EndCpuResponse { message }))
+    }
+
+    async fn trigger_burst(
+        &self,
+        request: Request<proto::TriggerBurstRequest>,
+    ) -> Result<Response<proto::TriggerBurstResponse>, Status> {
+        let message = self
+            .state
+            .trigger_burst(request.into_inner().duration_ms)
+            .map_err(engine_status)?;
+        Ok(Response::new(proto::TriggerBurstResponse { message }))
+    }
+
+    async fn update_config(
+        &self,
+        request: Request<proto::UpdateConfigRequest>,
+    ) -> Result<Response<proto::UpdateConfigResponse>, Status> {
+        let update = ConfigUpdate {
+            worker_ops: request.into_inner().worker_ops,
+        };
+        let message = self
+            .state
+            .update_config(&update)
+            .map_err(engine_status)?;
+        Ok(Response::new(proto::UpdateConfigResponse { message }))
+    }
+
+    async fn get_status(
+        &self,
+        _request: Request<proto::GetStatusRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        Ok(Response::new(current_status(&self.state)))
+    }
+
+    type MetricsStream = ReceiverStream<Result<proto::MetricsSample, Status>>;
+
+    async fn metrics(
+        &self,
+        _request: Request<proto::MetricsRequest>,
+    ) -> Result<Response<Self::MetricsStream>, Status> {
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let state = Arc::clone(&self.state);
+
+        // Emit one sample per sampling interval until the client disconnects
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                interval.tick().await;
+                let status = current_status(&state);
+                let sample = proto::MetricsSample {
+                    timestamp_ms: unix_time_ms(),
+                    running: status.running,
+                    mode: status.mode,
+                    ops_per_second: status.ops_per_second,
+                    burst_ops_per_second: status.burst_ops_per_second,
+                    cpu_utilization_percent: status.cpu_utilization_percent,
+                    per_core_utilization_percent: status.per_core_utilization_percent,
+                    temperatures: status.temperatures,
+                };
+                if tx.send(Ok(sample)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+// Serve the gRPC API on the given port until the process exits
+pub async fn serve(state: Arc<StressEngine>, port: u16) {
+    let addr = std::net::SocketAddr::from((std::net::Ipv6Addr::UNSPECIFIED, port));
+    tracing::info!(port, "gRPC server listening");
+
+    tonic::transport::Server::builder()
+        .add_service(StressReporterServer::new(StressReporterService { state }))
+        .serve(addr)
+        .await
+        .expect("gRPC server error");
+}
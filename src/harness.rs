@@ -0,0 +1,323 @@
+// Deterministic simulation on a virtual clock for --test-harness
+use crate::engine::{BurstBackend, BurstTrigger, ExecutionMode, RunConfig, RunSignal, StressEngine};
+use crate::metrics::{BurstBatch, BurstEvent, CpuUtilization, emit_burst_event, record_batch_latency, record_sample};
+use crate::power::{PackagePower, PowerReading};
+use crate::workloads::{BurstSampler, FleetSchedule, FleetSync};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+// Per-core ops/sec of the test harness's simulated agent
+pub(crate) const HARNESS_OPS_PER_SEC: f64 = 300_000.0;
+
+// Simulated package power: idle, plus this much per fully busy core
+const HARNESS_IDLE_WATTS: f64 = 20.0;
+const HARNESS_CORE_WATTS: f64 = 10.0;
+
+// Granularity of the test harness's simulation steps
+const HARNESS_TICK_MS: u64 = 100;
+
+// Longest single POST /harness/advance: the simulation runs every tick of it before responding
+pub(crate) const MAX_HARNESS_ADVANCE_MS: u64 = 86_400_000;
+
+// Deterministic simulation on a virtual clock for --test-harness: each POST /harness/advance
+// steps the simulated workers, burst schedules, and the 1-second sampler through virtual time
+pub(crate) struct TestHarness {
+    clock: Mutex<HarnessClock>,
+}
+
+struct HarnessClock {
+    // Virtual Unix time in milliseconds
+    now_ms: u64,
+    next_tick_ms: u64,
+    next_sample_ms: u64,
+    // Busy core-milliseconds since the last sample, reported as CPU utilization
+    busy_core_ms: u64,
+    // Seeded so burst durations are the same on every run
+    rng: rand::rngs::StdRng,
+    config_rx: tokio::sync::watch::Receiver<RunConfig>,
+    // Bursty mode burst durations and internal schedule of the current run
+    sampler: Option<BurstSampler>,
+    schedule: Option<FleetSchedule>,
+    seen_trigger: u64,
+    burst: Option<HarnessBurst>,
+}
+
+struct HarnessBurst {
+    start_ms: u64,
+    end_ms: u64,
+    ops: u64,
+    // One batch per simulation step
+    batches: Vec<BurstBatch>,
+    // Core ids of the run's workers, which share the burst's ops
+    core_ids: Vec<usize>,
+}
+
+impl TestHarness {
+    pub(crate) fn new(config_rx: tokio::sync::watch::Receiver<RunConfig>, now_ms: u64) -> Self {
+        use rand::SeedableRng;
+
+        TestHarness {
+            clock: Mutex::new(HarnessClock {
+                now_ms,
+                next_tick_ms: now_ms + HARNESS_TICK_MS,
+                next_sample_ms: now_ms + 1000,
+                busy_core_ms: 0,
+                rng: rand::rngs::StdRng::seed_from_u64(0),
+                config_rx,
+                sampler: None,
+                schedule: None,
+                seen_trigger: 0,
+                burst: None,
+            }),
+        }
+    }
+
+    pub(crate) fn now_ms(&self) -> u64 {
+        self.clock.lock().unwrap().now_ms
+    }
+
+    // Move virtual time forward, running every simulation step and sample that falls due
+    pub(crate) fn advance(&self, state: &Arc<StressEngine>, ms: u64) -> u64 {
+        let mut clock = self.clock.lock().unwrap();
+        let target_ms = clock.now_ms.saturating_add(ms);
+        while clock.next_tick_ms <= target_ms {
+            // An armed run (or a scheduled window) starts with the first tick that begins at or after its start time
+            state.start_armed_if_due(clock.next_tick_ms - HARNESS_TICK_MS);
+            state.run_schedules(clock.next_tick_ms - HARNESS_TICK_MS);
+            clock.now_ms = clock.next_tick_ms;
+            let Some(next_tick_ms) = clock.next_tick_ms.checked_add(HARNESS_TICK_MS) else {
+                break;
+            };
+            clock.next_tick_ms = next_tick_ms;
+            clock.tick(state);
+            if clock.now_ms >= clock.next_sample_ms {
+                clock.next_sample_ms = clock.next_sample_ms.saturating_add(1000);
+                clock.sample(state);
+            }
+        }
+        clock.now_ms = target_ms;
+        state.start_armed_if_due(target_ms);
+        state.run_schedules(target_ms);
+        target_ms
+    }
+}
+
+impl HarnessClock {
+    // Simulate the HARNESS_TICK_MS of work that ends at now_ms
+    fn tick(&mut self, state: &StressEngine) {
+        let tick_start_ms = self.now_ms - HARNESS_TICK_MS;
+        let signal = state.run.lock().unwrap().as_ref().map(|run| Arc::clone(&run.signal));
+        let Some(signal) = signal else {
+            self.end_burst(state, tick_start_ms);
+            return;
+        };
+
+        // A new run (or new settings) starts its schedule from scratch
+        if self.config_rx.has_changed().unwrap_or(false) {
+            self.sampler = None;
+            self.schedule = None;
+            self.seen_trigger = signal.last_trigger().generation;
+            self.end_burst(state, tick_start_ms);
+        }
+        let config = self.config_rx.borrow_and_update().clone();
+
+        let num_cores = state.worker_cores(&config).len() as u64;
+        let full_ops = HARNESS_OPS_PER_SEC * (HARNESS_TICK_MS * num_cores) as f64 / 1000.0;
+        let (ops, busy_core_ms) = match config.mode {
+            ExecutionMode::Threaded => (full_ops as u64, HARNESS_TICK_MS * num_cores),
+            ExecutionMode::FreshProcess => ((full_ops * 0.9) as u64, HARNESS_TICK_MS * num_cores),
+            ExecutionMode::Throttled => (
+                (full_ops * config.utilization as f64 / 100.0) as u64,
+                HARNESS_TICK_MS * num_cores * config.utilization / 100,
+            ),
+            ExecutionMode::Bursty => {
+                self.schedule_burst(state, &signal, &config, tick_start_ms);
+                match self.burst.as_mut() {
+                    Some(burst) => {
+                        // Warm threads skip the process creation penalty
+                        let ops = match config.burst_backend {
+                            BurstBackend::Process => (full_ops * 0.9) as u64,
+                            BurstBackend::Thread => full_ops as u64,
+                        };
+                        burst.ops += ops;
+                        burst.batches.push(BurstBatch {
+                            completed_ms: self.now_ms - burst.start_ms,
+                            ops: ops / num_cores,
+                        });
+                        record_batch_latency(state, std::time::Duration::from_millis(HARNESS_TICK_MS));
+                        state.burst_operations.fetch_add(ops, Ordering::Relaxed);
+                        state.burst_time_ms.fetch_add(HARNESS_TICK_MS, Ordering::Relaxed);
+                        (ops, HARNESS_TICK_MS * num_cores)
+                    }
+                    None => (0, 0),
+                }
+            }
+        };
+        state.current_counter.fetch_add(ops, Ordering::Relaxed);
+        self.busy_core_ms += busy_core_ms;
+
+        if self.burst.as_ref().is_some_and(|burst| burst.end_ms <= self.now_ms) {
+            self.end_burst(state, self.now_ms);
+        }
+    }
+
+    // Start a burst at `tick_start_ms` if the schedule or an external trigger calls for one
+    fn schedule_burst(&mut self, state: &StressEngine, signal: &RunSignal, config: &RunConfig, tick_start_ms: u64) {
+        if self.burst.is_some() {
+            return;
+        }
+
+        let end_ms = if config.burst_trigger == BurstTrigger::External {
+            let trigger = signal.last_trigger();
+            if trigger.generation == self.seen_trigger {
+                return;
+            }
+            self.seen_trigger = trigger.generation;
+            let sampler = self
+                .sampler
+                .get_or_insert_with(|| BurstSampler::new(config.burst_config.clone()));
+            let duration_ms = trigger
+                .duration_ms
+                .unwrap_or_else(|| (sampler.next_secs(&mut self.rng) * 1000.0) as u64);
+            tick_start_ms + duration_ms.max(1)
+        } else {
+            // Without a shared schedule, the run's own schedule is seeded and starts at the run start
+            let sync = config.fleet_sync.unwrap_or(FleetSync {
+                seed: 0,
+                epoch_ms: tick_start_ms,
+                phase_offset_ms: 0,
+            });
+            let schedule = self
+                .schedule
+                .get_or_insert_with(|| FleetSchedule::new(sync, config.burst_config.clone(), config.utilization));
+            let (burst_start_ms, burst_end_ms, _) = schedule.current_cycle(tick_start_ms);
+            if tick_start_ms < burst_start_ms || tick_start_ms >= burst_end_ms {
+                return;
+            }
+            burst_end_ms
+        };
+
+        let core_ids: Vec<usize> = state.worker_cores(config).into_iter().map(|(core_id, _)| core_id).collect();
+        for &core_id in &core_ids {
+            emit_burst_event(
+                state,
+                BurstEvent::BurstStart {
+                    core_id,
+                    timestamp_ms: tick_start_ms,
+                    planned_ms: end_ms - tick_start_ms,
+                },
+            );
+        }
+        self.burst = Some(HarnessBurst {
+            start_ms: tick_start_ms,
+            end_ms,
+            ops: 0,
+            batches: Vec::new(),
+            core_ids,
+        });
+    }
+
+    fn end_burst(&mut self, state: &StressEngine, now_ms: u64) {
+        let Some(burst) = self.burst.take() else {
+            return;
+        };
+        let cores = burst.core_ids.len().max(1) as u64;
+        for &core_id in &burst.core_ids {
+            emit_burst_event(
+                state,
+                BurstEvent::BurstEnd {
+                    core_id,
+                    timestamp_ms: now_ms,
+                    planned_ms: burst.end_ms - burst.start_ms,
+                    actual_ms: now_ms - burst.start_ms,
+                    ops: burst.ops / cores,
+                    batches: burst.batches.clone(),
+                },
+            );
+        }
+    }
+
+    // Close a virtual 1-second sampling interval
+    fn sample(&mut self, state: &StressEngine) {
+        let percent = self.busy_core_ms as f32 / (10.0 * state.num_cores as f32);
+        let watts = HARNESS_IDLE_WATTS + HARNESS_CORE_WATTS * self.busy_core_ms as f64 / 1000.0;
+        self.busy_core_ms = 0;
+        let cpu_utilization = CpuUtilization {
+            overall_percent: percent,
+            per_core_percent: vec![percent; state.num_cores],
+        };
+        let power = PowerReading {
+            watts,
+            joules: watts,
+            packages: vec![PackagePower {
+                domain: "package-0".to_string(),
+                watts,
+            }],
+        };
+        record_sample(state, cpu_utilization, Vec::new(), Some(power), self.now_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{EngineOptions, StartCpuRequest, StressEngine};
+    use crate::metrics::BurstEvent;
+    use std::sync::Arc;
+
+    #[test]
+    fn bursty_schedule_is_deterministic() {
+        let engine = Arc::new(StressEngine::test_harness(EngineOptions {
+            num_cores: 1,
+            ..EngineOptions::default()
+        }));
+        let mut events = engine.subscribe_burst_events();
+        let request: StartCpuRequest = serde_json::from_str(
+            r#"{"mode":"bursty","utilization":50,"burst_distribution":"fixed","burst_mean_secs":1}"#,
+        )
+        .unwrap();
+        engine.start_run(&request).unwrap();
+        let start_ms = engine.clock_ms().unwrap();
+        engine.advance_clock(4000);
+
+        // 1s bursts with 1s idle between them, starting with the run
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(match event {
+                BurstEvent::BurstStart { timestamp_ms, .. } => ("burst-start", timestamp_ms - start_ms),
+                BurstEvent::BurstEnd { timestamp_ms, actual_ms, batches, .. } => {
+                    assert_eq!(actual_ms, 1000);
+                    let completed: Vec<u64> = batches.iter().map(|batch| batch.completed_ms).collect();
+                    assert_eq!(completed, (1..=10).map(|step| step * 100).collect::<Vec<u64>>());
+                    assert!(batches.iter().all(|batch| batch.ops == 27_000));
+                    ("burst-end", timestamp_ms - start_ms)
+                }
+            });
+        }
+        assert_eq!(
+            received,
+            vec![("burst-start", 0), ("burst-end", 1000), ("burst-start", 2000), ("burst-end", 3000)]
+        );
+        assert_eq!(engine.snapshot().cpu_utilization_percent, 0.0);
+        // Each burst ran at the process backend's 270k ops/sec
+        let bursts = engine.snapshot().percentiles.burst_ops_per_second;
+        assert_eq!((bursts.samples, bursts.p50, bursts.p99), (2, Some(270_000.0), Some(270_000.0)));
+    }
+
+    #[test]
+    fn burst_backend_sets_burst_throughput() {
+        for (backend, expected) in [("process", 270_000), ("thread", 300_000)] {
+            let engine = Arc::new(StressEngine::test_harness(EngineOptions {
+                num_cores: 1,
+                ..EngineOptions::default()
+            }));
+            let request: StartCpuRequest = serde_json::from_str(&format!(
+                r#"{{"mode":"bursty","utilization":100,"burst_backend":"{}"}}"#,
+                backend
+            ))
+            .unwrap();
+            engine.start_run(&request).unwrap();
+            engine.advance_clock(1000);
+            assert_eq!(engine.snapshot().burst_ops_per_second, expected, "{} backend", backend);
+        }
+    }
+}
@@ -0,0 +1,1352 @@
+// HTTP API: axum handlers over a StressEngine, plus control call recording. Responses are JSON (except the
+// plain-text /cpu-perf and /burst-perf and the SSE stream), errors are {"error": ...} with a 4xx status,
+// and GET /openapi.json describes all of it.
+use crate::benchmark::{BenchmarkReport, BenchmarkRequest};
+use crate::discovery::Peer;
+use crate::encryption::EncryptionKey;
+use crate::engine::{
+    ConfigUpdate, EngineError, FieldError, HealthReport, JobInfo, JobRequest, MixedRequest, StartCpuRequest,
+    StressEngine, unix_time_ms,
+};
+use crate::harness::MAX_HARNESS_ADVANCE_MS;
+use crate::logging;
+use crate::metrics::{Baseline, Histograms, HistorySample, LatencyReport, MetricsSnapshot};
+use crate::process_stats::{DEFAULT_PROCESS_RECORDS, ProcessStatsReport};
+use crate::schedule::{ScheduleInfo, ScheduleRequest};
+use crate::units::OpsUnit;
+use axum::extract::rejection::{BytesRejection, JsonRejection, PathRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{routing::{delete, get, patch, post}, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use utoipa::OpenApi;
+
+// Body of POST /trigger-burst (optional)
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+struct TriggerBurstRequest {
+    duration_ms: Option<u64>,
+}
+
+// Body of POST /calibrate (optional)
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+struct CalibrateRequest {
+    // Length of the measurement (default 2000)
+    duration_ms: Option<u64>,
+}
+
+// Body of PUT /log-level and of GET /log-level responses (RUST_LOG syntax)
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+struct LogLevel {
+    filter: String,
+}
+
+// Query parameters of GET /history
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// Only return the most recent N samples
+    last: Option<usize>,
+}
+
+// Query parameters of GET /process-stats
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ProcessStatsQuery {
+    /// Return the most recent N processes (default 100, at most 1000 are kept)
+    last: Option<usize>,
+}
+
+// Query parameters of GET /cpu-perf and /burst-perf
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PerfQuery {
+    /// Units of the value: raw ops/sec (default), kilo-ops, or per-core
+    #[serde(default)]
+    #[param(inline, required = false)]
+    units: OpsUnit,
+    /// text (default): the bare number; json: the value with its units
+    #[serde(default)]
+    #[param(inline, required = false)]
+    format: PerfFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum PerfFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+// Body of GET /cpu-perf and /burst-perf with format=json
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PerfResponse {
+    pub value: f64,
+    pub units: OpsUnit,
+}
+
+// Body of POST /harness/advance
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+struct AdvanceRequest {
+    ms: u64,
+}
+
+// Body of GET /harness/clock and POST /harness/advance responses
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct HarnessClockResponse {
+    now_ms: u64,
+}
+
+// Body of successful control responses
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
+// Body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+    // Every invalid field of a start or job request; `error` joins their messages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+// A refused request: 400 for invalid input, 409 for a conflict with the agent's state, 404 for unknown
+// jobs and routes
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+    errors: Vec<FieldError>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        let body = ErrorResponse {
+            error: self.message,
+            errors: self.errors,
+        };
+        (self.status, axum::Json(body)).into_response()
+    }
+}
+
+impl From<EngineError> for ApiError {
+    fn from(error: EngineError) -> Self {
+        let status = match error {
+            EngineError::Invalid(_) | EngineError::Validation(_) => StatusCode::BAD_REQUEST,
+            EngineError::Conflict(_) => StatusCode::CONFLICT,
+            EngineError::NotFound(_) => StatusCode::NOT_FOUND,
+        };
+        let message = error.to_string();
+        let errors = match error {
+            EngineError::Validation(errors) => errors,
+            _ => Vec::new(),
+        };
+        ApiError { status, message, errors }
+    }
+}
+
+// Malformed bodies, paths, and query strings keep axum's status code (400 or 415) but get a JSON body. A
+// JSON body that doesn't match the request type is invalid input like any other, so it is a 400 rather
+// than axum's 422.
+macro_rules! impl_from_rejection {
+    ($($rejection:ty),*) => {
+        $(impl From<$rejection> for ApiError {
+            fn from(rejection: $rejection) -> Self {
+                let status = match rejection.status() {
+                    StatusCode::UNPROCESSABLE_ENTITY => StatusCode::BAD_REQUEST,
+                    status => status,
+                };
+                ApiError {
+                    status,
+                    message: rejection.body_text(),
+                    errors: Vec::new(),
+                }
+            }
+        })*
+    };
+}
+
+impl_from_rejection!(BytesRejection, JsonRejection, PathRejection, QueryRejection);
+
+type ApiResult<T> = Result<axum::Json<T>, ApiError>;
+
+// Parse a start or job request body. A body that doesn't match the request type (e.g. an unknown mode)
+// would only report its first bad field, so each field is deserialized on its own against `base`, a
+// minimal valid body: the response (400) lists every bad or missing field, plus whatever `validate`
+// finds wrong with the remaining fields.
+fn parse_request<T: serde::de::DeserializeOwned>(
+    body: serde_json::Value,
+    base: serde_json::Value,
+    validate: impl FnOnce(&T) -> Result<(), EngineError>,
+) -> Result<T, ApiError> {
+    let error = match serde_json::from_value::<T>(body.clone()) {
+        Ok(request) => return Ok(request),
+        Err(e) => ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message: e.to_string(),
+            errors: Vec::new(),
+        },
+    };
+    let (Some(fields), serde_json::Value::Object(base)) = (body.as_object(), base) else {
+        return Err(error);
+    };
+
+    let mut errors: Vec<FieldError> = base
+        .keys()
+        .filter(|field| !fields.contains_key(*field))
+        .map(|field| FieldError::new(field, format!("{} is required", field)))
+        .collect();
+    // The body with its bad and missing fields taken from `base`
+    let mut rest = base.clone();
+    for (field, value) in fields {
+        let mut single = base.clone();
+        single.insert(field.clone(), value.clone());
+        match serde_json::from_value::<T>(serde_json::Value::Object(single)) {
+            Ok(_) => {
+                rest.insert(field.clone(), value.clone());
+            }
+            Err(e) => errors.push(FieldError::new(field, format!("{}: {}", field, e))),
+        }
+    }
+    if errors.is_empty() {
+        return Err(error);
+    }
+    if let Ok(rest) = serde_json::from_value::<T>(serde_json::Value::Object(rest))
+        && let Err(EngineError::Validation(more)) = validate(&rest)
+    {
+        // Problems with the placeholders from `base` aren't the request's
+        let replaced: Vec<String> = errors.iter().map(|error| error.field.clone()).collect();
+        errors.extend(more.into_iter().filter(|error| !replaced.contains(&error.field)));
+    }
+    Err(ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: EngineError::Validation(errors.clone()).to_string(),
+        errors,
+    })
+}
+
+// Parse an optional JSON body: an empty body means the defaults, a malformed one is refused like any
+// other request body
+fn optional_json<T: serde::de::DeserializeOwned + Default>(body: &[u8]) -> Result<T, ApiError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(T::default());
+    }
+    let axum::Json(request) = axum::Json::from_bytes(body)?;
+    Ok(request)
+}
+
+fn message(message: String) -> ApiResult<MessageResponse> {
+    Ok(axum::Json(MessageResponse { message }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/harness/clock",
+    description = "Virtual time of the test harness (--test-harness only)",
+    responses((status = 200, body = HarnessClockResponse))
+)]
+async fn harness_clock_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::Json<HarnessClockResponse> {
+    axum::Json(HarnessClockResponse {
+        now_ms: state.clock_ms().expect("harness routes require --test-harness"),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/harness/advance",
+    description = "Fast-forward the virtual clock of the test harness (--test-harness only)",
+    request_body = AdvanceRequest,
+    responses((status = 200, body = HarnessClockResponse), (status = 400, body = ErrorResponse))
+)]
+async fn harness_advance_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    request: Result<axum::Json<AdvanceRequest>, JsonRejection>,
+) -> ApiResult<HarnessClockResponse> {
+    let axum::Json(request) = request?;
+    if request.ms > MAX_HARNESS_ADVANCE_MS {
+        let message = format!("ms must be at most {} (24 hours)", MAX_HARNESS_ADVANCE_MS);
+        return Err(EngineError::Validation(vec![FieldError::new("ms", message)]).into());
+    }
+    // The simulation of every tick runs before the response
+    let now_ms = tokio::task::spawn_blocking(move || state.advance_clock(request.ms))
+        .await
+        .expect("harness advance task panicked")
+        .expect("harness routes require --test-harness");
+    Ok(axum::Json(HarnessClockResponse { now_ms }))
+}
+
+// One recorded control API call, stored as a JSON line
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ControlRecord {
+    pub(crate) timestamp_ms: u64,
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) body: Option<serde_json::Value>,
+}
+
+// Appends every control API call to a JSONL file for later replay (one encrypted line per call if
+// a key is given)
+#[derive(Debug)]
+pub struct ControlRecorder {
+    file: Mutex<std::fs::File>,
+    key: Option<EncryptionKey>,
+}
+
+impl ControlRecorder {
+    pub fn open(path: &std::path::Path, key: Option<EncryptionKey>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(ControlRecorder {
+            file: Mutex::new(file),
+            key,
+        })
+    }
+
+    fn record(&self, record: &ControlRecord) {
+        use std::io::Write;
+
+        let mut line = serde_json::to_string(record).expect("Failed to serialize control record");
+        if let Some(key) = &self.key {
+            line = key.encrypt_line(&line);
+        }
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{}", line) {
+            tracing::error!("failed to write control record: {}", e);
+        }
+    }
+}
+
+// Middleware that logs every request with its status and latency: control calls and errors at info,
+// successful queries (polled by dashboards) at debug
+async fn log_requests_middleware(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let started = std::time::Instant::now();
+    let response = next.run(request).await;
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    if method == axum::http::Method::GET && status < 400 {
+        tracing::debug!(%method, path, status, latency_ms, "request");
+    } else {
+        tracing::info!(%method, path, status, latency_ms, "request");
+    }
+    response
+}
+
+// Middleware that records control calls (anything that isn't a GET) before handling them
+async fn record_control_middleware(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let Some(recorder) = state.control_recorder.as_ref() else {
+        return next.run(request).await;
+    };
+    if request.method() == axum::http::Method::GET {
+        return next.run(request).await;
+    }
+
+    // Buffered like the Bytes extractor, so axum's default body limit (2 MB) applies: a larger body is
+    // refused with 413 instead of being read into memory
+    let (parts, body) = request.into_parts();
+    let request = axum::extract::Request::from_parts(parts.clone(), body);
+    let bytes = match <axum::body::Bytes as axum::extract::FromRequest<()>>::from_request(request, &()).await {
+        Ok(bytes) => bytes,
+        Err(rejection) => return ApiError::from(rejection).into_response(),
+    };
+
+    recorder.record(&ControlRecord {
+        timestamp_ms: unix_time_ms(),
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        body: if bytes.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::from_slice(&bytes)
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned().into()),
+            )
+        },
+    });
+
+    let request = axum::extract::Request::from_parts(parts, axum::body::Body::from(bytes));
+    next.run(request).await
+}
+
+// ops/sec measured over `cores` worker cores, scaled and formatted as the query asks
+fn perf_response(ops_per_second: u64, cores: usize, query: PerfQuery) -> axum::response::Response {
+    let value = query.units.scale(ops_per_second, cores);
+    match query.format {
+        PerfFormat::Text => format!("{}\n", value).into_response(),
+        PerfFormat::Json => axum::Json(PerfResponse { value, units: query.units }).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/cpu-perf",
+    description = "Operations per second over the last sampling interval",
+    params(PerfQuery),
+    responses(
+        (status = 200, body = String, content_type = "text/plain", example = "254060\n"),
+        (status = 200, body = PerfResponse, content_type = "application/json"),
+        (status = 400, body = ErrorResponse)
+    )
+)]
+async fn cpu_perf_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    query: Result<axum::extract::Query<PerfQuery>, QueryRejection>,
+) -> Result<axum::response::Response, ApiError> {
+    let axum::extract::Query(query) = query?;
+    let ops = state.operations_per_second.load(Ordering::Relaxed);
+    let workers = state.worker_cores(&state.config.borrow()).len();
+    Ok(perf_response(ops, workers, query))
+}
+
+#[utoipa::path(
+    get,
+    path = "/history",
+    description = "Per-second status samples, oldest first",
+    params(HistoryQuery),
+    responses((status = 200, body = Vec<HistorySample>), (status = 400, body = ErrorResponse))
+)]
+async fn history_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    query: Result<axum::extract::Query<HistoryQuery>, QueryRejection>,
+) -> Result<([(axum::http::HeaderName, &'static str); 1], String), ApiError> {
+    let axum::extract::Query(query) = query?;
+    let body = serde_json::to_string(&state.history(query.last)).expect("Failed to serialize history");
+    Ok(([(axum::http::header::CONTENT_TYPE, "application/json")], body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/histograms",
+    description = "Mergeable batch latency (ms) and per-second ops sketches of the current run",
+    responses((status = 200, body = Histograms))
+)]
+async fn histograms_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::Json<Histograms> {
+    axum::Json(state.histograms())
+}
+
+#[utoipa::path(
+    get,
+    path = "/process-stats",
+    description = "Spawn latency, wall and CPU time, peak memory, and exit status of worker processes",
+    params(ProcessStatsQuery),
+    responses((status = 200, body = ProcessStatsReport), (status = 400, body = ErrorResponse))
+)]
+async fn process_stats_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    query: Result<axum::extract::Query<ProcessStatsQuery>, QueryRejection>,
+) -> ApiResult<ProcessStatsReport> {
+    let axum::extract::Query(query) = query?;
+    Ok(axum::Json(state.process_stats(query.last.unwrap_or(DEFAULT_PROCESS_RECORDS))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/peers",
+    description = "Agents on the LAN that announced themselves in the last 20 seconds (--discovery)",
+    responses((status = 200, body = Vec<Peer>), (status = 409, body = ErrorResponse))
+)]
+async fn peers_handler(axum::extract::State(state): axum::extract::State<Arc<StressEngine>>) -> ApiResult<Vec<Peer>> {
+    Ok(axum::Json(state.peers()?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/latency",
+    description = "Wakeup latency percentiles (microseconds) of the probes of runs started with latency_probe_us",
+    responses((status = 200, body = LatencyReport))
+)]
+async fn latency_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::Json<LatencyReport> {
+    axum::Json(state.latency())
+}
+
+#[utoipa::path(
+    get,
+    path = "/status",
+    description = "Run state and latest metrics",
+    responses((status = 200, body = MetricsSnapshot))
+)]
+async fn status_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::Json<MetricsSnapshot> {
+    axum::Json(state.snapshot())
+}
+
+// 200 if every background thread is healthy, 503 otherwise
+fn health_response(report: HealthReport) -> (StatusCode, axum::Json<HealthReport>) {
+    let code = if report.failing.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, axum::Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    description = "Liveness: every background thread is still beating",
+    responses((status = 200, body = HealthReport), (status = 503, body = HealthReport))
+)]
+async fn healthz_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> (StatusCode, axum::Json<HealthReport>) {
+    health_response(state.health(false))
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    description = "Readiness: every background thread has started and is beating",
+    responses((status = 200, body = HealthReport), (status = 503, body = HealthReport))
+)]
+async fn readyz_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> (StatusCode, axum::Json<HealthReport>) {
+    health_response(state.health(true))
+}
+
+#[utoipa::path(
+    get,
+    path = "/burst-events",
+    description = "Server-Sent Events stream of burst-start and burst-end events (bursty mode)",
+    responses((status = 200, body = String, content_type = "text/event-stream"))
+)]
+async fn burst_events_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::response::sse::Sse<
+    impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>,
+> {
+    use tokio_stream::StreamExt;
+    use tokio_stream::wrappers::BroadcastStream;
+
+    // Slow subscribers that fall behind just miss events
+    let stream = BroadcastStream::new(state.subscribe_burst_events()).filter_map(|event| {
+        let event = event.ok()?;
+        let data = serde_json::to_string(&event).ok()?;
+        Some(Ok(axum::response::sse::Event::default()
+            .event(event.name())
+            .data(data)))
+    });
+
+    axum::response::sse::Sse::new(stream).keep_alive(axum::response::sse::KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    description = "Server-Sent Events stream of the status every sampling interval (metrics), run and job lifecycle \
+        events, and burst-start and burst-end events",
+    responses((status = 200, body = String, content_type = "text/event-stream"))
+)]
+async fn events_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::response::sse::Sse<
+    impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>,
+> {
+    use axum::response::sse::Event;
+    use tokio_stream::StreamExt;
+    use tokio_stream::wrappers::BroadcastStream;
+
+    // One SSE event named after the event type, with the JSON as data
+    fn sse_event(name: &str, data: &impl Serialize) -> Option<Result<Event, std::convert::Infallible>> {
+        let data = serde_json::to_string(data).ok()?;
+        Some(Ok(Event::default().event(name).data(data)))
+    }
+
+    // Slow subscribers that fall behind just miss events
+    let samples = BroadcastStream::new(state.subscribe_samples())
+        .filter_map(|sample| sse_event("metrics", &sample.ok()?));
+    let lifecycle = BroadcastStream::new(state.subscribe_lifecycle_events()).filter_map(|event| {
+        let event = event.ok()?;
+        sse_event(event.name(), &event)
+    });
+    let bursts = BroadcastStream::new(state.subscribe_burst_events()).filter_map(|event| {
+        let event = event.ok()?;
+        sse_event(event.name(), &event)
+    });
+
+    axum::response::sse::Sse::new(samples.merge(lifecycle).merge(bursts))
+        .keep_alive(axum::response::sse::KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/burst-perf",
+    description = "Operations per second during bursts only (bursty mode)",
+    params(PerfQuery),
+    responses(
+        (status = 200, body = String, content_type = "text/plain", example = "233672\n"),
+        (status = 200, body = PerfResponse, content_type = "application/json"),
+        (status = 400, body = ErrorResponse)
+    )
+)]
+async fn burst_perf_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    query: Result<axum::extract::Query<PerfQuery>, QueryRejection>,
+) -> Result<axum::response::Response, ApiError> {
+    let axum::extract::Query(query) = query?;
+    // Burst ops/sec is already per core: ops divided by the summed burst time of all cores
+    let ops = state.burst_ops_per_second.load(Ordering::Relaxed);
+    Ok(perf_response(ops, 1, query))
+}
+
+#[utoipa::path(
+    get,
+    path = "/mem-perf",
+    description = "Memory bandwidth of the stream workload over the last sampling interval, in GB/s",
+    responses((status = 200, body = String, content_type = "text/plain", example = "38.42\n"))
+)]
+async fn mem_perf_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> String {
+    let bytes = state.memory_bytes_per_second.load(Ordering::Relaxed);
+    format!("{:.2}\n", bytes as f64 / 1e9)
+}
+
+#[utoipa::path(
+    post,
+    path = "/start-cpu",
+    description = "Start the stress test on all cores, switch its mode, or change the options of the running mode; \
+                   with start_at or start_delay_ms, arm it to do so at that time",
+    request_body = StartCpuRequest,
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 400, description = "Invalid options", body = ErrorResponse),
+        (status = 409, description = "Jobs are running", body = ErrorResponse)
+    )
+)]
+async fn start_cpu_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: Result<axum::Json<serde_json::Value>, JsonRejection>,
+) -> ApiResult<MessageResponse> {
+    let axum::Json(body) = body?;
+    let request = parse_request(body, serde_json::json!({"mode": "threaded"}), |request| {
+        state.validate_start(request)
+    })?;
+    // Joining the workers of a replaced run blocks, so keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || state.start_run(&request))
+        .await
+        .expect("start_cpu task panicked");
+    message(result?)
+}
+
+#[utoipa::path(
+    post,
+    path = "/trigger-burst",
+    description = "Fire one burst on every core; without a body the duration comes from the burst distribution",
+    request_body(content = Option<TriggerBurstRequest>),
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "Not running bursty mode with burst_trigger \"external\"", body = ErrorResponse)
+    )
+)]
+async fn trigger_burst_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: axum::body::Bytes,
+) -> ApiResult<MessageResponse> {
+    let request: TriggerBurstRequest = optional_json(&body)?;
+    message(state.trigger_burst(request.duration_ms)?)
+}
+
+#[utoipa::path(
+    post,
+    path = "/calibrate",
+    description = "Measure the single-core baseline ops/sec; /status then also reports scores relative to it",
+    request_body(content = Option<CalibrateRequest>),
+    responses(
+        (status = 200, body = Baseline),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "A stress test or job is running", body = ErrorResponse)
+    )
+)]
+async fn calibrate_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: axum::body::Bytes,
+) -> ApiResult<Baseline> {
+    let request: CalibrateRequest = optional_json(&body)?;
+    // The measurement keeps a core busy for its whole duration
+    let result = tokio::task::spawn_blocking(move || state.measure_baseline(request.duration_ms))
+        .await
+        .expect("calibrate task panicked");
+    Ok(axum::Json(result?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/benchmark",
+    description = "Run each mode (and workload) for the same time, one after another with a cooldown in between, \
+        and compare their ops/sec; responds when the last phase is done",
+    request_body = BenchmarkRequest,
+    responses(
+        (status = 200, body = BenchmarkReport),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "The agent isn't idle, or the benchmark was stopped", body = ErrorResponse)
+    )
+)]
+async fn benchmark_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: Result<axum::Json<serde_json::Value>, JsonRejection>,
+) -> ApiResult<BenchmarkReport> {
+    let axum::Json(body) = body?;
+    let request = parse_request(body, serde_json::json!({}), |request| state.validate_benchmark(request))?;
+    // The phases run for minutes
+    let report = tokio::task::spawn_blocking(move || state.run_benchmark(&request))
+        .await
+        .expect("benchmark task panicked");
+    Ok(axum::Json(report?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/config",
+    description = "Change agent settings without a restart; applies to the running stress test and jobs",
+    request_body = ConfigUpdate,
+    responses((status = 200, body = MessageResponse), (status = 400, body = ErrorResponse))
+)]
+async fn config_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    update: Result<axum::Json<ConfigUpdate>, JsonRejection>,
+) -> ApiResult<MessageResponse> {
+    let axum::Json(update) = update?;
+    message(state.update_config(&update)?)
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    description = "Start a job on a subset of the cores, alongside other jobs",
+    request_body = JobRequest,
+    responses(
+        (status = 200, body = JobInfo),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "The cores are in use", body = ErrorResponse)
+    )
+)]
+async fn create_job_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: Result<axum::Json<serde_json::Value>, JsonRejection>,
+) -> ApiResult<JobInfo> {
+    let axum::Json(body) = body?;
+    let request = parse_request(body, serde_json::json!({"cores": "0", "mode": "threaded"}), |request| {
+        state.validate_job(request)
+    })?;
+    Ok(axum::Json(state.create_job(&request)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/mixed",
+    description = "Run several modes at once, each as a job on its own share of the cores",
+    request_body = MixedRequest,
+    responses(
+        (status = 200, description = "The jobs started, one per mode", body = Vec<JobInfo>),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "Not enough free cores", body = ErrorResponse)
+    )
+)]
+async fn create_mixed_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: Result<axum::Json<serde_json::Value>, JsonRejection>,
+) -> ApiResult<Vec<JobInfo>> {
+    let axum::Json(body) = body?;
+    let request = parse_request(body, serde_json::json!({}), |request| state.validate_mixed(request))?;
+    Ok(axum::Json(state.create_mixed(&request)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    description = "Running jobs, oldest first",
+    responses((status = 200, body = Vec<JobInfo>))
+)]
+async fn jobs_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::Json<Vec<JobInfo>> {
+    axum::Json(state.jobs())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/jobs/{id}",
+    description = "Stop a job",
+    params(("id" = u64, Path, description = "Job id")),
+    responses((status = 200, body = MessageResponse), (status = 404, body = ErrorResponse))
+)]
+async fn stop_job_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    id: Result<axum::extract::Path<u64>, PathRejection>,
+) -> ApiResult<MessageResponse> {
+    let axum::extract::Path(id) = id?;
+    // Joining the job's workers blocks
+    let result = tokio::task::spawn_blocking(move || state.stop_job(id))
+        .await
+        .expect("stop_job task panicked");
+    message(result?)
+}
+
+#[utoipa::path(
+    post,
+    path = "/schedules",
+    description = "Run stress in windows: a cron expression plus a duration, or a daily window (UTC)",
+    request_body = ScheduleRequest,
+    responses((status = 200, body = ScheduleInfo), (status = 400, body = ErrorResponse))
+)]
+async fn create_schedule_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    body: Result<axum::Json<serde_json::Value>, JsonRejection>,
+) -> ApiResult<ScheduleInfo> {
+    let axum::Json(body) = body?;
+    let request = parse_request(body, serde_json::json!({"mode": "threaded"}), |request| {
+        state.validate_schedule(request)
+    })?;
+    Ok(axum::Json(state.create_schedule(&request)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/schedules",
+    description = "Schedules with their next window, oldest first",
+    responses((status = 200, body = Vec<ScheduleInfo>))
+)]
+async fn schedules_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> axum::Json<Vec<ScheduleInfo>> {
+    axum::Json(state.schedules())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/schedules/{id}",
+    description = "Delete a schedule, stopping the run of a window in progress",
+    params(("id" = u64, Path, description = "Schedule id")),
+    responses((status = 200, body = MessageResponse), (status = 404, body = ErrorResponse))
+)]
+async fn delete_schedule_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+    id: Result<axum::extract::Path<u64>, PathRejection>,
+) -> ApiResult<MessageResponse> {
+    let axum::extract::Path(id) = id?;
+    // Stopping the window's run blocks
+    let result = tokio::task::spawn_blocking(move || state.delete_schedule(id))
+        .await
+        .expect("delete_schedule task panicked");
+    message(result?)
+}
+
+#[utoipa::path(
+    post,
+    path = "/end-cpu",
+    description = "Stop the stress test and cancel an armed one (idempotent)",
+    responses((status = 200, body = MessageResponse))
+)]
+async fn end_cpu_handler(
+    axum::extract::State(state): axum::extract::State<Arc<StressEngine>>,
+) -> ApiResult<MessageResponse> {
+    let result = tokio::task::spawn_blocking(move || state.stop())
+        .await
+        .expect("end_cpu task panicked");
+    message(result)
+}
+
+#[utoipa::path(
+    get,
+    path = "/log-level",
+    description = "Current log filter",
+    responses(
+        (status = 200, body = LogLevel),
+        (status = 409, description = "Logging is managed by the embedding application", body = ErrorResponse)
+    )
+)]
+async fn get_log_level_handler() -> ApiResult<LogLevel> {
+    let filter = logging::current_filter()
+        .ok_or_else(|| EngineError::Conflict("logging is not managed by this process".to_string()))?;
+    Ok(axum::Json(LogLevel { filter }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/log-level",
+    description = "Change the log filter (RUST_LOG syntax) while running",
+    request_body = LogLevel,
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 400, description = "Invalid filter", body = ErrorResponse),
+        (status = 409, description = "Logging is managed by the embedding application", body = ErrorResponse)
+    )
+)]
+async fn set_log_level_handler(request: Result<axum::Json<LogLevel>, JsonRejection>) -> ApiResult<MessageResponse> {
+    let axum::Json(request) = request?;
+    logging::set_filter(&request.filter)?;
+    tracing::info!(filter = %request.filter, "log filter changed");
+    message(format!("Log filter set to {}", request.filter))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Distributed CPU Stress Reporter"),
+    paths(
+        start_cpu_handler,
+        end_cpu_handler,
+        trigger_burst_handler,
+        calibrate_handler,
+        benchmark_handler,
+        config_handler,
+        get_log_level_handler,
+        set_log_level_handler,
+        create_job_handler,
+        create_mixed_handler,
+        jobs_handler,
+        stop_job_handler,
+        create_schedule_handler,
+        schedules_handler,
+        delete_schedule_handler,
+        cpu_perf_handler,
+        burst_perf_handler,
+        mem_perf_handler,
+        status_handler,
+        history_handler,
+        histograms_handler,
+        process_stats_handler,
+        latency_handler,
+        peers_handler,
+        burst_events_handler,
+        events_handler,
+        healthz_handler,
+        readyz_handler,
+        harness_advance_handler,
+        harness_clock_handler,
+    )
+)]
+struct ApiDoc;
+
+// OpenAPI 3.1 description of the HTTP API
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+// HTTP handler for GET /openapi.json
+async fn openapi_handler() -> axum::Json<utoipa::openapi::OpenApi> {
+    axum::Json(openapi())
+}
+
+// Unknown routes get a JSON 404 like every other error
+async fn not_found_handler(uri: axum::http::Uri) -> ApiError {
+    ApiError {
+        status: StatusCode::NOT_FOUND,
+        message: format!("no such endpoint: {}", uri.path()),
+        errors: Vec::new(),
+    }
+}
+
+// HTTP API for an agent; also usable in-process, e.g. with StressEngine::test_harness
+pub fn build_router(state: Arc<StressEngine>) -> Router {
+    let mut router = Router::new()
+        .route("/cpu-perf", get(cpu_perf_handler))
+        .route("/burst-perf", get(burst_perf_handler))
+        .route("/mem-perf", get(mem_perf_handler))
+        .route("/status", get(status_handler))
+        .route("/history", get(history_handler))
+        .route("/histograms", get(histograms_handler))
+        .route("/process-stats", get(process_stats_handler))
+        .route("/latency", get(latency_handler))
+        .route("/peers", get(peers_handler))
+        .route("/burst-events", get(burst_events_handler))
+        .route("/events", get(events_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler))
+        .route("/start-cpu", post(start_cpu_handler))
+        .route("/end-cpu", post(end_cpu_handler))
+        .route("/trigger-burst", post(trigger_burst_handler))
+        .route("/calibrate", post(calibrate_handler))
+        .route("/benchmark", post(benchmark_handler))
+        .route("/config", patch(config_handler))
+        .route("/log-level", get(get_log_level_handler).put(set_log_level_handler))
+        .route("/jobs", get(jobs_handler).post(create_job_handler))
+        .route("/jobs/:id", delete(stop_job_handler))
+        .route("/mixed", post(create_mixed_handler))
+        .route("/schedules", get(schedules_handler).post(create_schedule_handler))
+        .route("/schedules/:id", delete(delete_schedule_handler))
+        .route("/openapi.json", get(openapi_handler))
+        .fallback(not_found_handler);
+    if state.harness.is_some() {
+        router = router
+            .route("/harness/advance", post(harness_advance_handler))
+            .route("/harness/clock", get(harness_clock_handler));
+    }
+
+    router
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(&state),
+            record_control_middleware,
+        ))
+        .layer(axum::middleware::from_fn(log_requests_middleware))
+        .with_state(state)
+}
+
+// Bind the Unix domain socket of --uds with the given permissions. A socket left behind by an agent that
+// didn't exit cleanly is replaced; one another agent is still serving on, or any other file, is not.
+#[cfg(unix)]
+pub fn bind_unix(path: &std::path::Path, mode: u32) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(std::io::Error::new(std::io::ErrorKind::AddrInUse, "another process is serving on it"));
+        }
+        std::fs::remove_file(path)?;
+    }
+    // Create the socket with `mode` already applied instead of tightening it after bind, when a client could
+    // have connected in between. umask is process-wide, but nothing else creates files while the agent starts.
+    // SAFETY: umask only swaps the process file mode mask
+    let previous = unsafe { libc::umask(!(mode as libc::mode_t) & 0o777) };
+    let listener = tokio::net::UnixListener::bind(path);
+    // SAFETY: as above
+    unsafe { libc::umask(previous) };
+    let listener = listener?;
+    // bind already applied `mode`; this also sets bits like setgid that the umask doesn't cover
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(listener)
+}
+
+// Serve `router` on a Unix domain socket until the process exits. axum 0.7 only serves TCP listeners, so
+// each connection is handed to hyper directly (HTTP/1.1 and HTTP/2, with upgrades).
+#[cfg(unix)]
+pub async fn serve_unix(listener: tokio::net::UnixListener, router: Router) {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::service::TowerToHyperService;
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                // e.g. out of file descriptors; back off instead of spinning
+                tracing::warn!(error = %e, "failed to accept a Unix socket connection");
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let service = TowerToHyperService::new(router.clone());
+        tokio::spawn(async move {
+            let connection = Builder::new(TokioExecutor::new());
+            if let Err(e) = connection.serve_connection_with_upgrades(TokioIo::new(stream), service).await {
+                tracing::debug!(error = %e, "Unix socket connection closed with an error");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{EngineOptions, ExecutionMode};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    async fn call(router: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    fn harness_router() -> Router {
+        build_router(Arc::new(StressEngine::test_harness(EngineOptions {
+            num_cores: 1,
+            ..EngineOptions::default()
+        })))
+    }
+
+    #[tokio::test]
+    async fn start_advance_and_read_status() {
+        let router = harness_router();
+
+        let (status, body) = call(&router, "POST", "/start-cpu", r#"{"mode":"threaded"}"#).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let response: MessageResponse = serde_json::from_str(&body).unwrap();
+        assert!(response.message.contains("started"), "{}", body);
+
+        let (_, body) = call(&router, "POST", "/harness/advance", r#"{"ms":2000}"#).await;
+        let clock: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(clock["now_ms"].is_u64());
+        let (status, body) = call(&router, "POST", "/harness/advance", r#"{"ms":18446744073709551615}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("ms must be at most"), "{}", body);
+
+        let (_, body) = call(&router, "GET", "/status", "").await;
+        let snapshot: MetricsSnapshot = serde_json::from_str(&body).unwrap();
+        assert!(snapshot.running);
+        assert_eq!(snapshot.ops_per_second, 300_000);
+
+        let (_, body) = call(&router, "GET", "/history?last=1", "").await;
+        let history: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(history.len(), 1);
+
+        let (_, body) = call(&router, "GET", "/cpu-perf", "").await;
+        assert!(body.contains("300000"), "{}", body);
+        let (_, body) = call(&router, "GET", "/cpu-perf?units=kilo-ops", "").await;
+        assert_eq!(body, "300\n");
+        let (_, body) = call(&router, "GET", "/cpu-perf?units=per-core&format=json", "").await;
+        assert_eq!(body, r#"{"value":300000.0,"units":"per-core"}"#);
+        let (status, body) = call(&router, "GET", "/burst-perf?units=mega", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("unknown variant"), "{}", body);
+    }
+
+    fn error(body: &str) -> String {
+        serde_json::from_str::<ErrorResponse>(body).unwrap_or_else(|_| panic!("not an error body: {}", body)).error
+    }
+
+    #[tokio::test]
+    async fn errors_have_status_codes_and_json_bodies() {
+        let router = harness_router();
+        let (status, body) = call(&router, "POST", "/start-cpu", r#"{"mode":"throttled","duty_cycle_ms":1}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("duty_cycle_ms"), "{}", body);
+        let (status, body) = call(&router, "POST", "/start-cpu", r#"{"mode":"sideways"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("unknown variant"), "{}", body);
+
+        let (status, body) = call(&router, "POST", "/trigger-burst", "{}").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(error(&body).contains("burst_trigger"), "{}", body);
+        let (status, body) = call(&router, "POST", "/trigger-burst", "").await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        let (status, _) = call(&router, "POST", "/trigger-burst", "{not json").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = call(&router, "POST", "/trigger-burst", r#"{"duration_ms":"abc"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("duration_ms"), "{}", body);
+
+        let (status, body) = call(&router, "POST", "/calibrate", r#"{"duration_ms":1}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("duration_ms"), "{}", body);
+        let (status, body) = call(&router, "POST", "/calibrate", r#"{"duration_ms":"oops"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("duration_ms"), "{}", body);
+
+        let (status, _) = call(&router, "PATCH", "/config", r#"{"worker_ops":0}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, body) = call(&router, "PATCH", "/config", r#"{"worker_ops":5000}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, r#"{"message":"worker_ops set to 5000"}"#);
+
+        let (status, body) = call(&router, "GET", "/no-such-endpoint", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(error(&body).contains("/no-such-endpoint"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn recorded_control_bodies_are_size_limited() {
+        let path = std::env::temp_dir().join(format!("cpu-stress-record-test-{}.jsonl", std::process::id()));
+        let router = build_router(Arc::new(StressEngine::test_harness(EngineOptions {
+            num_cores: 1,
+            control_recorder: Some(ControlRecorder::open(&path, None).unwrap()),
+            ..EngineOptions::default()
+        })));
+        let (status, body) = call(&router, "POST", "/start-cpu", r#"{"mode":"threaded"}"#).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let huge = format!(r#"{{"mode":"threaded","padding":"{}"}}"#, "x".repeat(3 * 1024 * 1024));
+        let (status, body) = call(&router, "POST", "/start-cpu", &huge).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!error(&body).is_empty());
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recorded.lines().count(), 1, "{}", &recorded[..recorded.len().min(200)]);
+    }
+
+    #[tokio::test]
+    async fn openapi_document_lists_every_route() {
+        let (status, body) = call(&harness_router(), "GET", "/openapi.json", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let document: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(document["openapi"].as_str().unwrap().starts_with("3."));
+        let paths = ["/start-cpu", "/end-cpu", "/calibrate", "/benchmark", "/config", "/jobs", "/jobs/{id}"];
+        for path in paths.into_iter().chain(["/log-level"]) {
+            assert!(document["paths"][path].is_object(), "missing {}", path);
+        }
+        let responses = &document["paths"]["/start-cpu"]["post"]["responses"];
+        assert!(responses["409"].is_object());
+        assert!(document["components"]["schemas"]["StartCpuRequest"].is_object());
+    }
+
+    #[tokio::test]
+    async fn jobs_run_on_disjoint_cores() {
+        let state = Arc::new(StressEngine::new(EngineOptions {
+            num_cores: 1,
+            simulate: true,
+            cores: Some(vec![0, 1, 2, 3]),
+            ..EngineOptions::default()
+        }));
+        let router = build_router(Arc::clone(&state));
+
+        let (_, body) = call(&router, "POST", "/jobs", r#"{"mode":"threaded","cores":"0-1","name":"steady"}"#).await;
+        let job: JobInfo = serde_json::from_str(&body).unwrap();
+        assert_eq!((job.name.as_str(), job.cores.clone()), ("steady", vec![0, 1]));
+
+        let (status, body) = call(&router, "POST", "/jobs", r#"{"mode":"bursty","cores":"1-2"}"#).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(error(&body).contains("cores 1 are already used by job"), "{}", body);
+        // Every problem at once: a bad mode, an out-of-range option, and cores in use
+        let invalid = r#"{"mode":"turbo","cores":"1-2","utilization":250}"#;
+        let (status, body) = call(&router, "POST", "/jobs", invalid).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let response: ErrorResponse = serde_json::from_str(&body).unwrap();
+        let fields: Vec<&str> = response.errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, ["mode", "utilization", "cores"], "{}", body);
+        assert!(response.errors[0].message.contains("unknown variant `turbo`"), "{}", body);
+        // A huge core range is refused before it is expanded
+        let (status, body) = call(&router, "POST", "/jobs", r#"{"mode":"threaded","cores":"0-4000000000"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("out of range"), "{}", body);
+        let (_, body) = call(&router, "POST", "/jobs", r#"{"mode":"bursty","cores":"2-3","duration_secs":60}"#).await;
+        assert!(serde_json::from_str::<JobInfo>(&body).is_ok(), "{}", body);
+        let (status, body) = call(&router, "POST", "/start-cpu", r#"{"mode":"threaded"}"#).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(error(&body).starts_with("2 job(s) are running"), "{}", body);
+
+        let (_, body) = call(&router, "GET", "/jobs", "").await;
+        let jobs: Vec<JobInfo> = serde_json::from_str(&body).unwrap();
+        assert_eq!(jobs.len(), 2);
+
+        for job in jobs {
+            let (_, body) = call(&router, "DELETE", &format!("/jobs/{}", job.id), "").await;
+            assert_eq!(body, format!(r#"{{"message":"Job {} stopped"}}"#, job.id));
+        }
+        let (status, _) = call(&router, "DELETE", "/jobs/1", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&router, "DELETE", "/jobs/first", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(state.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mixed_runs_split_the_cores_between_modes() {
+        let state = Arc::new(StressEngine::new(EngineOptions {
+            num_cores: 1,
+            simulate: true,
+            cores: Some(vec![0, 1, 2, 3]),
+            ..EngineOptions::default()
+        }));
+        let router = build_router(Arc::clone(&state));
+
+        let mixed = r#"{"threaded":{"cores":2},"bursty":{"cores":1,"utilization":40},"duration_secs":60}"#;
+        let (status, body) = call(&router, "POST", "/mixed", mixed).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let jobs: Vec<JobInfo> = serde_json::from_str(&body).unwrap();
+        let started: Vec<(&str, ExecutionMode, Vec<usize>)> =
+            jobs.iter().map(|job| (job.name.as_str(), job.mode, job.cores.clone())).collect();
+        let expected = [
+            ("mixed-threaded", ExecutionMode::Threaded, vec![0, 1]),
+            ("mixed-bursty", ExecutionMode::Bursty, vec![2]),
+        ];
+        assert_eq!(started, expected);
+
+        let (status, body) = call(&router, "POST", "/mixed", r#"{"throttled":{"cores":2}}"#).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(error(&body).contains("needs 2 cores, but only 1"), "{}", body);
+        let invalid = r#"{"fresh-process":{"cores":0},"throttled":{"cores":1,"utilization":250}}"#;
+        let (status, body) = call(&router, "POST", "/mixed", invalid).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error(&body).contains("fresh-process.cores"), "{}", body);
+        let (status, _) = call(&router, "POST", "/mixed", "{}").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        for job in jobs {
+            call(&router, "DELETE", &format!("/jobs/{}", job.id), "").await;
+        }
+        assert!(state.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn events_stream_metrics_lifecycle_and_bursts() {
+        let state = Arc::new(StressEngine::test_harness(EngineOptions {
+            num_cores: 1,
+            ..EngineOptions::default()
+        }));
+        let router = build_router(Arc::clone(&state));
+        let request = Request::builder().uri("/events").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        call(&router, "POST", "/start-cpu", r#"{"mode":"bursty","burst_distribution":"fixed"}"#).await;
+        state.advance_clock(10_000);
+        let mut body = response.into_body();
+        let mut text = String::new();
+        while !["event: started", "event: metrics", "event: burst-start"].iter().all(|name| text.contains(name)) {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame()).await;
+            let data = frame.expect("timed out").unwrap().unwrap().into_data().unwrap();
+            text.push_str(std::str::from_utf8(&data).unwrap());
+        }
+        assert!(text.contains(r#"data: {"event":"started","#), "{}", text);
+        assert!(text.contains(r#""ops_per_second":"#), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn harness_routes_exist_only_on_harness_engines() {
+        let router = build_router(Arc::new(StressEngine::new(EngineOptions::default())));
+        let (status, _) = call(&router, "GET", "/harness/clock", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = call(&harness_router(), "GET", "/harness/clock", "").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serves_on_a_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("stress-test-{}.sock", std::process::id()));
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(bind_unix(&path, 0o600).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let listener = bind_unix(&path, 0o600).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        tokio::spawn(serve_unix(listener, harness_router()));
+        let in_use = bind_unix(&path, 0o600).unwrap_err();
+        assert_eq!(in_use.kind(), std::io::ErrorKind::AddrInUse);
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.contains(r#""running":false"#), "{}", response);
+    }
+}
@@ -0,0 +1,42 @@
+// CPU stress testing and performance reporting: a stress engine with burst-aware metrics,
+// served over HTTP (and optionally gRPC). The binary is a thin wrapper around this library.
+pub mod benchmark;
+pub mod campaign;
+pub mod cgroup;
+pub mod clickhouse;
+pub mod client;
+pub mod cores;
+pub mod describe;
+pub mod discovery;
+pub mod encryption;
+pub mod engine;
+mod harness;
+pub mod http;
+pub mod logging;
+pub mod metrics;
+pub mod package;
+pub mod persist;
+pub mod power;
+pub mod process_stats;
+pub mod registry;
+pub mod report;
+pub mod schedule;
+pub mod sketch;
+pub mod sync;
+pub mod units;
+pub mod webhooks;
+pub mod workloads;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+pub use engine::{
+    BurstBackend, BurstDistribution, BurstSource, BurstTrigger, ConfigUpdate, EngineError, EngineOptions, ExecutionMode,
+    JobInfo, JobRequest, RunOptions, StartCpuRequest, StressEngine, Workload,
+};
+pub use http::build_router;
+pub use metrics::{
+    Baseline, BurstBatch, BurstEvent, Histograms, HistorySample, LatencyReport, LifecycleEvent, MeasurementQuality,
+    MetricsSnapshot, NormalizedScore, QualityGrade,
+};
+pub use sketch::Sketch;
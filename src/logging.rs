@@ -0,0 +1,87 @@
+// Logging: the agent's tracing events (runs, jobs, workers, sync, webhooks, HTTP requests) as text or as
+// JSON lines for log shippers, behind a filter that can be changed while running (PUT /log-level).
+use crate::engine::EngineError;
+use std::sync::OnceLock;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum LogFormat {
+    // Human-readable lines
+    Text,
+    // One JSON object per event, with its fields at the top level
+    Json,
+}
+
+// Filter used when neither --log-level nor RUST_LOG is set
+pub const DEFAULT_LOG_FILTER: &str = "info";
+
+// Swaps the filter of the subscriber installed by init
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+fn parse_filter(filter: &str) -> Result<EnvFilter, String> {
+    EnvFilter::builder()
+        .parse(filter)
+        .map_err(|e| format!("invalid log filter \"{}\": {}", filter, e))
+}
+
+// Install the process-wide subscriber. `filter` uses RUST_LOG syntax (e.g.
+// "info,distributed_cpu_stress_reporter::workloads=debug") and defaults to $RUST_LOG, else "info".
+pub fn init(format: LogFormat, filter: Option<&str>) -> Result<(), String> {
+    let filter = match filter {
+        Some(filter) => filter.to_string(),
+        None => std::env::var("RUST_LOG")
+            .ok()
+            .filter(|filter| !filter.is_empty())
+            .unwrap_or_else(|| DEFAULT_LOG_FILTER.to_string()),
+    };
+    let (filter, handle) = reload::Layer::new(parse_filter(&filter)?);
+    let output = match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().flatten_event(true).boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .try_init()
+        .map_err(|e| format!("failed to install the logger: {}", e))?;
+    let _ = FILTER.set(handle);
+    Ok(())
+}
+
+// Current filter, if this process installed the logger with init
+pub fn current_filter() -> Option<String> {
+    FILTER.get()?.with_current(|filter| filter.to_string()).ok()
+}
+
+// Replace the filter of the running logger (PUT /log-level)
+pub fn set_filter(filter: &str) -> Result<(), EngineError> {
+    let handle = FILTER
+        .get()
+        .ok_or_else(|| EngineError::Conflict("logging is not managed by this process".to_string()))?;
+    let filter = parse_filter(filter).map_err(EngineError::Invalid)?;
+    handle
+        .reload(filter)
+        .map_err(|e| EngineError::Conflict(format!("failed to change the log filter: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_changes_at_runtime() {
+        assert!(matches!(set_filter("debug"), Err(EngineError::Conflict(_))));
+        assert!(init(LogFormat::Json, Some("warn[")).is_err());
+
+        init(LogFormat::Json, Some("warn")).unwrap();
+        assert_eq!(current_filter().as_deref(), Some("warn"));
+        // Quiet levels only: the logger stays installed for the other tests in this binary
+        set_filter("warn,distributed_cpu_stress_reporter::workloads=error").unwrap();
+        assert_eq!(
+            current_filter().as_deref(),
+            Some("distributed_cpu_stress_reporter::workloads=error,warn")
+        );
+        assert!(matches!(set_filter("info=["), Err(EngineError::Invalid(_))));
+    }
+}
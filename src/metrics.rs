@@ -0,0 +1,1252 @@
+// Metrics: the per-second sampler, CPU temperatures, noise-floor calibration, burst events,
+// wakeup latency probes, and Prometheus rendering
+use crate::cgroup::CpuAllocation;
+use crate::engine::{
+    DEFAULT_WORKER_OPS, ExecutionMode, Heartbeat, JobInfo, RunGuards, RunSignal, StressEngine, Workload,
+    unix_time_ms,
+};
+use crate::power::{PowerMeter, PowerReading, RunEnergy};
+use crate::sketch::Sketch;
+use crate::workloads::{count_primes, is_prime};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Run state and latest metrics; body of GET /status
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MetricsSnapshot {
+    pub running: bool,
+    pub mode: ExecutionMode,
+    pub utilization: u64,
+    pub ops_per_second: u64,
+    pub burst_ops_per_second: u64,
+    // Measured system-wide CPU utilization (all processes), not just this agent's load
+    #[serde(default)]
+    pub cpu_utilization_percent: f32,
+    #[serde(default)]
+    pub per_core_utilization_percent: Vec<f32>,
+    // CPU package/core temperatures (empty if no sensors are available)
+    #[serde(default)]
+    pub temperatures: Vec<TemperatureReading>,
+    // Noise floor measured before the current (or most recent) run; None for simulated agents
+    #[serde(default)]
+    pub measurement_quality: Option<MeasurementQuality>,
+    // Single-core reference from POST /calibrate, and the ops/sec above relative to it (None until calibrated)
+    #[serde(default)]
+    pub baseline: Option<Baseline>,
+    #[serde(default)]
+    pub score: Option<NormalizedScore>,
+    // Worker threads/processes per run, and the CPUs detected for this agent (cpuset and cgroup quota)
+    #[serde(default)]
+    pub workers: usize,
+    // Whether the workers run on physical cores only (use_physical_cores_only), and the logical CPUs of
+    // each physical core with SMT siblings (e.g. [[0, 4], [1, 5]]; empty without SMT), to pair up the
+    // siblings of per_core_utilization_percent
+    #[serde(default)]
+    pub physical_cores_only: bool,
+    #[serde(default)]
+    pub smt_siblings: Vec<Vec<usize>>,
+    #[serde(default)]
+    pub cpu_allocation: Option<CpuAllocation>,
+    // Nice level of the workers of the current (or most recent) run; None if they keep the agent's
+    #[serde(default)]
+    pub nice: Option<i32>,
+    // What the workers compute, and the memory bandwidth of the stream workload over the last sampling
+    // interval (bytes read and written, 1 GB = 10^9 bytes; 0 for primes)
+    #[serde(default)]
+    pub workload: Workload,
+    #[serde(default)]
+    pub memory_gb_per_second: f64,
+    // Percentiles of the per-second ops and of the per-burst throughput of the current (or most recent)
+    // run, from the histograms; a throttling episode shows up as a low p50 long before it moves the mean
+    #[serde(default)]
+    pub percentiles: RunPercentiles,
+    // Why the current (or most recent) run was stopped by one of its auto-stop guards; None otherwise
+    #[serde(default)]
+    pub guard_trip: Option<GuardTrip>,
+    // When the run armed with start_at or start_delay_ms starts (Unix ms); None if no run is armed
+    #[serde(default)]
+    pub scheduled_start_ms: Option<u64>,
+    // Package power over the last sampling interval (RAPL; None without readable counters), and the energy
+    // of the current (or most recent) run
+    #[serde(default)]
+    pub power: Option<PowerReading>,
+    #[serde(default)]
+    pub energy: Option<RunEnergy>,
+    // Worker and background threads that panicked and were restarted since the agent started
+    #[serde(default)]
+    pub thread_restarts: u64,
+}
+
+// One entry of GET /history: the status as of one sampling interval
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct HistorySample {
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub status: MetricsSnapshot,
+}
+
+// Mergeable histograms of the current (or most recent) run; body of GET /histograms
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Histograms {
+    // Wall time of each worker batch: fresh-process workers and burst batches, in milliseconds
+    pub batch_latency_ms: Sketch,
+    // Total ops of each 1-second sampling interval while running
+    pub ops_per_second: Sketch,
+    // Throughput of each core's burst: its ops divided by its actual duration
+    #[serde(default)]
+    pub burst_ops_per_second: Sketch,
+}
+
+impl Histograms {
+    pub fn merge(&mut self, other: &Histograms) -> Result<(), String> {
+        self.batch_latency_ms.merge(&other.batch_latency_ms)?;
+        self.ops_per_second.merge(&other.ops_per_second)?;
+        self.burst_ops_per_second.merge(&other.burst_ops_per_second)
+    }
+}
+
+// Percentiles of one histogram (None without samples)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Percentiles {
+    pub samples: u64,
+    pub min: Option<f64>,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Percentiles {
+    pub fn new(sketch: &Sketch) -> Self {
+        Percentiles {
+            samples: sketch.count,
+            min: (sketch.count > 0).then_some(sketch.min),
+            p50: sketch.quantile(0.5),
+            p90: sketch.quantile(0.9),
+            p99: sketch.quantile(0.99),
+            max: (sketch.count > 0).then_some(sketch.max),
+        }
+    }
+}
+
+// Throughput percentiles of a run; `percentiles` in GET /status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RunPercentiles {
+    // Ops of each second the run was busy
+    pub ops_per_second: Percentiles,
+    // Ops/sec within each burst of each core (bursty mode)
+    pub burst_ops_per_second: Percentiles,
+}
+
+impl RunPercentiles {
+    pub fn new(histograms: &Histograms) -> Self {
+        RunPercentiles {
+            ops_per_second: Percentiles::new(&histograms.ops_per_second),
+            burst_ops_per_second: Percentiles::new(&histograms.burst_ops_per_second),
+        }
+    }
+}
+
+pub(crate) fn record_batch_latency(state: &StressEngine, elapsed: Duration) {
+    state.histograms.lock().unwrap().batch_latency_ms.add(elapsed.as_secs_f64() * 1000.0);
+}
+
+// Wakeups recorded by one core's latency probe
+#[derive(Debug, Clone)]
+pub(crate) struct CoreLatency {
+    interval: Duration,
+    // How much later than asked each wakeup came, in microseconds
+    lateness_us: Sketch,
+}
+
+impl CoreLatency {
+    pub(crate) fn new(interval: Duration) -> Self {
+        CoreLatency {
+            interval,
+            lateness_us: Sketch::default(),
+        }
+    }
+}
+
+// Percentiles of wakeup lateness, in microseconds (None without samples)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LatencyPercentiles {
+    pub samples: u64,
+    pub p50_us: Option<f64>,
+    pub p95_us: Option<f64>,
+    pub p99_us: Option<f64>,
+    pub max_us: Option<f64>,
+}
+
+impl LatencyPercentiles {
+    fn new(sketch: &Sketch) -> Self {
+        LatencyPercentiles {
+            samples: sketch.count,
+            p50_us: sketch.quantile(0.5),
+            p95_us: sketch.quantile(0.95),
+            p99_us: sketch.quantile(0.99),
+            max_us: (sketch.count > 0).then_some(sketch.max),
+        }
+    }
+}
+
+// One core's entry of GET /latency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CoreLatencyReport {
+    pub core_id: usize,
+    // The probe's sleep interval
+    pub interval_us: u64,
+    #[serde(flatten)]
+    pub latency: LatencyPercentiles,
+}
+
+// Body of GET /latency: how late the latency probes (latency_probe_us) woke up, over all cores and per
+// core, for the current (or most recent) run and the jobs; empty if no run had probes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LatencyReport {
+    #[serde(flatten)]
+    pub all_cores: LatencyPercentiles,
+    pub cores: Vec<CoreLatencyReport>,
+}
+
+impl LatencyReport {
+    pub(crate) fn new(cores: &std::collections::BTreeMap<usize, CoreLatency>) -> Self {
+        let mut all_cores = Sketch::default();
+        for core in cores.values() {
+            all_cores.merge(&core.lateness_us).expect("Latency sketches use the same accuracy");
+        }
+        LatencyReport {
+            all_cores: LatencyPercentiles::new(&all_cores),
+            cores: cores
+                .iter()
+                .map(|(&core_id, core)| CoreLatencyReport {
+                    core_id,
+                    interval_us: core.interval.as_micros() as u64,
+                    latency: LatencyPercentiles::new(&core.lateness_us),
+                })
+                .collect(),
+        }
+    }
+}
+
+// Latency probe: sleep `interval` at a time and record how much later than asked each wakeup comes. Run
+// next to the workers of a core, it shows how long the scheduler keeps a runnable thread waiting under load.
+pub(crate) fn latency_probe(
+    state: Arc<StressEngine>,
+    signal: Arc<RunSignal>,
+    heartbeat: Arc<Heartbeat>,
+    core_id: usize,
+    interval: Duration,
+) {
+    // Recorded locally and merged about once a second, so the probes don't contend on the shared lock
+    let mut pending = Sketch::default();
+    let mut merged_at = Instant::now();
+    while !signal.is_stopped() {
+        heartbeat.beat();
+        let start = Instant::now();
+        thread::sleep(interval);
+        let lateness = start.elapsed().saturating_sub(interval);
+        pending.add(lateness.as_secs_f64() * 1e6);
+
+        if merged_at.elapsed() >= Duration::from_secs(1) || signal.is_stopped() {
+            if let Some(core) = state.latency.lock().unwrap().get_mut(&core_id) {
+                core.lateness_us.merge(&pending).expect("Latency sketches use the same accuracy");
+            }
+            pending = Sketch::default();
+            merged_at = Instant::now();
+        }
+    }
+}
+
+// System CPU utilization measured by the sampler over the last sampling interval
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CpuUtilization {
+    pub(crate) overall_percent: f32,
+    pub(crate) per_core_percent: Vec<f32>,
+}
+
+// One CPU temperature sensor reading
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TemperatureReading {
+    // hwmon driver name, e.g. "coretemp" or "k10temp"
+    pub sensor: String,
+    // Sensor label, e.g. "Package id 0" or "Core 3"
+    pub label: String,
+    pub celsius: f32,
+}
+
+// hwmon drivers that report CPU package/core temperatures
+const CPU_HWMON_DRIVERS: &[&str] = &["coretemp", "k10temp", "zenpower", "cpu_thermal", "soc_thermal"];
+
+// Thermal sampler: read CPU package/core temperatures from hwmon (Linux only)
+#[cfg(target_os = "linux")]
+pub fn read_cpu_temperatures() -> Vec<TemperatureReading> {
+    let mut readings = Vec::new();
+    let Ok(entries) = std::fs::read_dir("/sys/class/hwmon") else {
+        return readings;
+    };
+
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let Ok(name) = std::fs::read_to_string(dir.join("name")) else {
+            continue;
+        };
+        let name = name.trim();
+        if !CPU_HWMON_DRIVERS.contains(&name) {
+            continue;
+        }
+
+        let Ok(files) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut sensor_readings: Vec<(u32, TemperatureReading)> = files
+            .flatten()
+            .filter_map(|file| {
+                // tempN_input holds millidegrees Celsius, tempN_label an optional label
+                let file_name = file.file_name().into_string().ok()?;
+                let index = file_name.strip_prefix("temp")?.strip_suffix("_input")?;
+                let millidegrees: i64 = std::fs::read_to_string(file.path()).ok()?.trim().parse().ok()?;
+                let label = std::fs::read_to_string(dir.join(format!("temp{}_label", index)))
+                    .map(|label| label.trim().to_string())
+                    .unwrap_or_else(|_| format!("temp{}", index));
+                Some((
+                    index.parse().unwrap_or(0),
+                    TemperatureReading {
+                        sensor: name.to_string(),
+                        label,
+                        celsius: millidegrees as f32 / 1000.0,
+                    },
+                ))
+            })
+            .collect();
+        sensor_readings.sort_by_key(|(index, _)| *index);
+        readings.extend(sensor_readings.into_iter().map(|(_, reading)| reading));
+    }
+
+    readings
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_cpu_temperatures() -> Vec<TemperatureReading> {
+    Vec::new()
+}
+
+// Sampling thread that measures operations per second with burst-aware metrics
+pub(crate) fn burst_sampler(state: Arc<StressEngine>, heartbeat: Arc<Heartbeat>) {
+    // System CPU usage is computed from the difference between consecutive refreshes
+    let mut system = sysinfo::System::new();
+    system.refresh_cpu_usage();
+    // A simulated agent's load isn't real, so neither would its power be
+    let mut power_meter = if state.simulated_ops_per_sec.is_some() {
+        PowerMeter::default()
+    } else {
+        PowerMeter::detect()
+    };
+    power_meter.sample();
+
+    loop {
+        heartbeat.beat();
+        thread::sleep(Duration::from_secs(1));
+
+        // System-wide CPU utilization (includes other processes and reflects throttling)
+        system.refresh_cpu_usage();
+        let cpu_utilization = CpuUtilization {
+            overall_percent: system.global_cpu_usage(),
+            per_core_percent: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+        };
+
+        // CPU temperatures and package power for correlating ops/sec decay with heat
+        record_sample(&state, cpu_utilization, read_cpu_temperatures(), power_meter.sample(), unix_time_ms());
+    }
+}
+
+// Close one sampling interval: turn the counters into per-second rates and append to the history
+pub(crate) fn record_sample(
+    state: &StressEngine,
+    cpu_utilization: CpuUtilization,
+    temperatures: Vec<TemperatureReading>,
+    power: Option<PowerReading>,
+    timestamp_ms: u64,
+) {
+    *state.cpu_utilization.lock().unwrap() = cpu_utilization;
+
+    // Burst-only metrics
+    let burst_ops = state.burst_operations.swap(0, Ordering::Relaxed);
+    let burst_time_ms = state.burst_time_ms.swap(0, Ordering::Relaxed);
+
+    if burst_time_ms > 0 {
+        let time_s = burst_time_ms as f64 / 1000.0;
+        let ops_per_sec = (burst_ops as f64 / time_s) as u64;
+        state
+            .burst_ops_per_second
+            .store(ops_per_sec, Ordering::Relaxed);
+    } else {
+        state.burst_ops_per_second.store(0, Ordering::Relaxed);
+    }
+
+    // Total ops/sec (includes idle time for threaded and fresh-process modes)
+    let total_ops = state.current_counter.swap(0, Ordering::Relaxed);
+    state.operations_per_second.store(total_ops, Ordering::Relaxed);
+    let memory_bytes = state.memory_counter.swap(0, Ordering::Relaxed);
+    state.memory_bytes_per_second.store(memory_bytes, Ordering::Relaxed);
+    if state.is_busy() {
+        state.histograms.lock().unwrap().ops_per_second.add(total_ops as f64);
+        if let Some(power) = &power {
+            state.energy.lock().unwrap().add(power, total_ops);
+        }
+    }
+    check_ops_drop(state, total_ops, timestamp_ms);
+    check_guards(state, total_ops, timestamp_ms);
+
+    *state.temperatures.lock().unwrap() = temperatures;
+    *state.power.lock().unwrap() = power;
+
+    // Keep a bounded per-second history
+    let sample = HistorySample {
+        timestamp_ms,
+        status: state.snapshot(),
+    };
+    // No subscribers is fine
+    let _ = state.sample_events.send(sample.clone());
+    let mut history = state.history.lock().unwrap();
+    if history.len() >= state.history_capacity {
+        history.pop_front();
+    }
+    history.push_back(sample);
+}
+
+// Compare the run's throughput with its peak. Bursty runs are judged by their burst-only rate, since
+// their total rate follows the idle periods; samples without burst activity are skipped.
+fn check_ops_drop(state: &StressEngine, total_ops: u64, timestamp_ms: u64) {
+    let Some(threshold_percent) = state.ops_drop_alert_percent else {
+        return;
+    };
+    if !state.is_running.load(Ordering::Relaxed) {
+        return;
+    }
+    let mode = state.config.borrow().mode;
+    let ops_per_second = match mode {
+        ExecutionMode::Bursty => state.burst_ops_per_second.load(Ordering::Relaxed),
+        _ => total_ops,
+    };
+    if mode == ExecutionMode::Bursty && ops_per_second == 0 {
+        return;
+    }
+
+    let mut detector = state.ops_drop_detector.lock().unwrap();
+    let event = match detector.observe(ops_per_second, threshold_percent) {
+        Some(Some(drop_percent)) => LifecycleEvent::OpsDrop {
+            timestamp_ms,
+            mode,
+            ops_per_second,
+            peak_ops_per_second: detector.peak(),
+            drop_percent,
+        },
+        Some(None) => LifecycleEvent::OpsRecovered {
+            timestamp_ms,
+            mode,
+            ops_per_second,
+            peak_ops_per_second: detector.peak(),
+        },
+        None => return,
+    };
+    drop(detector);
+    emit_lifecycle_event(state, event);
+}
+
+// Feed the run's throughput to its auto-stop guards, and stop the run when one trips. Like the ops-drop
+// check, bursty runs are judged by their burst-only rate and samples without burst activity are skipped.
+fn check_guards(state: &StressEngine, total_ops: u64, timestamp_ms: u64) {
+    if !state.is_running.load(Ordering::Relaxed) {
+        return;
+    }
+    let (mode, guards) = {
+        let config = state.config.borrow();
+        (config.mode, config.guards)
+    };
+    let ops_per_second = match mode {
+        ExecutionMode::Bursty => state.burst_ops_per_second.load(Ordering::Relaxed),
+        _ => total_ops,
+    };
+    if guards.is_empty() || (mode == ExecutionMode::Bursty && ops_per_second == 0) {
+        return;
+    }
+
+    let Some((rule, window_ops_per_second, peak_ops_per_second)) =
+        state.guard_window.lock().unwrap().observe(ops_per_second, &guards)
+    else {
+        return;
+    };
+    let reason = match rule {
+        GuardRule::OpsFloor => format!(
+            "ops/sec averaged {} over the last {} s, below the floor of {}",
+            window_ops_per_second,
+            guards.window_secs,
+            guards.ops_floor.unwrap_or_default()
+        ),
+        GuardRule::OpsDrop => format!(
+            "ops/sec averaged {} over the last {} s, {:.1}% below the run's peak of {} (limit {}%)",
+            window_ops_per_second,
+            guards.window_secs,
+            (peak_ops_per_second - window_ops_per_second) as f64 * 100.0 / peak_ops_per_second as f64,
+            peak_ops_per_second,
+            guards.max_drop_percent.unwrap_or_default()
+        ),
+    };
+    state.stop_by_guard(GuardTrip {
+        timestamp_ms,
+        mode,
+        rule,
+        window_ops_per_second,
+        peak_ops_per_second,
+        reason,
+    });
+}
+
+// How much run-to-run noise to expect from this environment, judged from the calibration spread:
+// A = under 2%, B = under 5%, C = 5% or more (consider discarding)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub enum QualityGrade {
+    A,
+    B,
+    C,
+}
+
+impl QualityGrade {
+    pub fn from_noise_percent(noise_percent: f64) -> Self {
+        if noise_percent < 2.0 {
+            QualityGrade::A
+        } else if noise_percent < 5.0 {
+            QualityGrade::B
+        } else {
+            QualityGrade::C
+        }
+    }
+}
+
+// Result of the noise-floor calibration run before each stress test
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MeasurementQuality {
+    pub grade: QualityGrade,
+    // Coefficient of variation of the calibration slice durations, in percent
+    pub noise_percent: f64,
+    // Single-core ops/sec during calibration
+    pub calibration_ops_per_sec: u64,
+}
+
+// Calibration workload: the same fixed range of prime candidates, timed repeatedly
+const CALIBRATION_SLICES: usize = 10;
+
+const CALIBRATION_RANGE: std::ops::Range<u64> = 1_000_000..1_020_000;
+
+// Measure the noise floor: time identical slices of work on one core and grade their spread.
+// Takes a few hundred milliseconds on an idle machine.
+pub fn measure_noise_floor() -> MeasurementQuality {
+    let run_slice = || {
+        let start = Instant::now();
+        let ops = CALIBRATION_RANGE.filter(|&n| is_prime(n)).count() as u64;
+        (ops, start.elapsed().as_secs_f64())
+    };
+
+    // Warm-up slice so caches and clock frequency settle before timing
+    run_slice();
+
+    let mut total_ops = 0u64;
+    let mut durations = Vec::with_capacity(CALIBRATION_SLICES);
+    for _ in 0..CALIBRATION_SLICES {
+        let (ops, secs) = run_slice();
+        total_ops += ops;
+        durations.push(secs);
+    }
+
+    let total_secs: f64 = durations.iter().sum();
+    let mean = total_secs / durations.len() as f64;
+    let variance = durations.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / durations.len() as f64;
+    let noise_percent = if mean > 0.0 { variance.sqrt() / mean * 100.0 } else { 0.0 };
+
+    MeasurementQuality {
+        grade: QualityGrade::from_noise_percent(noise_percent),
+        noise_percent,
+        calibration_ops_per_sec: (total_ops as f64 / total_secs) as u64,
+    }
+}
+
+// Single-core reference throughput measured by POST /calibrate. Raw ops/sec depend on the hardware, so
+// run metrics are also reported relative to it to compare heterogeneous agents.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Baseline {
+    // Ops/sec of one core running the fixed baseline workload
+    pub ops_per_second: u64,
+    pub measured_ms: u64,
+    pub duration_ms: u64,
+}
+
+// Run throughput in baseline cores: 1.0 = what one core did during calibration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct NormalizedScore {
+    // ops_per_second / baseline
+    pub score: f64,
+    // score divided by the number of worker cores (1.0 = every core as fast as at calibration)
+    pub score_per_core: f64,
+    // burst_ops_per_second / baseline (bursty mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst_score: Option<f64>,
+}
+
+impl NormalizedScore {
+    pub fn new(baseline: &Baseline, status: &MetricsSnapshot, num_cores: usize) -> Option<Self> {
+        if baseline.ops_per_second == 0 {
+            return None;
+        }
+        let relative = |ops_per_second: u64| ops_per_second as f64 / baseline.ops_per_second as f64;
+        let score = relative(status.ops_per_second);
+        Some(NormalizedScore {
+            score,
+            score_per_core: score / num_cores.max(1) as f64,
+            burst_score: (status.mode == ExecutionMode::Bursty).then(|| relative(status.burst_ops_per_second)),
+        })
+    }
+}
+
+// Baseline workload: find the first DEFAULT_WORKER_OPS primes (one default fresh-process worker's work) on
+// the calling thread, over and over for `duration`; returns ops/sec
+pub fn measure_baseline(duration: Duration) -> u64 {
+    // Warm-up so caches and clock frequency settle before timing
+    count_primes(DEFAULT_WORKER_OPS);
+
+    let start = Instant::now();
+    let mut ops = 0;
+    while start.elapsed() < duration {
+        ops += count_primes(DEFAULT_WORKER_OPS);
+    }
+    (ops as f64 / start.elapsed().as_secs_f64()) as u64
+}
+
+// Emitted at the start and end of every burst (GET /burst-events, --log-burst-events)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum BurstEvent {
+    BurstStart {
+        core_id: usize,
+        timestamp_ms: u64,
+        planned_ms: u64,
+    },
+    BurstEnd {
+        core_id: usize,
+        timestamp_ms: u64,
+        planned_ms: u64,
+        actual_ms: u64,
+        ops: u64,
+        // Intra-burst throughput curve, one entry per completed batch
+        batches: Vec<BurstBatch>,
+    },
+}
+
+// One batch of a burst: when it completed (ms after the burst started) and the ops it performed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BurstBatch {
+    pub completed_ms: u64,
+    pub ops: u64,
+}
+
+impl BurstEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BurstEvent::BurstStart { .. } => "burst-start",
+            BurstEvent::BurstEnd { .. } => "burst-end",
+        }
+    }
+}
+
+// Publish a burst event to SSE subscribers and the optional stdout log, and add finished bursts to the
+// burst throughput histogram
+pub(crate) fn emit_burst_event(state: &StressEngine, event: BurstEvent) {
+    if let BurstEvent::BurstEnd { actual_ms, ops, .. } = event
+        && actual_ms > 0
+    {
+        let ops_per_second = ops as f64 * 1000.0 / actual_ms as f64;
+        state.histograms.lock().unwrap().burst_ops_per_second.add(ops_per_second);
+    }
+    if state.log_burst_events {
+        println!("{}", serde_json::to_string(&event).expect("Failed to serialize burst event"));
+    }
+    // No subscribers is fine
+    let _ = state.burst_events.send(event);
+}
+
+// Run lifecycle and metric threshold events, delivered to --webhook URLs and GET /events
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum LifecycleEvent {
+    Started {
+        timestamp_ms: u64,
+        mode: ExecutionMode,
+    },
+    Stopped {
+        timestamp_ms: u64,
+        mode: ExecutionMode,
+    },
+    JobStarted {
+        timestamp_ms: u64,
+        job: JobInfo,
+    },
+    // A job's duration_secs elapsed
+    JobCompleted {
+        timestamp_ms: u64,
+        job: JobInfo,
+    },
+    // A job was stopped with DELETE /jobs/{id}
+    JobStopped {
+        timestamp_ms: u64,
+        job: JobInfo,
+    },
+    // The run's ops/sec fell more than the alert threshold below its peak
+    OpsDrop {
+        timestamp_ms: u64,
+        mode: ExecutionMode,
+        ops_per_second: u64,
+        peak_ops_per_second: u64,
+        drop_percent: f64,
+    },
+    // ...and is back within the threshold
+    OpsRecovered {
+        timestamp_ms: u64,
+        mode: ExecutionMode,
+        ops_per_second: u64,
+        peak_ops_per_second: u64,
+    },
+    // One of the run's auto-stop guards tripped; the run is stopped right after (a stopped event follows)
+    GuardTripped(GuardTrip),
+}
+
+impl LifecycleEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LifecycleEvent::Started { .. } => "started",
+            LifecycleEvent::Stopped { .. } => "stopped",
+            LifecycleEvent::JobStarted { .. } => "job-started",
+            LifecycleEvent::JobCompleted { .. } => "job-completed",
+            LifecycleEvent::JobStopped { .. } => "job-stopped",
+            LifecycleEvent::OpsDrop { .. } => "ops-drop",
+            LifecycleEvent::OpsRecovered { .. } => "ops-recovered",
+            LifecycleEvent::GuardTripped(_) => "guard-tripped",
+        }
+    }
+}
+
+pub(crate) fn emit_lifecycle_event(state: &StressEngine, event: LifecycleEvent) {
+    // No subscribers is fine
+    let _ = state.lifecycle_events.send(event);
+}
+
+// Which auto-stop guard stopped a run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum GuardRule {
+    // stop_if_ops_below
+    OpsFloor,
+    // stop_if_drop_percent_exceeds
+    OpsDrop,
+}
+
+// A run stopped by one of its auto-stop guards: the rule, the window average that broke it, and the
+// highest window average of the run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GuardTrip {
+    pub timestamp_ms: u64,
+    pub mode: ExecutionMode,
+    pub rule: GuardRule,
+    pub window_ops_per_second: u64,
+    pub peak_ops_per_second: u64,
+    pub reason: String,
+}
+
+// Rolling window of a run's per-second ops for its auto-stop guards. A guard is judged once the window
+// is full, on the window's average, so a single slow sample (a stalled sampler, a brief preemption) can't
+// stop a run.
+#[derive(Debug, Default)]
+pub(crate) struct GuardWindow {
+    samples: VecDeque<u64>,
+    // Highest window average so far
+    peak: u64,
+    // Samples still to skip while a soft start brings the workers up
+    warmup: u64,
+    tripped: bool,
+}
+
+impl GuardWindow {
+    pub(crate) fn new(warmup: Duration) -> Self {
+        GuardWindow {
+            warmup: warmup.as_secs_f64().ceil() as u64,
+            ..GuardWindow::default()
+        }
+    }
+
+    // Feed one sample; returns the rule that tripped with the window average and the peak. Trips at
+    // most once.
+    pub(crate) fn observe(&mut self, ops_per_second: u64, guards: &RunGuards) -> Option<(GuardRule, u64, u64)> {
+        if self.tripped {
+            return None;
+        }
+        if self.warmup > 0 {
+            self.warmup -= 1;
+            return None;
+        }
+        let window = guards.window_secs.max(1) as usize;
+        while self.samples.len() >= window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ops_per_second);
+        if self.samples.len() < window {
+            return None;
+        }
+        let average = self.samples.iter().sum::<u64>() / window as u64;
+        self.peak = self.peak.max(average);
+
+        let below_floor = guards.ops_floor.is_some_and(|floor| average < floor);
+        let dropped = self.peak > 0
+            && guards
+                .max_drop_percent
+                .is_some_and(|limit| (self.peak - average) as f64 * 100.0 / self.peak as f64 > limit);
+        let rule = if below_floor {
+            GuardRule::OpsFloor
+        } else if dropped {
+            GuardRule::OpsDrop
+        } else {
+            return None;
+        };
+        self.tripped = true;
+        Some((rule, average, self.peak))
+    }
+
+    pub(crate) fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+}
+
+// Watches a run's per-second ops for drops below a share of the run's peak. Each drop is reported
+// once, and again only after ops have recovered.
+#[derive(Debug, Default)]
+pub(crate) struct OpsDropDetector {
+    peak: u64,
+    dropped: bool,
+}
+
+impl OpsDropDetector {
+    // Feed one sample; returns the drop percentage when a drop starts (Some(Some(p))) or ends
+    // (Some(None))
+    pub(crate) fn observe(&mut self, ops_per_second: u64, threshold_percent: f64) -> Option<Option<f64>> {
+        self.peak = self.peak.max(ops_per_second);
+        if self.peak == 0 {
+            return None;
+        }
+        let drop_percent = (self.peak - ops_per_second) as f64 * 100.0 / self.peak as f64;
+        match (self.dropped, drop_percent > threshold_percent) {
+            (false, true) => {
+                self.dropped = true;
+                Some(Some(drop_percent))
+            }
+            (true, false) => {
+                self.dropped = false;
+                Some(None)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn peak(&self) -> u64 {
+        self.peak
+    }
+}
+
+// Wire name of an execution mode (as used in JSON requests)
+pub fn mode_name(mode: ExecutionMode) -> String {
+    serde_json::to_value(mode)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{:?}", mode))
+}
+
+// Render the current metrics in Prometheus text exposition format
+pub fn render_prometheus(status: &MetricsSnapshot) -> String {
+    let mode = mode_name(status.mode);
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, value: f64, labels: &str| {
+        out.push_str(&format!("# HELP {} {}\n", name, help));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        out.push_str(&format!("{}{} {}\n", name, labels, value));
+    };
+
+    gauge(
+        "cpu_stress_running",
+        "Whether the CPU stress test is running (1) or stopped (0).",
+        status.running as u64 as f64,
+        &format!("{{mode=\"{}\"}}", mode),
+    );
+    gauge(
+        "cpu_stress_ops_per_second",
+        "Operations (primes found) per second over the last sampling interval.",
+        status.ops_per_second as f64,
+        &format!("{{mode=\"{}\"}}", mode),
+    );
+    gauge(
+        "cpu_stress_burst_ops_per_second",
+        "Operations per second during burst periods only (bursty mode).",
+        status.burst_ops_per_second as f64,
+        "",
+    );
+    gauge(
+        "cpu_stress_utilization_percent",
+        "Configured target utilization for bursty and throttled modes.",
+        status.utilization as f64,
+        "",
+    );
+    gauge(
+        "cpu_stress_system_cpu_utilization_percent",
+        "Measured system-wide CPU utilization over the last sampling interval.",
+        status.cpu_utilization_percent as f64,
+        "",
+    );
+    gauge(
+        "cpu_stress_memory_bytes_per_second",
+        "Bytes read and written per second by the stream workload over the last sampling interval.",
+        status.memory_gb_per_second * 1e9,
+        "",
+    );
+    gauge(
+        "cpu_stress_workers",
+        "Worker threads/processes per run.",
+        status.workers as f64,
+        "",
+    );
+    if let Some(nice) = status.nice {
+        gauge(
+            "cpu_stress_worker_nice",
+            "Nice level of the workers of the current (or most recent) run.",
+            nice as f64,
+            "",
+        );
+    }
+    if let Some(quota) = status.cpu_allocation.and_then(|allocation| allocation.quota_cpus) {
+        gauge(
+            "cpu_stress_cpu_quota",
+            "CPU quota of the agent's cgroup in CPUs (cpu.max).",
+            quota,
+            "",
+        );
+    }
+
+    let name = "cpu_stress_thread_restarts_total";
+    out.push_str(&format!("# HELP {} Panicked threads restarted since the agent started.\n", name));
+    out.push_str(&format!("# TYPE {} counter\n", name));
+    out.push_str(&format!("{} {}\n", name, status.thread_restarts));
+
+    let percentiles = [
+        ("cpu_stress_ops_per_second_percentile", "per-second ops", &status.percentiles.ops_per_second),
+        ("cpu_stress_burst_ops_per_second_percentile", "per-burst ops/sec", &status.percentiles.burst_ops_per_second),
+    ];
+    for (name, what, percentiles) in percentiles {
+        if percentiles.samples == 0 {
+            continue;
+        }
+        let values = [("50", percentiles.p50), ("90", percentiles.p90), ("99", percentiles.p99)];
+        out.push_str(&format!("# HELP {} Percentiles of the {} of the current (or most recent) run.\n", name, what));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for (percentile, value) in values {
+            out.push_str(&format!("{}{{percentile=\"{}\"}} {}\n", name, percentile, value.unwrap_or(0.0)));
+        }
+    }
+
+    let name = "cpu_stress_core_cpu_utilization_percent";
+    out.push_str(&format!(
+        "# HELP {} Measured per-core CPU utilization over the last sampling interval.\n",
+        name
+    ));
+    out.push_str(&format!("# TYPE {} gauge\n", name));
+    for (core, percent) in status.per_core_utilization_percent.iter().enumerate() {
+        out.push_str(&format!("{}{{core=\"{}\"}} {}\n", name, core, percent));
+    }
+
+    if let Some(quality) = status.measurement_quality {
+        let name = "cpu_stress_measurement_noise_percent";
+        out.push_str(&format!(
+            "# HELP {} Noise floor measured before the run (coefficient of variation of calibration slices).\n",
+            name
+        ));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        out.push_str(&format!("{}{{grade=\"{:?}\"}} {}\n", name, quality.grade, quality.noise_percent));
+    }
+
+    if let (Some(baseline), Some(score)) = (status.baseline, status.score) {
+        let scores = [
+            (
+                "cpu_stress_baseline_ops_per_second",
+                "Single-core ops/sec of the baseline workload (POST /calibrate).",
+                baseline.ops_per_second as f64,
+            ),
+            ("cpu_stress_score", "Ops/sec relative to the single-core baseline.", score.score),
+            (
+                "cpu_stress_score_per_core",
+                "Ops/sec relative to the baseline, per worker core (1 = as fast as at calibration).",
+                score.score_per_core,
+            ),
+        ];
+        for (name, help, value) in scores {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+            out.push_str(&format!("# TYPE {} gauge\n", name));
+            out.push_str(&format!("{} {}\n", name, value));
+        }
+    }
+
+    if let Some(power) = &status.power {
+        let name = "cpu_stress_package_watts";
+        out.push_str(&format!("# HELP {} CPU package power over the last sampling interval (RAPL).\n", name));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for package in &power.packages {
+            out.push_str(&format!("{}{{domain=\"{}\"}} {}\n", name, package.domain, package.watts));
+        }
+    }
+    if let Some(energy) = status.energy {
+        let energies = [
+            ("cpu_stress_run_energy_joules", "Package energy used during the current run.", energy.joules),
+            (
+                "cpu_stress_run_ops_per_joule",
+                "Operations per joule of package energy during the current run.",
+                energy.ops_per_joule,
+            ),
+        ];
+        for (name, help, value) in energies {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+            out.push_str(&format!("# TYPE {} gauge\n", name));
+            out.push_str(&format!("{} {}\n", name, value));
+        }
+    }
+
+    if !status.temperatures.is_empty() {
+        let name = "cpu_stress_cpu_temperature_celsius";
+        out.push_str(&format!("# HELP {} CPU package/core temperature from hwmon.\n", name));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for reading in &status.temperatures {
+            out.push_str(&format!(
+                "{}{{sensor=\"{}\",label=\"{}\"}} {}\n",
+                name, reading.sensor, reading.label, reading.celsius
+            ));
+        }
+    }
+
+    out
+}
+
+// node_exporter textfile collector sink: atomically rewrite a .prom file every sampling interval
+pub(crate) fn textfile_writer(state: Arc<StressEngine>, heartbeat: Arc<Heartbeat>, path: std::path::PathBuf) {
+    // Write to a temp file in the same directory, then rename, so the collector never sees a partial file
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    loop {
+        heartbeat.beat();
+        let contents = render_prometheus(&state.snapshot());
+        let result = std::fs::write(&tmp_path, contents).and_then(|_| std::fs::rename(&tmp_path, &path));
+        if let Err(e) = result {
+            tracing::warn!(path = %path.display(), "failed to write textfile metrics: {}", e);
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> MetricsSnapshot {
+        MetricsSnapshot {
+            running: true,
+            mode: ExecutionMode::Bursty,
+            utilization: 60,
+            ops_per_second: 1234,
+            burst_ops_per_second: 2000,
+            cpu_utilization_percent: 55.5,
+            per_core_utilization_percent: vec![50.0, 61.0],
+            temperatures: vec![TemperatureReading {
+                sensor: "coretemp".to_string(),
+                label: "Package id 0".to_string(),
+                celsius: 48.0,
+            }],
+            measurement_quality: Some(MeasurementQuality {
+                grade: QualityGrade::B,
+                noise_percent: 3.5,
+                calibration_ops_per_sec: 100_000,
+            }),
+            baseline: Some(Baseline {
+                ops_per_second: 617,
+                measured_ms: 1_760_000_000_000,
+                duration_ms: 2000,
+            }),
+            score: Some(NormalizedScore {
+                score: 2.0,
+                score_per_core: 1.0,
+                burst_score: None,
+            }),
+            workers: 2,
+            physical_cores_only: false,
+            smt_siblings: Vec::new(),
+            cpu_allocation: Some(CpuAllocation::new(4, Some(2.5))),
+            nice: Some(19),
+            workload: Workload::Primes,
+            memory_gb_per_second: 0.0,
+            percentiles: RunPercentiles {
+                ops_per_second: Percentiles {
+                    samples: 60,
+                    min: Some(900.0),
+                    p50: Some(1200.0),
+                    p90: Some(1250.0),
+                    p99: Some(1300.0),
+                    max: Some(1300.0),
+                },
+                burst_ops_per_second: Percentiles::default(),
+            },
+            guard_trip: None,
+            scheduled_start_ms: None,
+            power: None,
+            energy: None,
+            thread_restarts: 0,
+        }
+    }
+
+    #[test]
+    fn scores_are_relative_to_the_baseline() {
+        let status = snapshot();
+        let score = NormalizedScore::new(&status.baseline.unwrap(), &status, 2).unwrap();
+        assert_eq!(score.score, 2.0);
+        assert_eq!(score.score_per_core, 1.0);
+        assert_eq!(score.burst_score, Some(2000.0 / 617.0));
+
+        let threaded = MetricsSnapshot {
+            mode: ExecutionMode::Threaded,
+            ..snapshot()
+        };
+        assert_eq!(NormalizedScore::new(&status.baseline.unwrap(), &threaded, 4).unwrap().burst_score, None);
+    }
+
+    #[test]
+    fn ops_drops_are_reported_once_until_recovered() {
+        let mut detector = OpsDropDetector::default();
+        assert_eq!(detector.observe(0, 20.0), None);
+        assert_eq!(detector.observe(1000, 20.0), None);
+        assert_eq!(detector.observe(850, 20.0), None);
+        assert_eq!(detector.observe(700, 20.0), Some(Some(30.0)));
+        assert_eq!(detector.observe(500, 20.0), None);
+        assert_eq!(detector.observe(900, 20.0), Some(None));
+        assert_eq!(detector.peak(), 1000);
+    }
+
+    #[test]
+    fn guards_judge_full_windows_after_the_warmup() {
+        let floor = RunGuards {
+            ops_floor: Some(500),
+            window_secs: 3,
+            ..RunGuards::default()
+        };
+        let mut window = GuardWindow::new(Duration::from_millis(1500));
+        // Two warmup samples, then the window fills; one slow sample isn't enough
+        for ops in [0, 0, 1000, 100, 1000] {
+            assert_eq!(window.observe(ops, &floor), None);
+        }
+        assert_eq!(window.observe(300, &floor), Some((GuardRule::OpsFloor, 466, 700)));
+        assert!(window.is_tripped());
+        assert_eq!(window.observe(0, &floor), None);
+
+        let drop = RunGuards {
+            max_drop_percent: Some(25.0),
+            window_secs: 2,
+            ..RunGuards::default()
+        };
+        let mut window = GuardWindow::default();
+        for ops in [1000, 1000, 800, 700] {
+            assert_eq!(window.observe(ops, &drop), None);
+        }
+        assert_eq!(window.observe(600, &drop), Some((GuardRule::OpsDrop, 650, 1000)));
+    }
+
+    #[test]
+    fn quality_grade_thresholds() {
+        assert_eq!(QualityGrade::from_noise_percent(0.5), QualityGrade::A);
+        assert_eq!(QualityGrade::from_noise_percent(2.0), QualityGrade::B);
+        assert_eq!(QualityGrade::from_noise_percent(4.9), QualityGrade::B);
+        assert_eq!(QualityGrade::from_noise_percent(5.0), QualityGrade::C);
+    }
+
+    #[test]
+    fn prometheus_output_includes_all_gauges() {
+        let out = render_prometheus(&snapshot());
+        for line in [
+            "cpu_stress_running{mode=\"bursty\"} 1",
+            "cpu_stress_ops_per_second{mode=\"bursty\"} 1234",
+            "cpu_stress_burst_ops_per_second 2000",
+            "cpu_stress_utilization_percent 60",
+            "cpu_stress_core_cpu_utilization_percent{core=\"1\"} 61",
+            "cpu_stress_measurement_noise_percent{grade=\"B\"} 3.5",
+            "cpu_stress_baseline_ops_per_second 617",
+            "cpu_stress_score_per_core 1",
+            "cpu_stress_workers 2",
+            "cpu_stress_memory_bytes_per_second 0",
+            "cpu_stress_ops_per_second_percentile{percentile=\"90\"} 1250",
+            "cpu_stress_cpu_quota 2.5",
+            "cpu_stress_worker_nice 19",
+            "cpu_stress_cpu_temperature_celsius{sensor=\"coretemp\",label=\"Package id 0\"} 48",
+        ] {
+            assert!(out.lines().any(|l| l == line), "missing {:?} in:\n{}", line, out);
+        }
+    }
+
+    #[test]
+    fn prometheus_output_omits_missing_sensors() {
+        let status = MetricsSnapshot {
+            temperatures: Vec::new(),
+            measurement_quality: None,
+            baseline: None,
+            score: None,
+            cpu_allocation: None,
+            ..snapshot()
+        };
+        let out = render_prometheus(&status);
+        assert!(!out.contains("cpu_stress_cpu_quota"));
+        assert!(!out.contains("cpu_stress_cpu_temperature_celsius"));
+        assert!(!out.contains("cpu_stress_score"));
+        assert!(!out.contains("cpu_stress_measurement_noise_percent"));
+    }
+
+    #[test]
+    fn latency_probe_records_wakeups_per_core() {
+        let state = Arc::new(StressEngine::new(crate::engine::EngineOptions {
+            num_cores: 1,
+            simulate: true,
+            ..crate::engine::EngineOptions::default()
+        }));
+        let interval = Duration::from_micros(500);
+        state.latency.lock().unwrap().insert(3, CoreLatency::new(interval));
+        let signal = Arc::new(RunSignal::default());
+        let probe = {
+            let (state, signal) = (Arc::clone(&state), Arc::clone(&signal));
+            let heartbeat = state.register_heartbeat("latency-probe-3".to_string());
+            thread::spawn(move || latency_probe(state, signal, heartbeat, 3, interval))
+        };
+        thread::sleep(Duration::from_millis(50));
+        signal.stop();
+        probe.join().unwrap();
+
+        let report = state.latency();
+        assert_eq!(report.cores.len(), 1);
+        assert_eq!((report.cores[0].core_id, report.cores[0].interval_us), (3, 500));
+        assert!(report.all_cores.samples > 10, "{:?}", report);
+        assert_eq!(report.all_cores, report.cores[0].latency);
+        let p50 = report.all_cores.p50_us.unwrap();
+        assert!(p50 <= report.all_cores.p99_us.unwrap() && report.all_cores.p99_us <= report.all_cores.max_us);
+    }
+}
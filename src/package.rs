@@ -0,0 +1,183 @@
+// Deployment package (`package` subcommand): a tarball of this binary, the agent flags it was given as
+// a config file, and a systemd unit that runs them. It is generated by the binary itself, so the unit
+// always uses flags this version understands. Unpack it at / on the target host.
+use std::path::Path;
+
+pub const SERVICE_NAME: &str = "distributed-cpu-stress-reporter";
+const BINARY_PATH: &str = "usr/local/bin/distributed-cpu-stress-reporter";
+const CONFIG_PATH: &str = "etc/distributed-cpu-stress-reporter/agent.env";
+const UNIT_PATH: &str = "etc/systemd/system/distributed-cpu-stress-reporter.service";
+
+// Environment file read by the unit: the agent flags in AGENT_ARGS. systemd splits $AGENT_ARGS on
+// whitespace without shell quoting, so flags that need quoting are refused.
+pub fn agent_config(agent_args: &[String]) -> Result<String, String> {
+    let needs_quoting = |c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '$' | '%');
+    if let Some(arg) = agent_args.iter().find(|arg| arg.is_empty() || arg.contains(needs_quoting)) {
+        return Err(format!("{:?} can't be written to the systemd config (whitespace, quotes, $, or %)", arg));
+    }
+    Ok(format!(
+        "# Flags of the agent service, generated by distributed-cpu-stress-reporter {} (see --help).\n\
+         # Restart the service after editing: systemctl restart {}\n\
+         AGENT_ARGS=\"{}\"\n",
+        env!("CARGO_PKG_VERSION"),
+        SERVICE_NAME,
+        agent_args.join(" ")
+    ))
+}
+
+pub fn systemd_unit(port: u16) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Distributed CPU Stress Reporter agent (port {port})\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         EnvironmentFile=/{CONFIG_PATH}\n\
+         ExecStart=/{BINARY_PATH} $AGENT_ARGS\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n"
+    )
+}
+
+// Whether an ELF executable needs a dynamic loader (has a PT_INTERP program header); None if it
+// isn't a 64-bit little-endian ELF file
+pub fn is_dynamically_linked(elf: &[u8]) -> Option<bool> {
+    const PT_INTERP: u32 = 3;
+    if elf.len() < 64 || &elf[..4] != b"\x7fELF" || elf[4] != 2 || elf[5] != 1 {
+        return None;
+    }
+    let read_u16 = |offset: usize| u16::from_le_bytes([elf[offset], elf[offset + 1]]) as usize;
+    let phoff = u64::from_le_bytes(elf[0x20..0x28].try_into().unwrap()) as usize;
+    let (phentsize, phnum) = (read_u16(0x36), read_u16(0x38));
+    // Offsets past the end of the address space can't hold a header, so they don't count as PT_INTERP
+    let mut headers = (0..phnum).map(|i| i.checked_mul(phentsize).and_then(|offset| phoff.checked_add(offset)));
+    Some(headers.any(|header| {
+        header
+            .and_then(|header| elf.get(header..header.checked_add(4)?))
+            .is_some_and(|p_type| u32::from_le_bytes(p_type.try_into().unwrap()) == PT_INTERP)
+    }))
+}
+
+// The flags given on the command line before the subcommand, in their order, from clap's matches of the
+// top-level command (so a flag value that happens to be "package" is kept)
+pub fn agent_args(command: &clap::Command, matches: &clap::ArgMatches) -> Vec<String> {
+    let mut flags: Vec<(usize, Vec<String>)> = Vec::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) != Some(clap::parser::ValueSource::CommandLine) {
+            continue;
+        }
+        let flag = match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("--{}", long),
+            (None, Some(short)) => format!("-{}", short),
+            // Positional arguments belong to subcommands only
+            (None, None) => continue,
+        };
+        let index = matches.index_of(id).unwrap_or(0);
+        let mut words = Vec::new();
+        if !arg.get_action().takes_values() {
+            let count = match arg.get_action() {
+                clap::ArgAction::Count => matches.get_count(id) as usize,
+                _ => 1,
+            };
+            words.extend(std::iter::repeat_n(flag, count));
+        } else {
+            for occurrence in matches.get_raw_occurrences(id).into_iter().flatten() {
+                let values: Vec<String> = occurrence.map(|value| value.to_string_lossy().into_owned()).collect();
+                words.push(flag.clone());
+                match arg.get_value_delimiter() {
+                    Some(delimiter) => words.push(values.join(&delimiter.to_string())),
+                    None => words.extend(values),
+                }
+            }
+        }
+        flags.push((index, words));
+    }
+    flags.sort_by_key(|(index, _)| *index);
+    flags.into_iter().flat_map(|(_, words)| words).collect()
+}
+
+// Write the package to `output`: the binary at `binary`, the config, and the unit
+pub fn write_package(output: &Path, binary: &Path, agent_args: &[String], port: u16) -> Result<(), String> {
+    let config = agent_config(agent_args)?;
+    let executable = std::fs::read(binary).map_err(|e| format!("failed to read {}: {}", binary.display(), e))?;
+    let file = std::fs::File::create(output).map_err(|e| format!("failed to create {}: {}", output.display(), e))?;
+
+    let mut tarball = tar::Builder::new(file);
+    let mut append = |path: &str, mode: u32, contents: &[u8]| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(mode);
+        header.set_mtime(crate::engine::unix_time_ms() / 1000);
+        header.set_cksum();
+        tarball
+            .append_data(&mut header, path, contents)
+            .map_err(|e| format!("failed to write {}: {}", output.display(), e))
+    };
+    append(BINARY_PATH, 0o755, &executable)?;
+    append(CONFIG_PATH, 0o644, config.as_bytes())?;
+    append(UNIT_PATH, 0o644, systemd_unit(port).as_bytes())?;
+    tarball
+        .into_inner()
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("failed to write {}: {}", output.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_holds_binary_config_and_unit() {
+        let dir = std::env::temp_dir().join(format!("cpu-stress-package-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("agent");
+        std::fs::write(&binary, b"\x7fELF binary").unwrap();
+        let output = dir.join("agent.tar");
+        let args = ["--port", "9000", "--cores", "0-3"].map(String::from);
+        write_package(&output, &binary, &args, 9000).unwrap();
+
+        let mut archive = tar::Archive::new(std::fs::File::open(&output).unwrap());
+        let mut entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut contents).unwrap();
+            entries.push((entry.path().unwrap().display().to_string(), entry.header().mode().unwrap(), contents));
+        }
+        assert_eq!(entries[0], (BINARY_PATH.to_string(), 0o755, "\x7fELF binary".to_string()));
+        assert!(entries[1].2.contains("AGENT_ARGS=\"--port 9000 --cores 0-3\"\n"));
+        assert!(entries[2].2.contains("ExecStart=/usr/local/bin/distributed-cpu-stress-reporter $AGENT_ARGS\n"));
+        assert!(entries[2].2.contains("(port 9000)"));
+
+        assert!(write_package(&output, &binary, &["--record".to_string(), "my file".to_string()], 9000).is_err());
+        assert_eq!(is_dynamically_linked(b"\x7fELF binary"), None);
+        #[cfg(target_os = "linux")]
+        assert!(is_dynamically_linked(&std::fs::read(std::env::current_exe().unwrap()).unwrap()).is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // A program header table at the very end of the address space
+        let mut elf = b"\x7fELF\x02\x01".to_vec();
+        elf.resize(64, 0);
+        elf[0x20..0x28].copy_from_slice(&u64::MAX.to_le_bytes());
+        elf[0x36..0x3a].copy_from_slice(&[56, 0, 2, 0]);
+        assert_eq!(is_dynamically_linked(&elf), Some(false));
+    }
+
+    #[test]
+    fn agent_args_come_from_the_parsed_flags() {
+        let command = clap::Command::new("agent")
+            .arg(clap::Arg::new("record").long("record"))
+            .arg(clap::Arg::new("cores").long("cores"))
+            .arg(clap::Arg::new("port").long("port").default_value("8080"))
+            .arg(clap::Arg::new("verbose").long("verbose").action(clap::ArgAction::SetTrue))
+            .subcommand(clap::Command::new("package").arg(clap::Arg::new("output").long("output")));
+        let argv = ["agent", "--record", "package", "--verbose", "--cores=0-3", "package", "--output", "a.tar"];
+        let matches = command.clone().get_matches_from(argv);
+        assert_eq!(agent_args(&command, &matches), ["--record", "package", "--verbose", "--cores", "0-3"]);
+    }
+}
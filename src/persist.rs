@@ -0,0 +1,116 @@
+// Run state file (--state-file): what the agent is running, rewritten on every change, so an agent that
+// restarts after a crash, reboot, or upgrade can pick its soak test back up (--resume)
+use crate::engine::{JobRequest, StartCpuRequest};
+use crate::schedule::ScheduleRequest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Contents of the state file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedState {
+    // When it was written (Unix ms)
+    pub saved_ms: u64,
+    // Worker count of the agent that wrote it (a resumed run uses the new agent's)
+    pub workers: usize,
+    // The agent's worker_ops, as changed by PATCH /config
+    pub worker_ops: u64,
+    // Start request of the running stress test; armed starts aren't saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<StartCpuRequest>,
+    #[serde(default)]
+    pub jobs: Vec<SavedJob>,
+    #[serde(default)]
+    pub schedules: Vec<SavedSchedule>,
+}
+
+// A running job: its request, and when it started so a resumed job only runs for what is left of its
+// duration_secs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedJob {
+    // Id in the agent that wrote it; a resumed job gets a new one
+    pub id: u64,
+    pub started_ms: u64,
+    #[serde(flatten)]
+    pub request: JobRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSchedule {
+    // Id in the agent that wrote it; a resumed schedule gets a new one
+    pub id: u64,
+    #[serde(flatten)]
+    pub request: ScheduleRequest,
+}
+
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: &Path) -> Self {
+        StateFile {
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The saved state; None if nothing was saved yet
+    pub fn load(&self) -> Result<Option<SavedState>, String> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {}", self.path.display(), e)),
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+
+    // Write to a temp file in the same directory, then rename, so a crash mid-write leaves the previous state
+    pub(crate) fn save(&self, state: &SavedState) -> std::io::Result<()> {
+        let mut tmp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(format!(".{}.tmp", std::process::id()));
+        let tmp_path = self.path.with_file_name(tmp_name);
+        let contents = serde_json::to_string_pretty(state).expect("Failed to serialize run state");
+        std::fs::write(&tmp_path, contents)?;
+        std::fs::rename(&tmp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ExecutionMode;
+
+    #[test]
+    fn state_files_round_trip() {
+        let path = std::env::temp_dir().join(format!("run-state-test-{}.json", std::process::id()));
+        let file = StateFile::new(&path);
+        assert!(file.load().unwrap().is_none());
+
+        let job: JobRequest =
+            serde_json::from_str(r#"{"name":"steady","cores":"0-1","mode":"bursty","utilization":40}"#).unwrap();
+        let state = SavedState {
+            saved_ms: 1_760_000_000_000,
+            workers: 4,
+            worker_ops: 20_000,
+            run: None,
+            jobs: vec![SavedJob {
+                id: 1,
+                started_ms: 1_759_999_000_000,
+                request: job,
+            }],
+            schedules: Vec::new(),
+        };
+        file.save(&state).unwrap();
+        let loaded = file.load().unwrap().unwrap();
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&state).unwrap());
+        assert_eq!(loaded.jobs[0].request.start.mode, ExecutionMode::Bursty);
+
+        std::fs::write(&path, "{").unwrap();
+        assert!(file.load().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+// Power and energy from the RAPL energy counters (Linux powercap; Intel, and AMD since Zen): the sampler
+// turns each interval's energy into package watts, and runs total their joules for ops per joule
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const POWERCAP_DIR: &str = "/sys/class/powercap";
+
+// Package power over the last sampling interval; `power` in /status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PowerReading {
+    // All packages together
+    pub watts: f64,
+    // Energy used over the interval
+    pub joules: f64,
+    pub packages: Vec<PackagePower>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PackagePower {
+    // RAPL domain name, e.g. "package-0"
+    pub domain: String,
+    pub watts: f64,
+}
+
+// Energy of the current (or most recent) run; `energy` in /status
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RunEnergy {
+    pub joules: f64,
+    // Operations completed while the energy was measured
+    pub ops: u64,
+    pub average_watts: f64,
+    pub ops_per_joule: f64,
+}
+
+// Running totals behind RunEnergy
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct EnergyTotals {
+    joules: f64,
+    ops: u64,
+    secs: f64,
+}
+
+impl EnergyTotals {
+    pub(crate) fn add(&mut self, power: &PowerReading, ops: u64) {
+        if power.watts > 0.0 {
+            self.secs += power.joules / power.watts;
+        }
+        self.joules += power.joules;
+        self.ops += ops;
+    }
+
+    // None until a sampling interval of the run has been measured
+    pub(crate) fn report(&self) -> Option<RunEnergy> {
+        (self.joules > 0.0).then(|| RunEnergy {
+            joules: self.joules,
+            ops: self.ops,
+            average_watts: if self.secs > 0.0 { self.joules / self.secs } else { 0.0 },
+            ops_per_joule: self.ops as f64 / self.joules,
+        })
+    }
+}
+
+// One package's counter: energy_uj counts microjoules and wraps around at max_energy_range_uj
+#[derive(Debug)]
+struct RaplDomain {
+    name: String,
+    energy_path: PathBuf,
+    max_range_uj: u64,
+}
+
+// Reads the package domains' counters once per sampling interval
+#[derive(Debug, Default)]
+pub(crate) struct PowerMeter {
+    domains: Vec<RaplDomain>,
+    previous: Option<(Vec<u64>, Instant)>,
+}
+
+impl PowerMeter {
+    // Package domains of this host. The counters are readable by root only on most kernels (since 5.10), so
+    // a meter that can't read them measures nothing.
+    pub(crate) fn detect() -> Self {
+        let meter = PowerMeter::detect_in(Path::new(POWERCAP_DIR));
+        match meter.read() {
+            Some(_) => {
+                let domains: Vec<&str> = meter.domains.iter().map(|domain| domain.name.as_str()).collect();
+                tracing::info!(domains = %domains.join(","), "measuring package power with RAPL");
+                meter
+            }
+            None if meter.domains.is_empty() => PowerMeter::default(),
+            None => {
+                tracing::warn!("RAPL energy counters are not readable (root only on most kernels); no power readings");
+                PowerMeter::default()
+            }
+        }
+    }
+
+    // Top-level RAPL domains (intel-rapl:N, not their core/uncore/dram subdomains intel-rapl:N:M) of a
+    // powercap directory
+    fn detect_in(dir: &Path) -> Self {
+        let mut domains: Vec<RaplDomain> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name().into_string().ok()?;
+                let index = file_name.strip_prefix("intel-rapl:")?;
+                if index.contains(':') {
+                    return None;
+                }
+                let path = entry.path();
+                let name = std::fs::read_to_string(path.join("name")).ok()?.trim().to_string();
+                if !name.starts_with("package") {
+                    return None;
+                }
+                let max_range_uj = std::fs::read_to_string(path.join("max_energy_range_uj")).ok()?;
+                let max_range_uj = max_range_uj.trim().parse().ok()?;
+                Some(RaplDomain {
+                    name,
+                    energy_path: path.join("energy_uj"),
+                    max_range_uj,
+                })
+            })
+            .collect();
+        domains.sort_by(|a, b| a.name.cmp(&b.name));
+        PowerMeter {
+            domains,
+            previous: None,
+        }
+    }
+
+    fn read(&self) -> Option<Vec<u64>> {
+        self.domains
+            .iter()
+            .map(|domain| std::fs::read_to_string(&domain.energy_path).ok()?.trim().parse().ok())
+            .collect()
+    }
+
+    // Power since the previous call; None on the first call and without readable counters
+    pub(crate) fn sample(&mut self) -> Option<PowerReading> {
+        self.sample_at(Instant::now())
+    }
+
+    fn sample_at(&mut self, now: Instant) -> Option<PowerReading> {
+        if self.domains.is_empty() {
+            return None;
+        }
+        let energies = self.read()?;
+        let (previous, previous_at) = self.previous.replace((energies.clone(), now))?;
+        let secs = now.duration_since(previous_at).as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+
+        let packages: Vec<PackagePower> = self
+            .domains
+            .iter()
+            .zip(energies.iter().zip(previous))
+            .map(|(domain, (&energy, previous))| {
+                let used_uj = if energy >= previous {
+                    energy - previous
+                } else {
+                    energy + domain.max_range_uj - previous
+                };
+                PackagePower {
+                    domain: domain.name.clone(),
+                    watts: used_uj as f64 / 1e6 / secs,
+                }
+            })
+            .collect();
+        let watts = packages.iter().map(|package| package.watts).sum::<f64>();
+        Some(PowerReading {
+            watts,
+            joules: watts * secs,
+            packages,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn write_domain(dir: &Path, entry: &str, name: &str, energy_uj: u64) {
+        let path = dir.join(entry);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("name"), format!("{}\n", name)).unwrap();
+        std::fs::write(path.join("energy_uj"), format!("{}\n", energy_uj)).unwrap();
+        std::fs::write(path.join("max_energy_range_uj"), "1000000000\n").unwrap();
+    }
+
+    #[test]
+    fn package_power_from_energy_counters() {
+        let dir = std::env::temp_dir().join(format!("rapl-test-{}", std::process::id()));
+        write_domain(&dir, "intel-rapl:0", "package-0", 5_000_000);
+        write_domain(&dir, "intel-rapl:1", "package-1", 999_000_000);
+        write_domain(&dir, "intel-rapl:0:0", "core", 0);
+        write_domain(&dir, "intel-rapl:2", "psys", 0);
+
+        let mut meter = PowerMeter::detect_in(&dir);
+        let start = Instant::now();
+        assert_eq!(meter.sample_at(start), None);
+        // 40 J on package 0, and package 1's counter wraps around after 1000 J
+        write_domain(&dir, "intel-rapl:0", "package-0", 45_000_000);
+        write_domain(&dir, "intel-rapl:1", "package-1", 19_000_000);
+        let reading = meter.sample_at(start + Duration::from_secs(2)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let packages: Vec<(&str, f64)> = reading.packages.iter().map(|p| (p.domain.as_str(), p.watts)).collect();
+        assert_eq!(packages, [("package-0", 20.0), ("package-1", 10.0)]);
+        assert_eq!((reading.watts, reading.joules), (30.0, 60.0));
+
+        let mut totals = EnergyTotals::default();
+        assert_eq!(totals.report(), None);
+        totals.add(&reading, 6_000_000);
+        let energy = totals.report().unwrap();
+        assert_eq!((energy.joules, energy.average_watts, energy.ops_per_joule), (60.0, 30.0, 100_000.0));
+    }
+}
@@ -0,0 +1,217 @@
+// Resource accounting of worker processes (fresh-process workers and process-backend burst batches): what
+// each one cost to create and run, so process-creation overhead can be measured instead of discarded
+use crate::metrics::Percentiles;
+use crate::sketch::Sketch;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::process::{Child, ExitStatus, Output};
+use std::time::Duration;
+
+// Worker processes kept for GET /process-stats; older ones only count toward the aggregates
+pub const MAX_PROCESS_RECORDS: usize = 1000;
+// Records returned by GET /process-stats without ?last
+pub const DEFAULT_PROCESS_RECORDS: usize = 100;
+
+// CPU time and peak memory of a finished child, from wait4
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ChildUsage {
+    pub(crate) user: Duration,
+    pub(crate) sys: Duration,
+    pub(crate) max_rss_kb: u64,
+}
+
+// One worker process; entry of GET /process-stats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ProcessRecord {
+    pub core_id: usize,
+    // When the process was reaped
+    pub timestamp_ms: u64,
+    // Time for spawn() to return (fork and exec), then from there until the process was reaped
+    pub spawn_us: u64,
+    pub wall_ms: f64,
+    // CPU time and peak resident set size (None on platforms without wait4 accounting)
+    pub user_ms: Option<f64>,
+    pub sys_ms: Option<f64>,
+    pub max_rss_kb: Option<u64>,
+    // Exit code, or the signal that killed the process
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl ProcessRecord {
+    pub(crate) fn new(
+        core_id: usize,
+        timestamp_ms: u64,
+        spawn: Duration,
+        wall: Duration,
+        status: ExitStatus,
+        usage: Option<ChildUsage>,
+    ) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+        ProcessRecord {
+            core_id,
+            timestamp_ms,
+            spawn_us: spawn.as_micros() as u64,
+            wall_ms: wall.as_secs_f64() * 1000.0,
+            user_ms: usage.map(|usage| usage.user.as_secs_f64() * 1000.0),
+            sys_ms: usage.map(|usage| usage.sys.as_secs_f64() * 1000.0),
+            max_rss_kb: usage.map(|usage| usage.max_rss_kb),
+            exit_code: status.code(),
+            signal,
+        }
+    }
+
+    fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+// Worker processes of the current (or most recent) run and of the jobs
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProcessStats {
+    processes: u64,
+    failed: u64,
+    spawn_errors: u64,
+    spawn_us: Sketch,
+    wall_ms: Sketch,
+    user_ms: Sketch,
+    sys_ms: Sketch,
+    max_rss_kb: Sketch,
+    recent: VecDeque<ProcessRecord>,
+}
+
+impl ProcessStats {
+    pub(crate) fn record(&mut self, record: ProcessRecord) {
+        self.processes += 1;
+        if !record.succeeded() {
+            self.failed += 1;
+        }
+        self.spawn_us.add(record.spawn_us as f64);
+        self.wall_ms.add(record.wall_ms);
+        if let (Some(user_ms), Some(sys_ms), Some(max_rss_kb)) = (record.user_ms, record.sys_ms, record.max_rss_kb) {
+            self.user_ms.add(user_ms);
+            self.sys_ms.add(sys_ms);
+            self.max_rss_kb.add(max_rss_kb as f64);
+        }
+        if self.recent.len() == MAX_PROCESS_RECORDS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(record);
+    }
+
+    // A worker process that could not be started at all
+    pub(crate) fn spawn_error(&mut self) {
+        self.spawn_errors += 1;
+    }
+
+    pub(crate) fn report(&self, last: usize) -> ProcessStatsReport {
+        let skip = self.recent.len().saturating_sub(last);
+        ProcessStatsReport {
+            processes: self.processes,
+            failed: self.failed,
+            spawn_errors: self.spawn_errors,
+            spawn_us: Percentiles::new(&self.spawn_us),
+            wall_ms: Percentiles::new(&self.wall_ms),
+            user_ms: Percentiles::new(&self.user_ms),
+            sys_ms: Percentiles::new(&self.sys_ms),
+            max_rss_kb: Percentiles::new(&self.max_rss_kb),
+            recent: self.recent.iter().skip(skip).cloned().collect(),
+        }
+    }
+}
+
+// Body of GET /process-stats: aggregates over every worker process of the current (or most recent) run
+// and of the jobs, and the most recent processes, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ProcessStatsReport {
+    pub processes: u64,
+    // Processes that exited with a non-zero code or were killed by a signal
+    pub failed: u64,
+    // Processes that could not be spawned (not included in `processes`)
+    pub spawn_errors: u64,
+    pub spawn_us: Percentiles,
+    pub wall_ms: Percentiles,
+    pub user_ms: Percentiles,
+    pub sys_ms: Percentiles,
+    pub max_rss_kb: Percentiles,
+    pub recent: Vec<ProcessRecord>,
+}
+
+// Wait for a child, collecting its stdout, exit status, and resource usage. wait4 reports the usage of
+// exactly this child, unlike getrusage(RUSAGE_CHILDREN), which sums every child of the agent.
+#[cfg(target_os = "linux")]
+pub(crate) fn wait_with_usage(mut child: Child) -> std::io::Result<(Output, Option<ChildUsage>)> {
+    use std::io::Read;
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut stdout = Vec::new();
+    // A read error is returned once the child is reaped, so it isn't left behind as a zombie; dropping the
+    // pipe first keeps the child from blocking on a full one
+    let read = child.stdout.take().map_or(Ok(0), |mut pipe| pipe.read_to_end(&mut stdout));
+    let mut status = 0;
+    // SAFETY: rusage is plain old data, so all zeroes is a valid value
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: the pid is our own unreaped child, and both pointers are valid for the call
+        if unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut usage) } >= 0 {
+            break;
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+    read?;
+    let timeval = |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+    let output = Output {
+        status: ExitStatus::from_raw(status),
+        stdout,
+        stderr: Vec::new(),
+    };
+    let usage = ChildUsage {
+        user: timeval(usage.ru_utime),
+        sys: timeval(usage.ru_stime),
+        // Kilobytes on Linux
+        max_rss_kb: usage.ru_maxrss as u64,
+    };
+    Ok((output, Some(usage)))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn wait_with_usage(child: Child) -> std::io::Result<(Output, Option<ChildUsage>)> {
+    child.wait_with_output().map(|output| (output, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn records_children_and_keeps_the_most_recent() {
+        let child = Command::new("sh").args(["-c", "echo 42; exit 3"]).stdout(Stdio::piped()).spawn().unwrap();
+        let (output, usage) = wait_with_usage(child).unwrap();
+        assert_eq!((output.stdout.as_slice(), output.status.code()), (b"42\n".as_slice(), Some(3)));
+        assert_eq!(usage.is_some(), cfg!(target_os = "linux"));
+
+        let mut stats = ProcessStats::default();
+        let spawn = Duration::from_micros(800);
+        stats.record(ProcessRecord::new(0, 1000, spawn, Duration::from_millis(20), output.status, usage));
+        let status = Command::new("true").status().unwrap();
+        for timestamp_ms in 1001..=1000 + MAX_PROCESS_RECORDS as u64 {
+            stats.record(ProcessRecord::new(1, timestamp_ms, spawn, Duration::from_millis(10), status, None));
+        }
+        stats.spawn_error();
+
+        let report = stats.report(2);
+        assert_eq!((report.processes, report.failed, report.spawn_errors), (1001, 1, 1));
+        assert_eq!(report.wall_ms.max, Some(20.0));
+        assert_eq!(report.spawn_us.p50, Some(800.0));
+        let timestamps: Vec<u64> = report.recent.iter().map(|record| record.timestamp_ms).collect();
+        assert_eq!(timestamps, [1999, 2000]);
+        assert_eq!(stats.report(usize::MAX).recent.len(), MAX_PROCESS_RECORDS);
+    }
+}
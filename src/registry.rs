@@ -0,0 +1,157 @@
+// Local registry of agent instances on one host, so instances can't accidentally share cores.
+// Each live instance holds an exclusive lock on its own entry file; an entry whose lock can be
+// taken belongs to an instance that has exited and is cleaned up.
+use crate::cores::format_core_list;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+// One registered agent instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceEntry {
+    pub pid: u32,
+    pub port: u16,
+    pub cores: Vec<usize>,
+}
+
+// Directory of instance entries, shared by all agents on the host
+#[derive(Debug)]
+pub struct InstanceRegistry {
+    dir: PathBuf,
+}
+
+// Registration of this instance; the entry is released when dropped (or when the process exits)
+#[derive(Debug)]
+pub struct InstanceLease {
+    path: PathBuf,
+    _file: File,
+}
+
+impl Drop for InstanceLease {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl InstanceRegistry {
+    pub fn new(dir: &Path) -> Self {
+        InstanceRegistry { dir: dir.to_path_buf() }
+    }
+
+    pub fn default_dir() -> PathBuf {
+        std::env::temp_dir().join("distributed-cpu-stress-reporter")
+    }
+
+    // Live instances, by port
+    pub fn instances(&self) -> Result<Vec<InstanceEntry>, String> {
+        let _guard = self.lock()?;
+        self.live_entries()
+    }
+
+    // Register an instance, failing if any of its cores is already used by a live instance
+    pub fn register(&self, entry: &InstanceEntry) -> Result<InstanceLease, String> {
+        let _guard = self.lock()?;
+
+        for other in self.live_entries()? {
+            let shared: Vec<usize> = entry.cores.iter().copied().filter(|core| other.cores.contains(core)).collect();
+            if !shared.is_empty() {
+                return Err(format!(
+                    "cores {} are already used by the instance on port {} (pid {}); choose a disjoint --cores set",
+                    format_core_list(&shared),
+                    other.port,
+                    other.pid
+                ));
+            }
+        }
+
+        let path = self.dir.join(format!("instance-{}.json", entry.pid));
+        let mut file = File::create(&path).map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+        file.lock().map_err(|e| format!("failed to lock {}: {}", path.display(), e))?;
+        let json = serde_json::to_string(entry).expect("Failed to serialize instance entry");
+        file.write_all(json.as_bytes())
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+
+        Ok(InstanceLease { path, _file: file })
+    }
+
+    // Serializes registrations so two instances starting at once can't both claim a core
+    fn lock(&self) -> Result<File, String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("failed to create {}: {}", self.dir.display(), e))?;
+        let path = self.dir.join("registry.lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+        file.lock().map_err(|e| format!("failed to lock {}: {}", path.display(), e))?;
+        Ok(file)
+    }
+
+    fn live_entries(&self) -> Result<Vec<InstanceEntry>, String> {
+        let dir = std::fs::read_dir(&self.dir).map_err(|e| format!("failed to read {}: {}", self.dir.display(), e))?;
+        let mut entries = Vec::new();
+        for path in dir.flatten().map(|dir_entry| dir_entry.path()) {
+            let is_entry = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("instance-") && name.ends_with(".json"));
+            if !is_entry {
+                continue;
+            }
+            let Ok(mut file) = File::open(&path) else {
+                continue;
+            };
+            match file.try_lock() {
+                // Nobody holds it: the instance is gone
+                Ok(()) => {
+                    let _ = std::fs::remove_file(&path);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    let mut json = String::new();
+                    if file.read_to_string(&mut json).is_ok()
+                        && let Ok(entry) = serde_json::from_str::<InstanceEntry>(&json)
+                    {
+                        entries.push(entry);
+                    }
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(format!("failed to check {}: {}", path.display(), e));
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.port);
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_core_sets_are_rejected_until_released() {
+        let dir = std::env::temp_dir().join(format!("cpu-stress-registry-test-{}", std::process::id()));
+        let registry = InstanceRegistry::new(&dir);
+
+        let first = registry
+            .register(&InstanceEntry { pid: 1, port: 8081, cores: vec![0, 1, 2, 3] })
+            .unwrap();
+        let error = registry
+            .register(&InstanceEntry { pid: 2, port: 8082, cores: vec![3, 4] })
+            .unwrap_err();
+        assert!(error.contains("cores 3 are already used by the instance on port 8081"), "{}", error);
+
+        let second = registry
+            .register(&InstanceEntry { pid: 2, port: 8082, cores: vec![4, 5] })
+            .unwrap();
+        let ports: Vec<u16> = registry.instances().unwrap().iter().map(|entry| entry.port).collect();
+        assert_eq!(ports, vec![8081, 8082]);
+
+        drop(first);
+        drop(second);
+        assert!(registry.instances().unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
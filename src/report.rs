@@ -0,0 +1,223 @@
+// Push mode (--report-to): the agent POSTs a metrics report to a collector URL every interval, for
+// networks where the collector can't reach the agents to pull /cpu-perf (NAT, firewalls). Reports that
+// can't be delivered are kept (up to MAX_BACKLOG) and resent oldest first, backing off between attempts.
+use crate::engine::{StressEngine, unix_time_ms};
+use crate::metrics::{BurstEvent, MetricsSnapshot};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Instant, MissedTickBehavior};
+
+pub const DEFAULT_REPORT_INTERVAL_SECS: u64 = 10;
+// Undelivered reports kept while the collector is unreachable; the oldest are dropped beyond this
+const MAX_BACKLOG: usize = 360;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+// Bursts that ended during one report interval, summed over all cores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BurstTotals {
+    pub count: u64,
+    pub total_ms: u64,
+    pub ops: u64,
+}
+
+impl BurstTotals {
+    fn add(&mut self, event: &BurstEvent) {
+        if let BurstEvent::BurstEnd { actual_ms, ops, .. } = event {
+            self.count += 1;
+            self.total_ms += actual_ms;
+            self.ops += ops;
+        }
+    }
+}
+
+// Body of each POST to --report-to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsReport {
+    // "hostname:port", as in webhook events and on the sync collector
+    pub agent: String,
+    pub hostname: String,
+    // Counts up from 1 with every report since the agent started; a gap means reports were dropped
+    pub sequence: u64,
+    // The interval this report covers
+    pub start_ms: u64,
+    pub timestamp_ms: u64,
+    // Mean of the per-second ops/sec samples in the interval (0 if there were none)
+    pub mean_ops_per_second: f64,
+    pub bursts: BurstTotals,
+    // Status as of timestamp_ms
+    #[serde(flatten)]
+    pub status: MetricsSnapshot,
+}
+
+// Builds consecutive reports of one agent
+struct Reporter {
+    state: Arc<StressEngine>,
+    agent: String,
+    sequence: u64,
+    start_ms: u64,
+    bursts: BurstTotals,
+}
+
+impl Reporter {
+    fn new(state: Arc<StressEngine>, agent: &str) -> Self {
+        let start_ms = state.clock_ms().unwrap_or_else(unix_time_ms);
+        Reporter {
+            state,
+            agent: agent.to_string(),
+            sequence: 0,
+            start_ms,
+            bursts: BurstTotals::default(),
+        }
+    }
+
+    // Report on the interval since the previous report, and start the next one
+    fn report(&mut self) -> MetricsReport {
+        let timestamp_ms = self.state.clock_ms().unwrap_or_else(unix_time_ms);
+        let ops: Vec<u64> = self
+            .state
+            .history(None)
+            .iter()
+            .filter(|sample| sample.timestamp_ms > self.start_ms && sample.timestamp_ms <= timestamp_ms)
+            .map(|sample| sample.status.ops_per_second)
+            .collect();
+        let mean_ops_per_second = match ops.len() {
+            0 => 0.0,
+            samples => ops.iter().sum::<u64>() as f64 / samples as f64,
+        };
+        let hostname = self.agent.rsplit_once(':').map_or(&*self.agent, |(host, _)| host);
+        self.sequence += 1;
+        let report = MetricsReport {
+            agent: self.agent.clone(),
+            hostname: hostname.to_string(),
+            sequence: self.sequence,
+            start_ms: self.start_ms,
+            timestamp_ms,
+            mean_ops_per_second,
+            bursts: std::mem::take(&mut self.bursts),
+            status: self.state.snapshot(),
+        };
+        self.start_ms = timestamp_ms;
+        report
+    }
+}
+
+// Report to `url` every `interval` until the process exits. `agent` identifies this agent in the
+// reports (e.g. "hostname:8080").
+pub fn spawn_reporter(state: &Arc<StressEngine>, url: &str, agent: &str, interval: Duration) {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client");
+    let mut events = state.subscribe_burst_events();
+    let mut reporter = Reporter::new(Arc::clone(state), agent);
+    let url = url.to_string();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut backlog = VecDeque::new();
+        // When to resend the backlog, and the delay that led there
+        let mut retry: Option<(Instant, Duration)> = None;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if backlog.len() == MAX_BACKLOG {
+                        backlog.pop_front();
+                        tracing::warn!(%url, "report backlog full, dropped the oldest report");
+                    }
+                    backlog.push_back(reporter.report());
+                }
+                _ = tokio::time::sleep_until(retry.map_or_else(Instant::now, |(at, _)| at)), if retry.is_some() => {}
+                event = events.recv() => {
+                    match event {
+                        Ok(event) => reporter.bursts.add(&event),
+                        Err(RecvError::Lagged(missed)) => {
+                            tracing::warn!(%url, missed, "burst totals of the next report are incomplete");
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                    continue;
+                }
+            }
+            if retry.is_some_and(|(at, _)| at > Instant::now()) {
+                continue;
+            }
+            match deliver(&client, &url, &mut backlog).await {
+                Ok(()) => retry = None,
+                Err(e) => {
+                    let delay = retry.map_or(MIN_RETRY_DELAY, |(_, delay)| (delay * 2).min(MAX_RETRY_DELAY));
+                    tracing::warn!(
+                        %url,
+                        pending = backlog.len(),
+                        retry_in_secs = delay.as_secs(),
+                        "report failed: {}",
+                        e
+                    );
+                    retry = Some((Instant::now() + delay, delay));
+                }
+            }
+        }
+    });
+}
+
+// Send the backlog oldest first, stopping at the first failure
+async fn deliver(client: &reqwest::Client, url: &str, backlog: &mut VecDeque<MetricsReport>) -> Result<(), String> {
+    while let Some(report) = backlog.front() {
+        match client.post(url).json(report).send().await {
+            Ok(response) if response.status().is_success() => backlog.pop_front(),
+            Ok(response) => return Err(format!("HTTP {}", response.status())),
+            Err(e) => return Err(e.to_string()),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{EngineOptions, ExecutionMode, RunOptions, StartCpuRequest};
+
+    #[test]
+    fn reports_cover_consecutive_intervals() {
+        let state = Arc::new(StressEngine::test_harness(EngineOptions {
+            num_cores: 1,
+            ..EngineOptions::default()
+        }));
+        let mut reporter = Reporter::new(Arc::clone(&state), "rack1:8080");
+        state
+            .start_run(&StartCpuRequest {
+                mode: ExecutionMode::Threaded,
+                options: RunOptions::default(),
+            })
+            .unwrap();
+        state.advance_clock(3000);
+        reporter.bursts.add(&BurstEvent::BurstEnd {
+            core_id: 0,
+            timestamp_ms: 0,
+            planned_ms: 200,
+            actual_ms: 210,
+            ops: 5000,
+            batches: Vec::new(),
+        });
+
+        let first = reporter.report();
+        assert_eq!((first.agent.as_str(), first.hostname.as_str(), first.sequence), ("rack1:8080", "rack1", 1));
+        assert_eq!(first.timestamp_ms - first.start_ms, 3000);
+        assert_eq!(first.mean_ops_per_second, 300_000.0);
+        assert_eq!(first.bursts, BurstTotals { count: 1, total_ms: 210, ops: 5000 });
+        assert!(first.status.running);
+
+        state.stop();
+        let second = reporter.report();
+        assert_eq!((second.sequence, second.start_ms), (2, first.timestamp_ms));
+        assert_eq!((second.mean_ops_per_second, second.bursts), (0.0, BurstTotals::default()));
+
+        let json = serde_json::to_value(&second).unwrap();
+        assert_eq!(json["mode"], "threaded");
+        assert_eq!(serde_json::from_value::<MetricsReport>(json).unwrap().sequence, 2);
+    }
+}
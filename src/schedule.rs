@@ -0,0 +1,383 @@
+// Stress windows (POST /schedules): runs that start and stop by themselves, e.g. nightly 02:00-04:00, from a
+// cron expression plus a duration or a daily window, all in UTC. The engine checks the schedules every
+// second (StressEngine::run_schedules; the test harness on its virtual clock).
+use crate::engine::{ExecutionMode, FieldError, RunSignal, StartCpuRequest};
+use crate::sync::SyncWindow;
+use crate::units::utc_month_day;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// Longest window of a cron schedule
+pub const MAX_WINDOW_SECS: f64 = 7.0 * 86_400.0;
+
+const MS_PER_MINUTE: u64 = 60_000;
+const MS_PER_DAY: u64 = 86_400_000;
+// Every expression that matches at all matches within this many days (29 February on a given weekday
+// comes around every 28 years at most, but with day and weekday both set either one matches)
+const SEARCH_DAYS: u64 = 8 * 366;
+
+// Body of POST /schedules: a start request plus when to run it
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ScheduleRequest {
+    // Defaults to "schedule-<id>"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // When windows start, as "minute hour day month weekday" in UTC, e.g. "0 2 * * 1-5" for 02:00 on
+    // weekdays; needs duration_secs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    // Or a daily window in UTC, e.g. "02:00-04:00" (may wrap past midnight)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    // How long each cron window lasts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    #[serde(flatten)]
+    pub start: StartCpuRequest,
+}
+
+impl ScheduleRequest {
+    // Start times and length of the windows; reports every invalid field
+    pub(crate) fn windows(&self) -> Result<(CronSchedule, u64), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut windows = None;
+        match (&self.cron, &self.window) {
+            (Some(cron), None) => {
+                let cron = CronSchedule::parse(cron).map_err(|e| errors.push(FieldError::new("cron", e))).ok();
+                let length_ms = match self.duration_secs {
+                    Some(secs) if (60.0..=MAX_WINDOW_SECS).contains(&secs) => Some((secs * 1000.0) as u64),
+                    Some(_) => {
+                        let message = format!("duration_secs must be between 60 and {}", MAX_WINDOW_SECS);
+                        errors.push(FieldError::new("duration_secs", message));
+                        None
+                    }
+                    None => {
+                        errors.push(FieldError::new("duration_secs", "a cron schedule needs duration_secs"));
+                        None
+                    }
+                };
+                windows = cron.zip(length_ms);
+            }
+            (None, Some(window)) => {
+                if self.duration_secs.is_some() {
+                    let message = "the window sets the duration; leave out duration_secs";
+                    errors.push(FieldError::new("duration_secs", message));
+                }
+                match SyncWindow::parse(window) {
+                    Ok(window) => {
+                        let length_ms = window.length_minutes() as u64 * MS_PER_MINUTE;
+                        windows = Some((CronSchedule::daily(window.start_minute()), length_ms));
+                    }
+                    Err(e) => errors.push(FieldError::new("window", e)),
+                }
+            }
+            (Some(_), Some(_)) => errors.push(FieldError::new("window", "give either cron or window, not both")),
+            (None, None) => errors.push(FieldError::new("cron", "a schedule needs cron or window")),
+        }
+        match windows {
+            Some(windows) if errors.is_empty() => Ok(windows),
+            _ => Err(errors),
+        }
+    }
+}
+
+// One entry of GET /schedules
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ScheduleInfo {
+    pub id: u64,
+    pub name: String,
+    pub mode: ExecutionMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    pub duration_secs: f64,
+    // Start of the next window (Unix ms)
+    pub next_start_ms: Option<u64>,
+    // End of the window in progress (Unix ms), if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_until_ms: Option<u64>,
+    // Why the run of the last window didn't start, e.g. because jobs were running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+// Minutes, hours, days, months, and weekdays a cron expression matches, as bit sets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Whether day of month and weekday were left as "*"; when both are set, a day matching either counts
+    // (as in cron)
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    // Five fields: minute hour day-of-month month weekday. Each is "*", a number, a range "a-b", a step "*/n",
+    // "a-b/n", or "a/n", or a comma-separated list of those; Sunday is 0 or 7.
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "invalid cron expression {:?} (expected 5 fields: minute hour day month weekday)",
+                expression
+            ));
+        };
+        let weekdays = parse_field(weekday, "weekday", 0, 7)?;
+        let schedule = CronSchedule {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days: parse_field(day, "day", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        };
+        match schedule.next_start(0) {
+            Some(_) => Ok(schedule),
+            None => Err(format!("cron expression {:?} never matches", expression)),
+        }
+    }
+
+    // Every day at the given minute of the day
+    fn daily(minute_of_day: u32) -> Self {
+        CronSchedule {
+            minutes: 1 << (minute_of_day % 60),
+            hours: 1 << (minute_of_day / 60),
+            days: u64::MAX,
+            months: u64::MAX,
+            weekdays: u64::MAX,
+            any_day: true,
+            any_weekday: true,
+        }
+    }
+
+    fn matches_day(&self, day: u64) -> bool {
+        let (month, day_of_month) = utc_month_day(day * MS_PER_DAY);
+        // 1970-01-01 was a Thursday
+        let weekday = (day + 4) % 7;
+        let day_matches = self.days & 1 << day_of_month != 0;
+        let weekday_matches = self.weekdays & 1 << weekday != 0;
+        self.months & 1 << month != 0
+            && match (self.any_day, self.any_weekday) {
+                (true, true) => true,
+                (true, false) => weekday_matches,
+                (false, true) => day_matches,
+                (false, false) => day_matches || weekday_matches,
+            }
+    }
+
+    // First start at or after from_ms (Unix ms); starts are on whole minutes
+    pub(crate) fn next_start(&self, from_ms: u64) -> Option<u64> {
+        let from_ms = from_ms.div_ceil(MS_PER_MINUTE) * MS_PER_MINUTE;
+        let first_day = from_ms / MS_PER_DAY;
+        (first_day..first_day + SEARCH_DAYS)
+            .filter(|&day| self.matches_day(day))
+            .flat_map(|day| {
+                let minutes = (0..24u64)
+                    .filter(|hour| self.hours & 1 << hour != 0)
+                    .flat_map(|hour| (0..60u64).map(move |minute| (hour, minute)))
+                    .filter(|(_, minute)| self.minutes & 1 << minute != 0);
+                minutes.map(move |(hour, minute)| day * MS_PER_DAY + (hour * 60 + minute) * MS_PER_MINUTE)
+            })
+            .find(|&start| start >= from_ms)
+    }
+}
+
+fn parse_field(field: &str, name: &str, min: u64, max: u64) -> Result<u64, String> {
+    let invalid = || {
+        format!(
+            "invalid {} field {:?} (expected *, N, N-M, or */S, or a list of them, with values {} to {})",
+            name, field, min, max
+        )
+    };
+    let number = |value: &str| value.parse::<u64>().map_err(|_| invalid());
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, number(step)?),
+            None => (part, 1),
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (number(first)?, number(last)?),
+            // "a/n" runs from a to the end
+            None if part.contains('/') => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if step == 0 || first < min || last > max || first > last {
+            return Err(invalid());
+        }
+        for value in (first..=last).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+// A schedule of the engine: its request, and the window in progress
+pub(crate) struct Schedule {
+    pub(crate) id: u64,
+    pub(crate) request: ScheduleRequest,
+    cron: CronSchedule,
+    length_ms: u64,
+    // Start of the last window whose run was started (or couldn't be), so each window starts once
+    last_window_ms: Option<u64>,
+    active: Option<ActiveWindow>,
+    last_error: Option<String>,
+}
+
+struct ActiveWindow {
+    end_ms: u64,
+    // The run started for the window; None until it has started
+    signal: Option<Arc<RunSignal>>,
+}
+
+// What run_schedules does for a schedule
+pub(crate) enum ScheduleAction {
+    Start(u64, Box<StartCpuRequest>),
+    // Stop the run of a window that ended, if it is still the run the window started
+    Stop(Arc<RunSignal>),
+}
+
+impl Schedule {
+    pub(crate) fn new(id: u64, request: ScheduleRequest, cron: CronSchedule, length_ms: u64) -> Self {
+        Schedule {
+            id,
+            request,
+            cron,
+            length_ms,
+            last_window_ms: None,
+            active: None,
+            last_error: None,
+        }
+    }
+
+    // What is due at now_ms: the end of the window in progress, or the start of one. A schedule created (or
+    // an agent started) in the middle of a window starts its run right away.
+    pub(crate) fn due(&mut self, now_ms: u64) -> Option<ScheduleAction> {
+        if let Some(active) = &self.active {
+            if now_ms < active.end_ms {
+                return None;
+            }
+            return self.active.take().and_then(|active| active.signal).map(ScheduleAction::Stop);
+        }
+        let start_ms = self.cron.next_start((now_ms + 1).saturating_sub(self.length_ms))?;
+        if start_ms > now_ms || self.last_window_ms == Some(start_ms) {
+            return None;
+        }
+        self.last_window_ms = Some(start_ms);
+        self.active = Some(ActiveWindow {
+            end_ms: start_ms + self.length_ms,
+            signal: None,
+        });
+        Some(ScheduleAction::Start(self.id, Box::new(self.request.start.clone())))
+    }
+
+    // Record how the start of the window in progress went
+    pub(crate) fn started(&mut self, result: Result<Arc<RunSignal>, String>) {
+        match result {
+            Ok(signal) => {
+                self.last_error = None;
+                if let Some(active) = &mut self.active {
+                    active.signal = Some(signal);
+                }
+            }
+            Err(e) => {
+                self.last_error = Some(e);
+                self.active = None;
+            }
+        }
+    }
+
+    // The run of the window in progress, if it started
+    pub(crate) fn active_run(&self) -> Option<Arc<RunSignal>> {
+        self.active.as_ref().and_then(|active| active.signal.clone())
+    }
+
+    pub(crate) fn info(&self, now_ms: u64) -> ScheduleInfo {
+        ScheduleInfo {
+            id: self.id,
+            name: self.request.name.clone().unwrap_or_else(|| format!("schedule-{}", self.id)),
+            mode: self.request.start.mode,
+            cron: self.request.cron.clone(),
+            window: self.request.window.clone(),
+            duration_secs: self.length_ms as f64 / 1000.0,
+            next_start_ms: self.cron.next_start(now_ms + 1),
+            active_until_ms: self.active.as_ref().map(|active| active.end_ms),
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::parse_rfc3339;
+
+    fn at(timestamp: &str) -> u64 {
+        parse_rfc3339(timestamp).unwrap()
+    }
+
+    #[test]
+    fn cron_expressions_find_the_next_start() {
+        // Thursday 2025-10-09 14:03:21 UTC
+        let now = at("2025-10-09T14:03:21Z");
+        let next = |expression: &str| CronSchedule::parse(expression).unwrap().next_start(now);
+        assert_eq!(next("* * * * *"), Some(at("2025-10-09T14:04:00Z")));
+        assert_eq!(next("0 2 * * *"), Some(at("2025-10-10T02:00:00Z")));
+        assert_eq!(next("*/15 14,16 * * *"), Some(at("2025-10-09T14:15:00Z")));
+        assert_eq!(next("30 9 * * 1-5"), Some(at("2025-10-10T09:30:00Z")));
+        assert_eq!(next("0 0 * * 7"), Some(at("2025-10-12T00:00:00Z")));
+        assert_eq!(next("0 0 1 * *"), Some(at("2025-11-01T00:00:00Z")));
+        // Day of month or weekday, as in cron
+        assert_eq!(next("0 0 13 * 6"), Some(at("2025-10-11T00:00:00Z")));
+        assert_eq!(next("0 0 29 2 *"), Some(at("2028-02-29T00:00:00Z")));
+        // A start at from_ms counts
+        let on_the_minute = at("2025-10-09T14:03:00Z");
+        assert_eq!(CronSchedule::parse("3 14 * * *").unwrap().next_start(on_the_minute), Some(on_the_minute));
+
+        let invalid = ["", "* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *"];
+        // 30 February never comes
+        for invalid in invalid.into_iter().chain(["0 0 30 2 *"]) {
+            assert!(CronSchedule::parse(invalid).is_err(), "accepted {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn windows_start_once_and_end_on_time() {
+        let request: ScheduleRequest =
+            serde_json::from_str(r#"{"window":"23:30-00:30","mode":"threaded"}"#).unwrap();
+        let (cron, length_ms) = request.windows().unwrap();
+        assert_eq!(length_ms, 3_600_000);
+        let mut schedule = Schedule::new(1, request, cron, length_ms);
+
+        assert!(schedule.due(at("2025-10-09T23:29:59Z")).is_none());
+        assert!(matches!(schedule.due(at("2025-10-09T23:30:00Z")), Some(ScheduleAction::Start(1, _))));
+        schedule.started(Ok(Arc::new(RunSignal::default())));
+        assert!(schedule.due(at("2025-10-10T00:29:59Z")).is_none());
+        assert!(matches!(schedule.due(at("2025-10-10T00:30:00Z")), Some(ScheduleAction::Stop(_))));
+        assert!(schedule.due(at("2025-10-10T00:30:01Z")).is_none());
+        // Joining in the middle of the next window
+        let info = schedule.info(at("2025-10-10T23:45:00Z"));
+        assert_eq!(info.next_start_ms, Some(at("2025-10-11T23:30:00Z")));
+        assert!(matches!(schedule.due(at("2025-10-10T23:45:00Z")), Some(ScheduleAction::Start(..))));
+        assert_eq!(schedule.info(at("2025-10-10T23:45:00Z")).active_until_ms, Some(at("2025-10-11T00:30:00Z")));
+
+        let fields = |json: &str| -> Vec<String> {
+            let request: ScheduleRequest = serde_json::from_str(json).unwrap();
+            request.windows().unwrap_err().into_iter().map(|error| error.field).collect()
+        };
+        assert_eq!(fields(r#"{"mode":"threaded"}"#), ["cron"]);
+        assert_eq!(fields(r#"{"mode":"threaded","cron":"0 2 * * *"}"#), ["duration_secs"]);
+        let cron = r#"{"mode":"threaded","cron":"0 2 * *","duration_secs":1}"#;
+        assert_eq!(fields(cron), ["cron", "duration_secs"]);
+        let window = r#"{"mode":"threaded","window":"02:00-04:00","duration_secs":60}"#;
+        assert_eq!(fields(window), ["duration_secs"]);
+        assert_eq!(fields(r#"{"mode":"threaded","window":"02:00-04:00","cron":"0 2 * * *"}"#), ["window"]);
+    }
+}
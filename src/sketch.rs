@@ -0,0 +1,175 @@
+// Mergeable quantile sketch (DDSketch-style logarithmic buckets) for latency and throughput histograms
+use serde::{Deserialize, Serialize};
+
+// Every quantile estimate is within 1% of the true value
+pub const DEFAULT_RELATIVE_ACCURACY: f64 = 0.01;
+
+// Values at or below this are counted in the zero bucket
+const MIN_INDEXABLE_VALUE: f64 = 1e-9;
+
+// Full histogram of non-negative values in logarithmic buckets. Sketches with the same accuracy merge
+// exactly, so quantiles of merged per-node sketches are correct fleet-wide quantiles.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Sketch {
+    pub relative_accuracy: f64,
+    pub count: u64,
+    pub zero_count: u64,
+    // Exact extremes and total (all 0 while the sketch is empty)
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    // (bucket index, count), sorted by index; bucket i holds values in (gamma^(i-1), gamma^i]
+    // with gamma = (1 + relative_accuracy) / (1 - relative_accuracy)
+    pub bins: Vec<(i32, u64)>,
+}
+
+impl Default for Sketch {
+    fn default() -> Self {
+        Sketch::new(DEFAULT_RELATIVE_ACCURACY)
+    }
+}
+
+impl Sketch {
+    pub fn new(relative_accuracy: f64) -> Self {
+        Sketch {
+            relative_accuracy,
+            count: 0,
+            zero_count: 0,
+            min: 0.0,
+            max: 0.0,
+            sum: 0.0,
+            bins: Vec::new(),
+        }
+    }
+
+    fn gamma(&self) -> f64 {
+        (1.0 + self.relative_accuracy) / (1.0 - self.relative_accuracy)
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let value = value.max(0.0);
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+
+        if value <= MIN_INDEXABLE_VALUE {
+            self.zero_count += 1;
+            return;
+        }
+        let index = (value.ln() / self.gamma().ln()).ceil() as i32;
+        match self.bins.binary_search_by_key(&index, |&(i, _)| i) {
+            Ok(pos) => self.bins[pos].1 += 1,
+            Err(pos) => self.bins.insert(pos, (index, 1)),
+        }
+    }
+
+    // Add all values of another sketch; both must use the same relative accuracy
+    pub fn merge(&mut self, other: &Sketch) -> Result<(), String> {
+        if other.relative_accuracy != self.relative_accuracy {
+            return Err(format!(
+                "cannot merge sketches with relative accuracy {} and {}",
+                self.relative_accuracy, other.relative_accuracy
+            ));
+        }
+        if other.count == 0 {
+            return Ok(());
+        }
+        if self.count == 0 {
+            self.min = other.min;
+            self.max = other.max;
+        } else {
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+        }
+        self.count += other.count;
+        self.zero_count += other.zero_count;
+        self.sum += other.sum;
+        for &(index, count) in &other.bins {
+            match self.bins.binary_search_by_key(&index, |&(i, _)| i) {
+                Ok(pos) => self.bins[pos].1 += count,
+                Err(pos) => self.bins.insert(pos, (index, count)),
+            }
+        }
+        Ok(())
+    }
+
+    // Estimated value at quantile q (0.0-1.0), or None if the sketch is empty
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * (self.count - 1) as f64) as u64;
+        // The extremes are tracked exactly
+        if rank == 0 {
+            return Some(self.min);
+        }
+        if rank == self.count - 1 {
+            return Some(self.max);
+        }
+        if rank < self.zero_count {
+            return Some(0.0);
+        }
+
+        let gamma = self.gamma();
+        let mut seen = self.zero_count;
+        for &(index, count) in &self.bins {
+            seen += count;
+            if rank < seen {
+                // Midpoint (in relative terms) of the bucket
+                let value = 2.0 * gamma.powi(index) / (gamma + 1.0);
+                return Some(value.clamp(self.min, self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_are_within_relative_accuracy() {
+        let mut sketch = Sketch::default();
+        for value in 1..=10_000 {
+            sketch.add(value as f64);
+        }
+        for (q, exact) in [(0.5, 5000.0), (0.9, 9000.0), (0.99, 9900.0)] {
+            let estimate = sketch.quantile(q).unwrap();
+            assert!((estimate - exact).abs() / exact <= 0.011, "p{} = {}", q * 100.0, estimate);
+        }
+        assert_eq!(sketch.quantile(0.0), Some(1.0));
+        assert_eq!(sketch.quantile(1.0), Some(10_000.0));
+    }
+
+    #[test]
+    fn merged_sketches_match_one_sketch_of_all_values() {
+        let (mut a, mut b, mut all) = (Sketch::default(), Sketch::default(), Sketch::default());
+        for value in 0..1000 {
+            let value = value as f64 * 1.7;
+            if value < 800.0 { a.add(value) } else { b.add(value) }
+            all.add(value);
+        }
+        let mut merged = Sketch::default();
+        merged.merge(&a).unwrap();
+        merged.merge(&b).unwrap();
+        assert_eq!(merged.bins, all.bins);
+        assert_eq!((merged.count, merged.zero_count, merged.min, merged.max), (all.count, 1, 0.0, all.max));
+        assert_eq!(merged.quantile(0.95), all.quantile(0.95));
+
+        assert!(merged.merge(&Sketch::new(0.02)).is_err());
+    }
+}
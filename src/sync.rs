@@ -0,0 +1,593 @@
+// Result sync for agents on metered or cellular links: per-second samples are spooled to disk and pushed
+// to a collector (the `collect` subcommand) under a bandwidth cap, optionally only inside a daily time
+// window. Transfers are resumable: the collector keeps each agent's samples as one append-only stream
+// and only accepts bytes at its current length, so after a failure the agent continues where it left off.
+// The collector also receives the periodic reports of --report-to (see report.rs).
+use crate::encryption::EncryptionKey;
+use crate::engine::{Heartbeat, StressEngine, supervise, unix_time_ms};
+use crate::http::ErrorResponse;
+use crate::report::MetricsReport;
+use axum::Router;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Largest chunk per request; a capped sync sends about one second's worth of its bandwidth per request
+const MAX_CHUNK_BYTES: u64 = 1024 * 1024;
+const MIN_CHUNK_BYTES: u64 = 1024;
+// Synced bytes are dropped from the spool once this much has piled up
+const COMPACT_BYTES: u64 = 1024 * 1024;
+const IDLE_POLL: Duration = Duration::from_secs(5);
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+// Settings of the agent side (--sync-to and friends)
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    // Collector address (host:port)
+    pub collector: String,
+    // Name of this agent's stream on the collector (e.g. "hostname:8080")
+    pub agent: String,
+    // Payload bytes per second; None is unlimited
+    pub bandwidth_bytes_per_sec: Option<u64>,
+    // Only sync inside this daily window
+    pub window: Option<SyncWindow>,
+    // Encrypt every spooled sample line (--encryption-key-file); the collector stores the lines as sent
+    pub encryption_key: Option<EncryptionKey>,
+}
+
+// Daily time window in UTC, e.g. 22:00-06:00 (may wrap past midnight)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncWindow {
+    start_minute: u32,
+    end_minute: u32,
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+impl SyncWindow {
+    pub fn parse(window: &str) -> Result<Self, String> {
+        let parse_time = |time: &str| -> Result<u32, String> {
+            let (hours, minutes) = time
+                .trim()
+                .split_once(':')
+                .ok_or_else(|| format!("invalid time \"{}\" (expected HH:MM)", time))?;
+            match (hours.parse::<u32>(), minutes.parse::<u32>()) {
+                (Ok(hours), Ok(minutes)) if hours < 24 && minutes < 60 && time.trim().len() <= 5 => {
+                    Ok(hours * 60 + minutes)
+                }
+                _ => Err(format!("invalid time \"{}\" (expected HH:MM)", time)),
+            }
+        };
+        let (start, end) = window
+            .split_once('-')
+            .ok_or_else(|| format!("invalid window \"{}\" (expected HH:MM-HH:MM)", window))?;
+        let (start_minute, end_minute) = (parse_time(start)?, parse_time(end)?);
+        if start_minute == end_minute {
+            return Err(format!("window \"{}\" is empty", window));
+        }
+        Ok(SyncWindow { start_minute, end_minute })
+    }
+
+    // Minute of the day the window opens, and how many minutes it stays open
+    pub(crate) fn start_minute(&self) -> u32 {
+        self.start_minute
+    }
+
+    pub(crate) fn length_minutes(&self) -> u32 {
+        (self.end_minute + MINUTES_PER_DAY - self.start_minute) % MINUTES_PER_DAY
+    }
+
+    fn contains(&self, minute: u32) -> bool {
+        if self.start_minute < self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute)
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+
+    // Milliseconds until the window opens (0 while it is open)
+    pub fn wait_ms(&self, now_ms: u64) -> u64 {
+        let minute = ((now_ms / 60_000) % MINUTES_PER_DAY as u64) as u32;
+        if self.contains(minute) {
+            return 0;
+        }
+        let minutes = (self.start_minute + MINUTES_PER_DAY - minute) % MINUTES_PER_DAY;
+        minutes as u64 * 60_000 - now_ms % 60_000
+    }
+}
+
+// Bandwidth such as 512, 16K, or 1M (bytes per second; K/M/G are powers of 1024)
+pub fn parse_bandwidth(rate: &str) -> Result<u64, String> {
+    let rate = rate.trim();
+    let (digits, multiplier) = match rate.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&rate[..i], 1024),
+        Some((i, 'M' | 'm')) => (&rate[..i], 1024 * 1024),
+        Some((i, 'G' | 'g')) => (&rate[..i], 1024 * 1024 * 1024),
+        _ => (rate, 1),
+    };
+    match digits.parse::<u64>() {
+        Ok(value) if value > 0 => Ok(value.saturating_mul(multiplier)),
+        _ => Err(format!("invalid bandwidth \"{}\" (bytes per second, e.g. 512, 16K, or 1M)", rate)),
+    }
+}
+
+// Position of the spool in the agent's sample stream: the file holds the stream from `base` on, and
+// everything before `synced` is on the collector
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct SpoolCursor {
+    base: u64,
+    synced: u64,
+}
+
+#[derive(Debug)]
+struct SpoolState {
+    file: File,
+    len: u64,
+    cursor: SpoolCursor,
+}
+
+// On-disk queue of samples waiting for the collector: samples.jsonl plus the cursor in sync-state.json
+#[derive(Debug)]
+pub struct SampleSpool {
+    dir: PathBuf,
+    state: Mutex<SpoolState>,
+}
+
+impl SampleSpool {
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join("samples.jsonl");
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+        let len = file
+            .metadata()
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?
+            .len();
+
+        let cursor_path = dir.join("sync-state.json");
+        let mut cursor = match std::fs::read_to_string(&cursor_path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("failed to parse {}: {}", cursor_path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SpoolCursor::default(),
+            Err(e) => return Err(format!("failed to read {}: {}", cursor_path.display(), e)),
+        };
+        // Interrupted compaction: the file was truncated but the cursor wasn't saved
+        if cursor.synced > cursor.base + len || cursor.synced < cursor.base {
+            cursor.base = cursor.synced;
+        }
+
+        Ok(SampleSpool {
+            dir: dir.to_path_buf(),
+            state: Mutex::new(SpoolState { file, len, cursor }),
+        })
+    }
+
+    // ~/.distributed-cpu-stress-reporter/sync-<port> (survives reboots, unlike a temp dir)
+    pub fn default_dir(port: u16) -> PathBuf {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join(".distributed-cpu-stress-reporter")
+            .join(format!("sync-{}", port))
+    }
+
+    pub fn append(&self, data: &[u8]) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .file
+            .write_all(data)
+            .map_err(|e| format!("failed to append to the sample spool: {}", e))?;
+        state.len += data.len() as u64;
+        Ok(())
+    }
+
+    // Stream offset and bytes of the next unsynced chunk (up to max_bytes), if any
+    pub fn pending(&self, max_bytes: u64) -> Result<Option<(u64, Vec<u8>)>, String> {
+        let mut state = self.state.lock().unwrap();
+        let SpoolCursor { base, synced } = state.cursor;
+        let available = base + state.len - synced;
+        if available == 0 {
+            return Ok(None);
+        }
+        let mut data = vec![0; available.min(max_bytes) as usize];
+        state
+            .file
+            .seek(SeekFrom::Start(synced - base))
+            .and_then(|_| state.file.read_exact(&mut data))
+            .map_err(|e| format!("failed to read the sample spool: {}", e))?;
+        Ok(Some((synced, data)))
+    }
+
+    // Record that the collector has the stream up to `offset`. If the collector's copy doesn't match
+    // the spool (it lost data, or this spool was reset), the unsynced samples continue at `offset`.
+    pub fn acknowledge(&self, offset: u64) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let SpoolCursor { base, synced } = state.cursor;
+        let end = base + state.len;
+        if (base..=end).contains(&offset) {
+            state.cursor.synced = offset;
+            if offset == end && state.len >= COMPACT_BYTES {
+                state
+                    .file
+                    .set_len(0)
+                    .map_err(|e| format!("failed to compact the sample spool: {}", e))?;
+                state.len = 0;
+                state.cursor.base = offset;
+            }
+        } else {
+            tracing::warn!(
+                collector_offset = offset,
+                "collector's offset is outside the spool ({}..{}); continuing from it",
+                base,
+                end
+            );
+            let mut unsynced = vec![0; (end - synced) as usize];
+            state
+                .file
+                .seek(SeekFrom::Start(synced - base))
+                .and_then(|_| state.file.read_exact(&mut unsynced))
+                .and_then(|_| state.file.set_len(0))
+                .and_then(|_| state.file.write_all(&unsynced))
+                .map_err(|e| format!("failed to rewrite the sample spool: {}", e))?;
+            state.len = unsynced.len() as u64;
+            state.cursor = SpoolCursor {
+                base: offset,
+                synced: offset,
+            };
+        }
+        self.save_cursor(&state.cursor)
+    }
+
+    fn save_cursor(&self, cursor: &SpoolCursor) -> Result<(), String> {
+        let path = self.dir.join("sync-state.json");
+        let tmp_path = self.dir.join("sync-state.json.tmp");
+        let json = serde_json::to_string(cursor).expect("Failed to serialize sync cursor");
+        std::fs::write(&tmp_path, json).map_err(|e| format!("failed to write {}: {}", tmp_path.display(), e))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| format!("failed to replace {}: {}", path.display(), e))
+    }
+}
+
+// Collector's position in an agent's stream; body of GET and PUT /samples/{agent}
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SyncOffset {
+    pub offset: u64,
+}
+
+// Spool new per-second samples and push them to the collector until the process exits
+pub fn spawn_sync(state: &Arc<StressEngine>, spool: Arc<SampleSpool>, options: SyncOptions) {
+    let engine = Arc::clone(state);
+    let heartbeat = state.register_heartbeat("sample-spool".to_string());
+    let spooler_spool = Arc::clone(&spool);
+    let key = options.encryption_key.clone();
+    thread::spawn(move || {
+        supervise(&engine, &heartbeat, None, || {
+            sample_spooler(Arc::clone(&engine), Arc::clone(&heartbeat), Arc::clone(&spooler_spool), key.as_ref())
+        })
+    });
+    tokio::spawn(uploader(spool, options));
+}
+
+fn sample_spooler(
+    state: Arc<StressEngine>,
+    heartbeat: Arc<Heartbeat>,
+    spool: Arc<SampleSpool>,
+    key: Option<&EncryptionKey>,
+) {
+    let mut last_timestamp_ms = 0;
+    loop {
+        heartbeat.beat();
+        let lines = new_sample_lines(&state, &mut last_timestamp_ms, key);
+        if !lines.is_empty()
+            && let Err(e) = spool.append(lines.as_bytes())
+        {
+            tracing::error!("{}", e);
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+// Spool lines of the samples taken after `last_timestamp_ms`, which moves to the newest of them
+fn new_sample_lines(state: &StressEngine, last_timestamp_ms: &mut u64, key: Option<&EncryptionKey>) -> String {
+    let mut lines = String::new();
+    for sample in state.history(None) {
+        if sample.timestamp_ms > *last_timestamp_ms {
+            *last_timestamp_ms = sample.timestamp_ms;
+            let line = serde_json::to_string(&sample).expect("Failed to serialize sample");
+            match key {
+                Some(key) => lines.push_str(&key.encrypt_line(&line)),
+                None => lines.push_str(&line),
+            }
+            lines.push('\n');
+        }
+    }
+    lines
+}
+
+async fn uploader(spool: Arc<SampleSpool>, options: SyncOptions) {
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/samples/{}", options.collector, options.agent);
+    let chunk_bytes = options
+        .bandwidth_bytes_per_sec
+        .map_or(MAX_CHUNK_BYTES, |rate| rate.clamp(MIN_CHUNK_BYTES, MAX_CHUNK_BYTES));
+    let mut retry_delay = MIN_RETRY_DELAY;
+
+    loop {
+        if let Some(window) = &options.window {
+            let wait_ms = window.wait_ms(unix_time_ms());
+            if wait_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(wait_ms.min(60_000))).await;
+                continue;
+            }
+        }
+        let (offset, data) = match spool.pending(chunk_bytes) {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => {
+                tokio::time::sleep(IDLE_POLL).await;
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("sync: {}", e);
+                tokio::time::sleep(MAX_RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        let started = Instant::now();
+        let bytes = data.len() as u64;
+        let result = push_chunk(&client, &url, offset, data).await;
+        match result.and_then(|acknowledged| spool.acknowledge(acknowledged)) {
+            Ok(()) => retry_delay = MIN_RETRY_DELAY,
+            Err(e) => {
+                tracing::warn!(
+                    collector = %options.collector,
+                    retry_in_secs = retry_delay.as_secs(),
+                    "sync failed: {}",
+                    e
+                );
+                tokio::time::sleep(retry_delay).await;
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                continue;
+            }
+        }
+
+        // Stay under the cap on average
+        if let Some(rate) = options.bandwidth_bytes_per_sec {
+            let budget = Duration::from_secs_f64(bytes as f64 / rate as f64);
+            if let Some(remaining) = budget.checked_sub(started.elapsed()) {
+                tokio::time::sleep(remaining).await;
+            }
+        }
+    }
+}
+
+// Send one chunk; returns how much of the stream the collector has afterwards
+async fn push_chunk(client: &reqwest::Client, url: &str, offset: u64, data: Vec<u8>) -> Result<u64, String> {
+    let response = client
+        .put(url)
+        .query(&[("offset", offset)])
+        .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+        .body(data)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    // 409: the collector is at a different offset (e.g. an earlier response was lost); resume from there
+    if status.is_success() || status == reqwest::StatusCode::CONFLICT {
+        let position: SyncOffset = response.json().await.map_err(|e| format!("invalid response: {}", e))?;
+        Ok(position.offset)
+    } else {
+        Err(format!("HTTP {}", status))
+    }
+}
+
+// Collector: one <agent>.jsonl file of samples per agent in `dir`, and every agent's reports in
+// reports.jsonl
+#[derive(Debug)]
+struct Collector {
+    dir: PathBuf,
+    // Serializes appends so concurrent retries of one agent can't interleave
+    lock: Mutex<()>,
+    // Most recent report of each agent
+    latest_reports: Mutex<BTreeMap<String, MetricsReport>>,
+}
+
+const REPORTS_FILE: &str = "reports.jsonl";
+
+fn valid_agent_name(agent: &str) -> bool {
+    !agent.is_empty()
+        && !agent.starts_with('.')
+        && agent
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+impl Collector {
+    fn path(&self, agent: &str) -> Option<PathBuf> {
+        valid_agent_name(agent).then(|| self.dir.join(format!("{}.jsonl", agent)))
+    }
+}
+
+fn file_len(path: &Path) -> std::io::Result<u64> {
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    let body = ErrorResponse {
+        error: message,
+        errors: Vec::new(),
+    };
+    (status, axum::Json(body)).into_response()
+}
+
+async fn offset_handler(State(collector): State<Arc<Collector>>, UrlPath(agent): UrlPath<String>) -> Response {
+    let Some(path) = collector.path(&agent) else {
+        return error_response(StatusCode::BAD_REQUEST, format!("invalid agent name \"{}\"", agent));
+    };
+    match file_len(&path) {
+        Ok(offset) => axum::Json(SyncOffset { offset }).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn append_handler(
+    State(collector): State<Arc<Collector>>,
+    UrlPath(agent): UrlPath<String>,
+    Query(position): Query<SyncOffset>,
+    body: axum::body::Bytes,
+) -> Response {
+    let Some(path) = collector.path(&agent) else {
+        return error_response(StatusCode::BAD_REQUEST, format!("invalid agent name \"{}\"", agent));
+    };
+    let _guard = collector.lock.lock().unwrap();
+    let result = file_len(&path).and_then(|len| {
+        if len != position.offset {
+            return Ok((StatusCode::CONFLICT, len));
+        }
+        let mut file = std::fs::OpenOptions::new().append(true).create(true).open(&path)?;
+        file.write_all(&body)?;
+        Ok((StatusCode::OK, len + body.len() as u64))
+    });
+    match result {
+        Ok((status, offset)) => (status, axum::Json(SyncOffset { offset })).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn report_handler(
+    State(collector): State<Arc<Collector>>,
+    report: Result<axum::Json<MetricsReport>, JsonRejection>,
+) -> Response {
+    let report = match report {
+        Ok(axum::Json(report)) => report,
+        Err(rejection) => return error_response(StatusCode::BAD_REQUEST, rejection.body_text()),
+    };
+    if !valid_agent_name(&report.agent) {
+        return error_response(StatusCode::BAD_REQUEST, format!("invalid agent name \"{}\"", report.agent));
+    }
+    let _guard = collector.lock.lock().unwrap();
+    let line = serde_json::to_string(&report).expect("Failed to serialize report") + "\n";
+    let result = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(collector.dir.join(REPORTS_FILE))
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+    collector.latest_reports.lock().unwrap().insert(report.agent.clone(), report);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn latest_reports_handler(State(collector): State<Arc<Collector>>) -> Response {
+    let reports: Vec<MetricsReport> = collector.latest_reports.lock().unwrap().values().cloned().collect();
+    axum::Json(reports).into_response()
+}
+
+// Routes of the collector: GET /samples/{agent} (current offset), PUT /samples/{agent}?offset=N,
+// POST /reports (one --report-to report), and GET /reports (each agent's latest report since startup)
+pub fn collector_router(dir: &Path) -> Router {
+    let collector = Arc::new(Collector {
+        dir: dir.to_path_buf(),
+        lock: Mutex::new(()),
+        latest_reports: Mutex::new(BTreeMap::new()),
+    });
+    Router::new()
+        .route("/samples/:agent", get(offset_handler).put(append_handler))
+        .route("/reports", get(latest_reports_handler).post(report_handler))
+        .with_state(collector)
+}
+
+// Receive agents' samples into `dir` until the process exits
+pub async fn serve_collector(dir: &Path, port: u16) {
+    std::fs::create_dir_all(dir).unwrap_or_else(|e| panic!("Failed to create {}: {}", dir.display(), e));
+    let listener = tokio::net::TcpListener::bind(("::", port))
+        .await
+        .unwrap_or_else(|e| panic!("Failed to bind to port {}: {}", port, e));
+    tracing::info!(dir = %dir.display(), port, "collecting agent samples and reports");
+    axum::serve(listener, collector_router(dir))
+        .await
+        .expect("Server error");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_and_bandwidths_parse() {
+        let night = SyncWindow::parse("22:00-06:30").unwrap();
+        let at = |hours: u64, minutes: u64| (19_000 * 1440 + hours * 60 + minutes) * 60_000;
+        assert_eq!(night.wait_ms(at(23, 0)), 0);
+        assert_eq!(night.wait_ms(at(6, 29)), 0);
+        assert_eq!(night.wait_ms(at(6, 30)), (15 * 60 + 30) * 60_000);
+        assert_eq!(night.wait_ms(at(21, 59) + 30_000), 30_000);
+        for invalid in ["22:00", "25:00-06:00", "22:00-22:00", "22:000-06:00"] {
+            assert!(SyncWindow::parse(invalid).is_err(), "accepted {:?}", invalid);
+        }
+
+        assert_eq!(parse_bandwidth("16K"), Ok(16 * 1024));
+        assert_eq!(parse_bandwidth("512"), Ok(512));
+        assert!(parse_bandwidth("0").is_err());
+        assert!(parse_bandwidth("fast").is_err());
+    }
+
+    #[test]
+    fn spooled_samples_are_encrypted_with_a_key() {
+        let state = Arc::new(StressEngine::test_harness(crate::EngineOptions {
+            num_cores: 1,
+            ..crate::EngineOptions::default()
+        }));
+        state.advance_clock(2000);
+        let key = EncryptionKey::from_hex(&"ab".repeat(32)).unwrap();
+        let mut last_timestamp_ms = 0;
+        let lines = new_sample_lines(&state, &mut last_timestamp_ms, Some(&key));
+        assert_eq!(lines.lines().count(), 2);
+        for line in lines.lines() {
+            assert!(crate::encryption::is_encrypted_line(line), "{}", line);
+            let sample: crate::metrics::HistorySample = serde_json::from_str(&key.decrypt_line(line).unwrap()).unwrap();
+            assert!(sample.timestamp_ms <= last_timestamp_ms);
+        }
+        assert!(new_sample_lines(&state, &mut last_timestamp_ms, Some(&key)).is_empty());
+    }
+
+    #[test]
+    fn spool_resumes_where_the_collector_left_off() {
+        let dir = std::env::temp_dir().join(format!("cpu-stress-sync-test-{}", std::process::id()));
+        let spool = SampleSpool::open(&dir).unwrap();
+        spool.append(b"one\ntwo\n").unwrap();
+        assert_eq!(spool.pending(5).unwrap(), Some((0, b"one\nt".to_vec())));
+
+        // A lost response: the collector already has the first 6 bytes
+        spool.acknowledge(6).unwrap();
+        drop(spool);
+        let spool = SampleSpool::open(&dir).unwrap();
+        assert_eq!(spool.pending(100).unwrap(), Some((6, b"o\n".to_vec())));
+        spool.acknowledge(8).unwrap();
+        assert_eq!(spool.pending(100).unwrap(), None);
+
+        // The collector's copy is ahead of this spool (e.g. it was reset): new samples continue there
+        spool.append(b"three\n").unwrap();
+        spool.acknowledge(100).unwrap();
+        assert_eq!(spool.pending(100).unwrap(), Some((100, b"three\n".to_vec())));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
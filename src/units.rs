@@ -0,0 +1,312 @@
+// Human-readable numbers and timestamps for client output (status, watch, reports), and the ops units
+// /cpu-perf and /burst-perf can scale to (?units=). Other machine outputs (/status, Prometheus) always
+// carry the raw values.
+use serde::{Deserialize, Serialize};
+
+// Scale a value to SI units with three significant digits, e.g. 1234567 as "1.23M" and 254060 as "254k"
+pub fn si(value: f64) -> String {
+    // Switch units where rounding would print "1000k"
+    let (scaled, suffix) = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")]
+        .into_iter()
+        .find(|(scale, _)| value.abs() >= scale * 0.9995)
+        .map_or((value, ""), |(scale, suffix)| (value / scale, suffix));
+    let decimals = match scaled.abs() {
+        _ if suffix.is_empty() => 0,
+        abs if abs < 9.995 => 2,
+        abs if abs < 99.95 => 1,
+        _ => 0,
+    };
+    format!("{:.*}{}", decimals, scaled, suffix)
+}
+
+// Units of an ops/sec value: as counted, in thousands, or per worker core
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpsUnit {
+    #[default]
+    Raw,
+    KiloOps,
+    PerCore,
+}
+
+impl OpsUnit {
+    // Scale ops/sec measured over `cores` worker cores, rounded to 0.001 so text and JSON agree
+    pub fn scale(self, ops_per_second: u64, cores: usize) -> f64 {
+        let value = match self {
+            OpsUnit::Raw => return ops_per_second as f64,
+            OpsUnit::KiloOps => ops_per_second as f64 / 1000.0,
+            OpsUnit::PerCore => ops_per_second as f64 / cores.max(1) as f64,
+        };
+        (value * 1000.0).round() / 1000.0
+    }
+}
+
+// Time zone of printed timestamps (--time-zone)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeZone {
+    Utc,
+    // The host's zone (TZ / /etc/localtime), including daylight saving time
+    Local,
+    // Fixed offset from UTC in minutes
+    Offset(i32),
+}
+
+impl TimeZone {
+    // "utc", "local", or an offset such as +05:30, -08:00, or +02
+    pub fn parse(zone: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid time zone \"{}\" (expected local, utc, or an offset such as +05:30)", zone);
+        match zone.trim().to_ascii_lowercase().as_str() {
+            "utc" | "z" => return Ok(TimeZone::Utc),
+            "local" => return Ok(TimeZone::Local),
+            _ => {}
+        }
+        let zone = zone.trim();
+        let (sign, offset) = match zone.split_at_checked(1) {
+            Some(("+", offset)) => (1, offset),
+            Some(("-", offset)) => (-1, offset),
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+        match (hours.parse::<i32>(), minutes.parse::<i32>()) {
+            (Ok(hours), Ok(minutes)) if (0..=14).contains(&hours) && (0..60).contains(&minutes) => {
+                Ok(TimeZone::Offset(sign * (hours * 60 + minutes)))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    // Offset from UTC in minutes at the given instant
+    fn offset_minutes(&self, timestamp_ms: u64) -> i32 {
+        match *self {
+            TimeZone::Utc => 0,
+            TimeZone::Local => local_offset_secs((timestamp_ms / 1000) as i64) / 60,
+            TimeZone::Offset(minutes) => minutes,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn local_offset_secs(secs: i64) -> i32 {
+    let time = secs as libc::time_t;
+    // SAFETY: tm is plain data, and localtime_r only writes to the given struct
+    let offset = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff
+    };
+    offset as i32
+}
+
+// Without libc, "local" falls back to UTC
+#[cfg(not(target_os = "linux"))]
+fn local_offset_secs(_secs: i64) -> i32 {
+    0
+}
+
+// Calendar date and time of day at an instant in a time zone
+struct CivilTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    offset_minutes: i32,
+}
+
+impl CivilTime {
+    fn new(timestamp_ms: u64, zone: TimeZone) -> Self {
+        let offset_minutes = zone.offset_minutes(timestamp_ms);
+        let secs = (timestamp_ms / 1000) as i64 + offset_minutes as i64 * 60;
+        let (days, secs_of_day) = (secs.div_euclid(86_400), secs.rem_euclid(86_400) as u32);
+
+        // Days since 1970-01-01 to a Gregorian date (Howard Hinnant's civil_from_days)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        CivilTime {
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day / 60 % 60,
+            second: secs_of_day % 60,
+            offset_minutes,
+        }
+    }
+}
+
+// e.g. "2025-10-09 14:03:21 +02:00" (or "... UTC")
+pub fn format_timestamp(timestamp_ms: u64, zone: TimeZone) -> String {
+    let time = CivilTime::new(timestamp_ms, zone);
+    let offset = match (zone, time.offset_minutes) {
+        (TimeZone::Utc, _) => "UTC".to_string(),
+        (_, minutes) => format!(
+            "{}{:02}:{:02}",
+            if minutes < 0 { '-' } else { '+' },
+            minutes.abs() / 60,
+            minutes.abs() % 60
+        ),
+    };
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second, offset
+    )
+}
+
+// e.g. "14:03:21", for lines that repeat every second
+pub fn format_time_of_day(timestamp_ms: u64, zone: TimeZone) -> String {
+    let time = CivilTime::new(timestamp_ms, zone);
+    format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second)
+}
+
+// e.g. "2025-10-09T14:03:21.500Z", for timestamps sent to agents (start_at)
+pub fn format_rfc3339(timestamp_ms: u64) -> String {
+    let time = CivilTime::new(timestamp_ms, TimeZone::Utc);
+    format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        time.year,
+        time.month,
+        time.day,
+        time.hour,
+        time.minute,
+        time.second,
+        timestamp_ms % 1000
+    )
+}
+
+// Month (1-12) and day of the month of an instant in UTC, for matching cron schedules
+pub(crate) fn utc_month_day(timestamp_ms: u64) -> (u32, u32) {
+    let time = CivilTime::new(timestamp_ms, TimeZone::Utc);
+    (time.month, time.day)
+}
+
+// Parse an RFC 3339 timestamp such as "2025-10-09T14:03:21Z" or "2025-10-09T16:03:21.5+02:00" into Unix ms.
+// Fractions beyond milliseconds are truncated.
+pub fn parse_rfc3339(value: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid RFC 3339 timestamp {:?} (e.g. 2025-10-09T14:03:21Z)", value);
+    let field = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit()).then(|| digits.parse::<u32>().ok()).flatten();
+
+    let (date, time) = value.split_once(['T', 't', ' ']).ok_or_else(invalid)?;
+    let [year, month, day] = date.splitn(3, '-').collect::<Vec<_>>()[..] else {
+        return Err(invalid());
+    };
+    let (time, offset_minutes) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let split = time.rfind(['+', '-']).ok_or_else(invalid)?;
+        let (time, offset) = time.split_at(split);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+        match (field(hours), field(minutes)) {
+            (Some(hours), Some(minutes)) if hours < 24 && minutes < 60 => (time, sign * (hours * 60 + minutes) as i64),
+            _ => return Err(invalid()),
+        }
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let [hour, minute, second] = time.splitn(3, ':').collect::<Vec<_>>()[..] else {
+        return Err(invalid());
+    };
+    if year.len() != 4 || [month, day, hour, minute, second].iter().any(|part| part.len() != 2) {
+        return Err(invalid());
+    }
+    let millis = match fraction {
+        "" => 0,
+        _ if fraction.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse::<i64>().map_err(|_| invalid())?
+        }
+        _ => return Err(invalid()),
+    };
+    let (Some(year), Some(month), Some(day), Some(hour), Some(minute), Some(second)) =
+        (field(year), field(month), field(day), field(hour), field(minute), field(second))
+    else {
+        return Err(invalid());
+    };
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=12).contains(&month) || !(1..=days_in_month).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+
+    // Gregorian date to days since 1970-01-01 (Howard Hinnant's days_from_civil)
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = i64::from((month + 9) % 12);
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let secs = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second) - offset_minutes * 60;
+    u64::try_from(secs * 1000 + millis).map_err(|_| format!("{:?} is before 1970", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn si_scaling() {
+        assert_eq!(si(0.0), "0");
+        assert_eq!(si(812.4), "812");
+        assert_eq!(si(999.7), "1.00k");
+        assert_eq!(si(254_060.0), "254k");
+        assert_eq!(si(1_234_567.0), "1.23M");
+        assert_eq!(si(999_999.0), "1.00M");
+        assert_eq!(si(42_500_000_000.0), "42.5G");
+    }
+
+    #[test]
+    fn ops_units() {
+        assert_eq!(OpsUnit::Raw.scale(254_060, 4), 254_060.0);
+        assert_eq!(OpsUnit::KiloOps.scale(254_060, 4), 254.06);
+        assert_eq!(OpsUnit::PerCore.scale(254_060, 4), 63_515.0);
+        assert_eq!(OpsUnit::PerCore.scale(1000, 3), 333.333);
+        assert_eq!(OpsUnit::PerCore.scale(1000, 0), 1000.0);
+    }
+
+    #[test]
+    fn timestamps_in_time_zones() {
+        // 2025-10-09 14:03:21.500 UTC
+        let ms = 1_760_018_601_500;
+        assert_eq!(format_timestamp(ms, TimeZone::Utc), "2025-10-09 14:03:21 UTC");
+        assert_eq!(format_timestamp(ms, TimeZone::parse("+05:30").unwrap()), "2025-10-09 19:33:21 +05:30");
+        assert_eq!(format_timestamp(ms, TimeZone::parse("-08").unwrap()), "2025-10-09 06:03:21 -08:00");
+        assert_eq!(format_time_of_day(0, TimeZone::parse("-01:00").unwrap()), "23:00:00");
+        assert_eq!(format_timestamp(951_782_400_000, TimeZone::Utc), "2000-02-29 00:00:00 UTC");
+
+        for invalid in ["", "+", "05:30", "+5:60", "-15", "+-1", "Europe/Paris"] {
+            assert!(TimeZone::parse(invalid).is_err(), "accepted {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn rfc3339_timestamps() {
+        let ms = 1_760_018_601_500;
+        assert_eq!(format_rfc3339(ms), "2025-10-09T14:03:21.500Z");
+        assert_eq!(parse_rfc3339("2025-10-09T14:03:21.500Z"), Ok(ms));
+        assert_eq!(parse_rfc3339("2025-10-09T16:03:21.5+02:00"), Ok(ms));
+        assert_eq!(parse_rfc3339("2025-10-09t09:03:21.500123-05:00"), Ok(ms));
+        assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z"), Ok(951_782_400_000));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Ok(0));
+
+        for invalid in ["", "2025-10-09", "2025-10-09T14:03:21", "2025-02-29T00:00:00Z", "2025-10-09T24:00:00Z"] {
+            assert!(parse_rfc3339(invalid).is_err(), "accepted {:?}", invalid);
+        }
+        assert!(parse_rfc3339("1969-12-31T23:59:59Z").is_err());
+        assert!(parse_rfc3339("2025-10-09T14:03:21.x+01:00").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+// Webhook notifications: every run/job lifecycle and ops-drop event is POSTed as JSON to each --webhook
+// URL. Each URL gets its own task, so a slow or unreachable receiver doesn't delay the others.
+use crate::engine::StressEngine;
+use crate::metrics::LifecycleEvent;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+// Delivery attempts per event, with a doubling delay between them (1s, 2s)
+const MAX_ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Check a --webhook URL. Only plain http is supported (the HTTP client is built without TLS).
+pub fn validate_webhook_url(url: &str) -> Result<(), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("{}: only http:// URLs are supported", url))?;
+    if rest.is_empty() || rest.starts_with('/') {
+        return Err(format!("{}: missing host", url));
+    }
+    reqwest::Url::parse(url).map_err(|e| format!("{}: {}", url, e))?;
+    Ok(())
+}
+
+// JSON body of one notification: the event plus the agent that sent it
+fn payload(event: &LifecycleEvent, agent: &str) -> serde_json::Value {
+    let mut payload = serde_json::to_value(event).expect("Failed to serialize lifecycle event");
+    payload["agent"] = serde_json::Value::String(agent.to_string());
+    payload
+}
+
+// Deliver lifecycle events to each URL until the process exits. `agent` identifies this agent in
+// the notifications (e.g. "hostname:8080").
+pub fn spawn_webhooks(state: &Arc<StressEngine>, urls: &[String], agent: &str) {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client");
+    for url in urls {
+        // Subscribe now so events from runs started right after this call aren't missed
+        let mut events = state.subscribe_lifecycle_events();
+        let (client, url, agent) = (client.clone(), url.clone(), agent.to_string());
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(%url, missed, "webhook dropped events (receiver too slow)");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                deliver(&client, &url, &payload(&event, &agent)).await;
+            }
+        });
+    }
+}
+
+async fn deliver(client: &reqwest::Client, url: &str, payload: &serde_json::Value) {
+    let mut delay = Duration::from_secs(1);
+    for attempt in 1..=MAX_ATTEMPTS {
+        let error = match client.post(url).json(payload).send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+        if attempt == MAX_ATTEMPTS {
+            tracing::warn!(%url, event = %payload["event"], "webhook gave up: {}", error);
+            return;
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ExecutionMode;
+
+    #[test]
+    fn urls_and_payloads() {
+        assert!(validate_webhook_url("http://hooks.local:9000/cpu").is_ok());
+        for invalid in ["https://hooks.local/cpu", "hooks.local/cpu", "http://", "http:///cpu"] {
+            assert!(validate_webhook_url(invalid).is_err(), "accepted {:?}", invalid);
+        }
+
+        let event = LifecycleEvent::Started {
+            timestamp_ms: 1_000,
+            mode: ExecutionMode::Bursty,
+        };
+        assert_eq!(
+            payload(&event, "rack1:8080"),
+            serde_json::json!({"event": "started", "timestamp_ms": 1000, "mode": "bursty", "agent": "rack1:8080"})
+        );
+    }
+}
@@ -0,0 +1,1041 @@
+// CPU workloads: the prime-counting and stream memory-bandwidth kernels, per-mode worker loops, and burst
+// scheduling
+use crate::engine::{
+    BurstBackend, BurstDistribution, BurstSource, BurstTrigger, ExecutionMode, FieldError, Heartbeat, RunConfig,
+    RunOptions, RunSignal, StressEngine, Workload, unix_time_ms,
+};
+use crate::metrics::{BurstBatch, BurstEvent, emit_burst_event, record_batch_latency};
+use crate::process_stats::{ProcessRecord, wait_with_usage};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+// Longest burst_schedule accepted in a start request
+const MAX_SCHEDULE_LEN: usize = 100_000;
+
+// Burst duration model used by the burst coordinators
+#[derive(Debug, Clone)]
+pub(crate) struct BurstConfig {
+    pub(crate) distribution: BurstDistribution,
+    pub(crate) mean_secs: f64,
+    pub(crate) min_secs: f64,
+    pub(crate) max_secs: f64,
+    pub(crate) source: BurstSource,
+    // Durations in seconds for BurstSource::Schedule (empty otherwise)
+    pub(crate) schedule: Arc<[f64]>,
+}
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        BurstConfig {
+            distribution: BurstDistribution::Exponential,
+            mean_secs: 0.3,
+            min_secs: 0.05,
+            max_secs: 1.0,
+            source: BurstSource::Random,
+            schedule: Arc::from([]),
+        }
+    }
+}
+
+impl BurstConfig {
+    // Build a config from the optional run options, falling back to defaults
+    pub(crate) fn from_options(options: &RunOptions) -> Result<Self, Vec<FieldError>> {
+        let defaults = BurstConfig::default();
+        let config = BurstConfig {
+            distribution: options.burst_distribution.unwrap_or(defaults.distribution),
+            mean_secs: options.burst_mean_secs.unwrap_or(defaults.mean_secs),
+            min_secs: options.burst_min_secs.unwrap_or(defaults.min_secs),
+            max_secs: options.burst_max_secs.unwrap_or(defaults.max_secs),
+            source: options.burst_source.unwrap_or(if options.burst_schedule.is_some() {
+                BurstSource::Schedule
+            } else {
+                BurstSource::Random
+            }),
+            schedule: Arc::from(options.burst_schedule.clone().unwrap_or_default()),
+        };
+
+        let mut errors = Vec::new();
+        for (name, value) in [
+            ("burst_mean_secs", config.mean_secs),
+            ("burst_min_secs", config.min_secs),
+            ("burst_max_secs", config.max_secs),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                errors.push(FieldError::new(name, format!("{} must be a positive number of seconds", name)));
+            }
+        }
+        if config.min_secs > config.max_secs {
+            errors.push(FieldError::new("burst_min_secs", "burst_min_secs must not exceed burst_max_secs"));
+        } else if config.distribution != BurstDistribution::Uniform
+            && config.source != BurstSource::Schedule
+            && !(config.min_secs..=config.max_secs).contains(&config.mean_secs)
+        {
+            // Outside the bounds the durations would all be clamped to one of them
+            let message = "burst_mean_secs must be between burst_min_secs and burst_max_secs";
+            errors.push(FieldError::new("burst_mean_secs", message));
+        }
+
+        match config.source {
+            BurstSource::Schedule => {
+                if config.schedule.is_empty() {
+                    let message = "burst_source \"schedule\" requires a non-empty burst_schedule";
+                    errors.push(FieldError::new("burst_schedule", message));
+                }
+                if config.schedule.len() > MAX_SCHEDULE_LEN {
+                    let message = format!("burst_schedule may have at most {} entries", MAX_SCHEDULE_LEN);
+                    errors.push(FieldError::new("burst_schedule", message));
+                }
+                if let Some(i) = config.schedule.iter().position(|secs| !secs.is_finite() || *secs <= 0.0) {
+                    let message = format!("burst_schedule entry {} must be a positive number of seconds", i);
+                    errors.push(FieldError::new("burst_schedule", message));
+                }
+            }
+            _ if options.burst_schedule.is_some() => {
+                let message = "burst_schedule can only be used with burst_source \"schedule\"";
+                errors.push(FieldError::new("burst_schedule", message));
+            }
+            _ => {}
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Map a point u in [0, 1) to a burst duration through the distribution's inverse CDF
+    // (truncated to [min, max]), so evenly spread points give evenly covered durations
+    pub(crate) fn quantile_secs(&self, u: f64) -> f64 {
+        let secs = match self.distribution {
+            BurstDistribution::Exponential => {
+                let low = (-self.min_secs / self.mean_secs).exp();
+                let high = (-self.max_secs / self.mean_secs).exp();
+                -self.mean_secs * (low - u * (low - high)).ln()
+            }
+            BurstDistribution::Uniform => self.min_secs + u * (self.max_secs - self.min_secs),
+            BurstDistribution::Fixed => self.mean_secs,
+        };
+        secs.clamp(self.min_secs, self.max_secs)
+    }
+
+    // Sample a burst duration in seconds according to the configured distribution
+    pub(crate) fn sample_secs<R: rand::Rng>(&self, rng: &mut R) -> f64 {
+        use rand_distr::{Distribution, Exp};
+
+        let sampled = match self.distribution {
+            BurstDistribution::Exponential => {
+                // λ = 1/mean
+                let exp = Exp::new(1.0 / self.mean_secs)
+                    .expect("Failed to create exponential distribution");
+                exp.sample(rng)
+            }
+            BurstDistribution::Uniform => {
+                if self.min_secs == self.max_secs {
+                    self.min_secs
+                } else {
+                    rng.gen_range(self.min_secs..=self.max_secs)
+                }
+            }
+            BurstDistribution::Fixed => self.mean_secs,
+        };
+
+        sampled.clamp(self.min_secs, self.max_secs)
+    }
+}
+
+// Burst durations of one schedule, in order: random draws or successive points of a sequence
+pub(crate) struct BurstSampler {
+    config: BurstConfig,
+    index: u64,
+}
+
+impl BurstSampler {
+    pub(crate) fn new(config: BurstConfig) -> Self {
+        BurstSampler { config, index: 0 }
+    }
+
+    // Next burst duration in seconds; `rng` is only used by the random source
+    pub(crate) fn next_secs<R: rand::Rng>(&mut self, rng: &mut R) -> f64 {
+        let secs = match self.config.source {
+            BurstSource::Random => self.config.sample_secs(rng),
+            // Skip the sequence's first point (0), which would always give the minimum duration
+            BurstSource::LowDiscrepancy => self.config.quantile_secs(van_der_corput(self.index + 1)),
+            BurstSource::Schedule => self.config.schedule[(self.index % self.config.schedule.len() as u64) as usize],
+        };
+        self.index += 1;
+        secs
+    }
+}
+
+// n-th point of the base-2 van der Corput sequence in [0, 1): 1/2, 1/4, 3/4, 1/8, 5/8, ...
+fn van_der_corput(n: u64) -> f64 {
+    n.reverse_bits() as f64 / 2f64.powi(64)
+}
+
+// Simple prime number check using trial division
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    if n == 2 {
+        return true;
+    }
+    if n.is_multiple_of(2) {
+        return false;
+    }
+    let limit = (n as f64).sqrt() as u64;
+    for i in (3..=limit).step_by(2) {
+        if n.is_multiple_of(i) {
+            return false;
+        }
+    }
+    true
+}
+
+// CPU-bound worker that continuously calculates primes until its run stops
+pub(crate) fn cpu_worker(state: Arc<StressEngine>, signal: Arc<RunSignal>, heartbeat: Arc<Heartbeat>) {
+    let mut n = 2u64;
+    while !signal.is_stopped() {
+        if n.is_multiple_of(4096) {
+            heartbeat.beat();
+        }
+        if is_prime(n) {
+            signal.count_ops(&state, 1);
+        }
+        n = n.wrapping_add(1);
+        if n < 2 {
+            n = 2; // Reset on overflow
+        }
+    }
+}
+
+// STREAM-style memory-bandwidth kernel: three arrays far larger than the caches, swept by the copy,
+// scale, add, and triad kernels in turn (c = a, b = s·c, c = a + b, a = b + s·c)
+pub(crate) struct StreamArrays {
+    a: Vec<f64>,
+    b: Vec<f64>,
+    c: Vec<f64>,
+    // Kernel of the next step: 0 copy, 1 scale, 2 add, 3 triad
+    next: usize,
+}
+
+impl StreamArrays {
+    const SCALAR: f64 = 3.0;
+
+    // Allocate and touch the three arrays (on the calling thread, so the pages are local to its core); fails
+    // instead of aborting when the memory isn't available
+    pub(crate) fn new(array_mb: u64) -> Result<Self, std::collections::TryReserveError> {
+        let len = (array_mb as usize * 1024 * 1024) / std::mem::size_of::<f64>();
+        let array = |value: f64| -> Result<Vec<f64>, std::collections::TryReserveError> {
+            let mut array = Vec::new();
+            array.try_reserve_exact(len)?;
+            array.resize(len, value);
+            Ok(array)
+        };
+        Ok(StreamArrays {
+            a: array(1.0)?,
+            b: array(2.0)?,
+            c: array(0.0)?,
+            next: 0,
+        })
+    }
+
+    // Run the next kernel over the whole arrays; returns the bytes it read and wrote
+    pub(crate) fn step(&mut self) -> u64 {
+        let array_bytes = std::mem::size_of_val(self.a.as_slice()) as u64;
+        let kernel = self.next;
+        self.next = (self.next + 1) % 4;
+        match kernel {
+            0 => {
+                self.c.copy_from_slice(&self.a);
+                2 * array_bytes
+            }
+            1 => {
+                for (b, c) in self.b.iter_mut().zip(&self.c) {
+                    *b = Self::SCALAR * c;
+                }
+                2 * array_bytes
+            }
+            2 => {
+                for ((c, a), b) in self.c.iter_mut().zip(&self.a).zip(&self.b) {
+                    *c = a + b;
+                }
+                3 * array_bytes
+            }
+            _ => {
+                for ((a, b), c) in self.a.iter_mut().zip(&self.b).zip(&self.c) {
+                    *a = b + Self::SCALAR * c;
+                }
+                std::hint::black_box(&self.a);
+                3 * array_bytes
+            }
+        }
+    }
+}
+
+// Allocate a stream worker's arrays, logging why if that fails
+fn stream_arrays(core_id: usize, array_mb: u64) -> Option<StreamArrays> {
+    match StreamArrays::new(array_mb) {
+        Ok(arrays) => Some(arrays),
+        Err(e) => {
+            tracing::error!(core_id, array_mb, "failed to allocate the stream arrays: {}", e);
+            None
+        }
+    }
+}
+
+// Memory-bound worker that sweeps its stream arrays until its run stops
+pub(crate) fn stream_worker(
+    state: Arc<StressEngine>,
+    signal: Arc<RunSignal>,
+    heartbeat: Arc<Heartbeat>,
+    core_id: usize,
+    array_mb: u64,
+) {
+    let Some(mut arrays) = stream_arrays(core_id, array_mb) else {
+        return;
+    };
+    while !signal.is_stopped() {
+        heartbeat.beat();
+        signal.count_bytes(&state, arrays.step());
+    }
+}
+
+// Throttled mode: hold a steady utilization by alternating compute and sleep within each duty cycle
+pub(crate) fn throttled_worker(
+    state: Arc<StressEngine>,
+    signal: Arc<RunSignal>,
+    heartbeat: Arc<Heartbeat>,
+    config: watch::Receiver<RunConfig>,
+    core_id: usize,
+) {
+    // The workload is fixed for the worker's lifetime; changing it starts new workers
+    let (workload, array_mb) = (config.borrow().workload, config.borrow().stream_array_mb);
+    let mut stream = match workload {
+        Workload::Primes => None,
+        Workload::Stream => match stream_arrays(core_id, array_mb) {
+            Some(arrays) => Some(arrays),
+            None => return,
+        },
+    };
+    let mut n = 2u64;
+    while !signal.is_stopped() {
+        heartbeat.beat();
+
+        // Settings may change between windows
+        let config = config.borrow().clone();
+        let window = Duration::from_millis(config.duty_cycle_ms);
+        let busy = window * config.utilization as u32 / 100;
+
+        // COMPUTE PHASE - check the clock every few hundred candidates (or after every stream kernel) to
+        // keep overhead low
+        let window_start = Instant::now();
+        let mut ops = 0u64;
+        while window_start.elapsed() < busy && !signal.is_stopped() {
+            if let Some(arrays) = &mut stream {
+                signal.count_bytes(&state, arrays.step());
+                continue;
+            }
+            for _ in 0..256 {
+                if is_prime(n) {
+                    ops += 1;
+                }
+                n = n.wrapping_add(1);
+                if n < 2 {
+                    n = 2; // Reset on overflow
+                }
+            }
+        }
+        signal.count_ops(&state, ops);
+
+        // SLEEP PHASE - rest of the window
+        let elapsed = window_start.elapsed();
+        if elapsed < window {
+            signal.sleep(&heartbeat, window - elapsed);
+        }
+    }
+}
+
+// Worker mode: Run a fixed amount of work and exit
+pub fn run_worker(num_ops: u64) {
+    // Print the number of operations performed
+    println!("{}", count_primes(num_ops));
+}
+
+// One unit of burst work: find the first `num_ops` primes; returns the number found
+pub fn count_primes(num_ops: u64) -> u64 {
+    let mut count = 0u64;
+    let mut n = 2u64;
+
+    while count < num_ops {
+        if is_prime(n) {
+            count += 1;
+        }
+        n = n.wrapping_add(1);
+        if n < 2 {
+            n = 2; // Reset on overflow
+        }
+    }
+
+    std::hint::black_box(count)
+}
+
+// Run one worker process to completion, inside the worker cgroup if one is configured, and add what it
+// cost to the process stats
+fn run_worker_process(state: &StressEngine, worker_ops: u64, core_id: usize) -> std::io::Result<std::process::Output> {
+    static EXE_PATH: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    let exe_path =
+        EXE_PATH.get_or_init(|| std::env::current_exe().expect("Failed to get current executable path"));
+
+    let spawn_start = Instant::now();
+    let spawned = Command::new(exe_path)
+        .arg("--worker")
+        .arg("--worker-ops")
+        .arg(worker_ops.to_string())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let spawn = spawn_start.elapsed();
+    let mut child = spawned.inspect_err(|_| state.process_stats.lock().unwrap().spawn_error())?;
+
+    // An uncapped worker would defeat the cgroup, so don't let it run
+    if let Some(cgroup) = &state.worker_cgroup
+        && let Err(e) = cgroup.add(child.id())
+    {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(std::io::Error::other(e));
+    }
+
+    let (output, usage) = wait_with_usage(child)?;
+    let wall = spawn_start.elapsed() - spawn;
+    let record = ProcessRecord::new(core_id, unix_time_ms(), spawn, wall, output.status, usage);
+    state.process_stats.lock().unwrap().record(record);
+    Ok(output)
+}
+
+// Fresh-process mode: Spawn child processes continuously
+pub(crate) fn process_spawner(
+    state: Arc<StressEngine>,
+    signal: Arc<RunSignal>,
+    heartbeat: Arc<Heartbeat>,
+    config: watch::Receiver<RunConfig>,
+    core_id: usize,
+) {
+    while !signal.is_stopped() {
+        heartbeat.beat();
+
+        // Spawn child process (worker_ops may change between processes, see PATCH /config)
+        let worker_ops = config.borrow().worker_ops;
+        let process_start = Instant::now();
+        let output = run_worker_process(&state, worker_ops, core_id);
+
+        match output {
+            Ok(output) => {
+                if output.status.success() {
+                    // Parse the operation count from stdout
+                    if let Ok(stdout) = String::from_utf8(output.stdout)
+                        && let Ok(ops) = stdout.trim().parse::<u64>()
+                    {
+                        signal.count_ops(&state, ops);
+                        record_batch_latency(&state, process_start.elapsed());
+                    }
+                } else {
+                    tracing::warn!(core_id, status = %output.status, "worker process failed");
+                }
+            }
+            Err(e) => {
+                tracing::error!(core_id, "failed to spawn worker process: {}", e);
+                signal.sleep(&heartbeat, Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+// Shared burst schedule for fleet-aligned bursts: every agent given the same seed and epoch
+// derives the same sequence of bursts, anchored to wall-clock time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FleetSync {
+    pub(crate) seed: u64,
+    pub(crate) epoch_ms: u64,
+    pub(crate) phase_offset_ms: u64,
+}
+
+// Bounds on the frame length of a random or low-discrepancy FleetSchedule
+const MIN_FRAME_MS: u64 = 60_000;
+const MAX_FRAME_MS: u64 = 3_600_000;
+
+// Cycles of the longest possible length that fit in one frame
+const CYCLES_PER_FRAME: u64 = 16;
+
+// Deterministic burst/idle cycle generator for a FleetSync schedule. Time after the schedule start is
+// split into fixed-length frames, each generated from its own seed, so an agent joining late jumps
+// straight to the current frame instead of replaying every cycle since the epoch. A burst_schedule
+// frame is one pass through the schedule; other frames cut their last cycle short at the frame end.
+pub(crate) struct FleetSchedule {
+    seed: u64,
+    burst_config: BurstConfig,
+    utilization: u64,
+    start_ms: u64,
+    frame_ms: u64,
+    frame: Option<u64>,
+    rng: rand::rngs::StdRng,
+    sampler: BurstSampler,
+    cycle_start_ms: u64,
+    burst_ms: u64,
+    idle_ms: u64,
+}
+
+impl FleetSchedule {
+    pub(crate) fn new(sync: FleetSync, burst_config: BurstConfig, utilization: u64) -> Self {
+        use rand::SeedableRng;
+
+        // 0% would never burst; treat it as the smallest non-zero duty cycle
+        let utilization = utilization.clamp(1, 100);
+        let frame_ms = match burst_config.source {
+            BurstSource::Schedule => burst_config.schedule.iter().fold(0u64, |total, secs| {
+                let (burst_ms, idle_ms) = cycle_ms(*secs, utilization);
+                total.saturating_add(burst_ms.saturating_add(idle_ms))
+            }),
+            _ => {
+                let (burst_ms, idle_ms) = cycle_ms(burst_config.max_secs, utilization);
+                burst_ms
+                    .saturating_add(idle_ms)
+                    .saturating_mul(CYCLES_PER_FRAME)
+                    .clamp(MIN_FRAME_MS, MAX_FRAME_MS)
+            }
+        };
+        FleetSchedule {
+            seed: sync.seed,
+            sampler: BurstSampler::new(burst_config.clone()),
+            burst_config,
+            utilization,
+            start_ms: sync.epoch_ms.saturating_add(sync.phase_offset_ms),
+            frame_ms: frame_ms.max(1),
+            frame: None,
+            rng: rand::rngs::StdRng::seed_from_u64(sync.seed),
+            cycle_start_ms: 0,
+            burst_ms: 0,
+            idle_ms: 0,
+        }
+    }
+
+    // Restart the cycle sequence at the start of `frame`
+    fn enter_frame(&mut self, frame: u64) {
+        use rand::SeedableRng;
+
+        self.frame = Some(frame);
+        self.rng = rand::rngs::StdRng::seed_from_u64(self.seed ^ frame.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        self.sampler = BurstSampler::new(self.burst_config.clone());
+        self.cycle_start_ms = self.start_ms.saturating_add(frame.saturating_mul(self.frame_ms));
+        self.sample_cycle();
+    }
+
+    fn sample_cycle(&mut self) {
+        let (burst_ms, idle_ms) = cycle_ms(self.sampler.next_secs(&mut self.rng), self.utilization);
+        let frame_end_ms = self.frame_end_ms();
+        let left_ms = frame_end_ms.saturating_sub(self.cycle_start_ms).max(1);
+        self.burst_ms = burst_ms.min(left_ms);
+        self.idle_ms = idle_ms.min(left_ms - self.burst_ms);
+    }
+
+    fn frame_end_ms(&self) -> u64 {
+        let frame = self.frame.unwrap_or(0);
+        self.start_ms.saturating_add(frame.saturating_add(1).saturating_mul(self.frame_ms))
+    }
+
+    // Return (burst_start, burst_end, cycle_end) of the cycle containing (or following) `now_ms`
+    pub(crate) fn current_cycle(&mut self, now_ms: u64) -> (u64, u64, u64) {
+        let frame = now_ms.saturating_sub(self.start_ms) / self.frame_ms;
+        if self.frame != Some(frame) {
+            self.enter_frame(frame);
+        }
+        // Cycles tile the frame, so this stops at the frame end at the latest
+        loop {
+            let cycle_end_ms = self.cycle_start_ms.saturating_add(self.burst_ms + self.idle_ms);
+            if cycle_end_ms > now_ms || cycle_end_ms >= self.frame_end_ms() {
+                break;
+            }
+            self.cycle_start_ms = cycle_end_ms;
+            self.sample_cycle();
+        }
+        let burst_end = self.cycle_start_ms.saturating_add(self.burst_ms);
+        (self.cycle_start_ms, burst_end, burst_end.saturating_add(self.idle_ms))
+    }
+}
+
+// Burst and idle milliseconds of one cycle with a burst of `burst_secs` at `utilization` percent
+fn cycle_ms(burst_secs: f64, utilization: u64) -> (u64, u64) {
+    let burst_ms = ((burst_secs * 1000.0) as u64).max(1);
+    (burst_ms, burst_ms.saturating_mul(100 - utilization) / utilization)
+}
+
+// Batches each burst is split into, so its throughput curve (ramp-up, steady state, tail) is captured
+const BATCHES_PER_BURST: f64 = 20.0;
+
+// Shortest batch worth spawning a worker process for (except the one that ends the burst)
+const MIN_BATCH_MS: f64 = 5.0;
+
+// Sizes the worker processes of a burst from the throughput of this core's previous batches
+pub(crate) struct BatchSizer {
+    // Upper bound per batch (the run's worker_ops)
+    max_ops: u64,
+    // Smoothed ops per millisecond, including process spawn overhead; None until the first batch
+    ops_per_ms: Option<f64>,
+}
+
+impl BatchSizer {
+    pub(crate) fn new(max_ops: u64) -> Self {
+        BatchSizer {
+            max_ops: max_ops.max(1),
+            ops_per_ms: None,
+        }
+    }
+
+    // Ops for the next batch: a 1/BATCHES_PER_BURST share of the burst, shrunk to end the burst on time
+    pub(crate) fn next_batch_ops(&self, planned_ms: f64, remaining_ms: f64) -> u64 {
+        let Some(ops_per_ms) = self.ops_per_ms else {
+            return self.max_ops;
+        };
+        let batch_ms = (planned_ms / BATCHES_PER_BURST).max(MIN_BATCH_MS).min(remaining_ms);
+        ((ops_per_ms * batch_ms) as u64).clamp(1, self.max_ops)
+    }
+
+    pub(crate) fn record(&mut self, ops: u64, elapsed: Duration) {
+        let ops_per_ms = ops as f64 / (elapsed.as_secs_f64() * 1000.0).max(0.001);
+        self.ops_per_ms = Some(match self.ops_per_ms {
+            Some(previous) => previous * 0.7 + ops_per_ms * 0.3,
+            None => ops_per_ms,
+        });
+    }
+}
+
+// Run batches back-to-back (fresh worker processes, or on this thread) until `burst_duration`
+// elapses (or the run stops), recording the completion of each batch. Returns the time actually
+// spent bursting, or None if a worker could not be spawned.
+fn run_burst(
+    state: &StressEngine,
+    signal: &RunSignal,
+    heartbeat: &Heartbeat,
+    core_id: usize,
+    sizer: &mut BatchSizer,
+    backend: BurstBackend,
+    burst_duration: Duration,
+) -> Option<u64> {
+    let planned_ms = burst_duration.as_millis() as u64;
+    emit_burst_event(
+        state,
+        BurstEvent::BurstStart {
+            core_id,
+            timestamp_ms: unix_time_ms(),
+            planned_ms,
+        },
+    );
+
+    let burst_start = Instant::now();
+    let mut burst_ops = 0u64;
+    let mut batches = Vec::new();
+    let mut spawn_failed = false;
+
+    while burst_start.elapsed() < burst_duration && !signal.is_stopped() {
+        heartbeat.beat();
+        let remaining = burst_duration.saturating_sub(burst_start.elapsed());
+        let batch_ops = sizer.next_batch_ops(planned_ms as f64, remaining.as_secs_f64() * 1000.0);
+        let batch_start = Instant::now();
+        let batch_ops = match backend {
+            BurstBackend::Thread => Some(count_primes(batch_ops)),
+            BurstBackend::Process => match run_worker_process(state, batch_ops, core_id) {
+                Ok(output) if output.status.success() => String::from_utf8(output.stdout)
+                    .ok()
+                    .and_then(|stdout| stdout.trim().parse::<u64>().ok()),
+                Ok(output) => {
+                    tracing::warn!(core_id, status = %output.status, "burst worker process failed");
+                    None
+                }
+                Err(e) => {
+                    tracing::error!(core_id, "failed to spawn burst worker process: {}", e);
+                    spawn_failed = true;
+                    break;
+                }
+            },
+        };
+
+        // Track burst operations
+        if let Some(ops) = batch_ops {
+            burst_ops += ops;
+            state.burst_operations.fetch_add(ops, Ordering::Relaxed);
+            signal.count_ops(state, ops);
+            sizer.record(ops, batch_start.elapsed());
+            record_batch_latency(state, batch_start.elapsed());
+            batches.push(BurstBatch {
+                completed_ms: burst_start.elapsed().as_millis() as u64,
+                ops,
+            });
+        }
+
+        // Track actual time spent in burst alongside the ops, so each sampling interval
+        // sees matching ops and time even when a burst spans several intervals
+        state
+            .burst_time_ms
+            .fetch_add(batch_start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    let actual_ms = burst_start.elapsed().as_millis() as u64;
+    emit_burst_event(
+        state,
+        BurstEvent::BurstEnd {
+            core_id,
+            timestamp_ms: unix_time_ms(),
+            planned_ms,
+            actual_ms,
+            ops: burst_ops,
+            batches,
+        },
+    );
+
+    if spawn_failed { None } else { Some(actual_ms) }
+}
+
+// Bursty mode: Alternate between CPU bursts and idle periods
+pub(crate) fn burst_coordinator(
+    state: Arc<StressEngine>,
+    signal: Arc<RunSignal>,
+    heartbeat: Arc<Heartbeat>,
+    mut config_rx: watch::Receiver<RunConfig>,
+    core_id: usize,
+) {
+    use rand::thread_rng;
+
+    let mut rng = thread_rng(); // Independent RNG per thread
+    let mut sizer = BatchSizer::new(config_rx.borrow().worker_ops);
+    // Burst durations and fleet schedule in use, rebuilt whenever the run is reconfigured
+    let mut sampler: Option<BurstSampler> = None;
+    let mut fleet_schedule: Option<FleetSchedule> = None;
+    // Last external trigger this coordinator has acted on (or skipped)
+    let mut seen_trigger = 0u64;
+
+    while !signal.is_stopped() {
+        heartbeat.beat();
+
+        if config_rx.has_changed().unwrap_or(false) {
+            sampler = None;
+            fleet_schedule = None;
+            // Batch throughput differs between backends, and the batch cap may have changed
+            sizer = BatchSizer::new(config_rx.borrow().worker_ops);
+        }
+        let config = config_rx.borrow_and_update().clone();
+        let utilization = config.utilization;
+        let backend = config.burst_backend;
+        let sampler = sampler.get_or_insert_with(|| BurstSampler::new(config.burst_config.clone()));
+
+        // EXTERNALLY TRIGGERED - sleep until the next trigger
+        if config.burst_trigger == BurstTrigger::External {
+            let Some(trigger) = signal.wait_for_trigger(&heartbeat, seen_trigger) else {
+                continue;
+            };
+            seen_trigger = trigger.generation;
+            let burst_duration = match trigger.duration_ms {
+                Some(ms) => Duration::from_millis(ms),
+                None => Duration::from_secs_f64(sampler.next_secs(&mut rng)),
+            };
+
+            if run_burst(&state, &signal, &heartbeat, core_id, &mut sizer, backend, burst_duration).is_none() {
+                signal.sleep(&heartbeat, Duration::from_millis(100));
+            }
+            continue;
+        }
+        // Triggers fired while not in external mode must not fire later
+        seen_trigger = signal.last_trigger().generation;
+
+        // FLEET-ALIGNED SCHEDULE - burst in the shared wall-clock windows
+        if let Some(sync) = config.fleet_sync {
+            let schedule = fleet_schedule
+                .get_or_insert_with(|| FleetSchedule::new(sync, config.burst_config.clone(), utilization));
+
+            let now_ms = unix_time_ms();
+            let (burst_start_ms, burst_end_ms, cycle_end_ms) = schedule.current_cycle(now_ms);
+            if now_ms < burst_start_ms {
+                signal.sleep(&heartbeat, Duration::from_millis(burst_start_ms - now_ms));
+            } else if now_ms < burst_end_ms {
+                let burst_duration = Duration::from_millis(burst_end_ms - now_ms);
+                if run_burst(&state, &signal, &heartbeat, core_id, &mut sizer, backend, burst_duration).is_none() {
+                    signal.sleep(&heartbeat, Duration::from_millis(100));
+                }
+            } else {
+                signal.sleep(&heartbeat, Duration::from_millis(cycle_end_ms - now_ms));
+            }
+            continue;
+        }
+
+        // INDEPENDENT SCHEDULE - take the next burst duration from the configured source
+        let burst_duration = Duration::from_secs_f64(sampler.next_secs(&mut rng));
+
+        // BURST PERIOD - run batches back-to-back until the burst duration elapses
+        let Some(burst_elapsed_ms) =
+            run_burst(&state, &signal, &heartbeat, core_id, &mut sizer, backend, burst_duration)
+        else {
+            signal.sleep(&heartbeat, Duration::from_millis(100));
+            continue;
+        };
+
+        // Calculate idle duration from the actual burst length
+        // utilization = burst_time / (burst_time + idle_time)
+        // Solving for idle_time: idle_time = burst_time * (100 - utilization) / utilization
+        let idle_duration_ms = if utilization > 0 && utilization < 100 {
+            (burst_elapsed_ms * (100 - utilization)) / utilization
+        } else if utilization == 100 {
+            0 // No idle time
+        } else {
+            u64::MAX // utilization = 0 means always idle (until stopped or reconfigured)
+        };
+
+        // IDLE PERIOD - ends early if the run is stopped or reconfigured
+        if idle_duration_ms > 0 {
+            signal.sleep(&heartbeat, Duration::from_millis(idle_duration_ms));
+        }
+    }
+}
+
+// Simulated agent: feed synthetic operation counts into the normal metrics pipeline
+pub(crate) fn simulated_worker(
+    state: Arc<StressEngine>,
+    signal: Arc<RunSignal>,
+    heartbeat: Arc<Heartbeat>,
+    config: watch::Receiver<RunConfig>,
+    num_cores: usize,
+    per_core_ops_per_sec: f64,
+) {
+    use rand::{Rng, thread_rng};
+
+    const TICK_MS: u64 = 100;
+
+    // Memory bandwidth per simulated stream worker, in bytes per second
+    const STREAM_BYTES_PER_SEC: f64 = 8e9;
+
+    let mut rng = thread_rng();
+    let started = Instant::now();
+
+    while signal.sleep(&heartbeat, Duration::from_millis(TICK_MS)) {
+        let config = config.borrow().clone();
+        let mode = config.mode;
+        // Soft start: count only the simulated workers whose ramp delay has passed
+        let active_cores = (0..num_cores)
+            .filter(|&index| config.ramp_delay(index, num_cores) <= started.elapsed())
+            .count();
+        // ±5% noise per tick, and a small penalty for process creation overhead
+        let noise: f64 = rng.gen_range(0.95..1.05);
+        let rate = match mode {
+            ExecutionMode::Threaded | ExecutionMode::Throttled => per_core_ops_per_sec,
+            ExecutionMode::Bursty if config.burst_backend == BurstBackend::Thread => per_core_ops_per_sec,
+            ExecutionMode::FreshProcess | ExecutionMode::Bursty => per_core_ops_per_sec * 0.9,
+        } * noise;
+
+        let busy_ms = match mode {
+            ExecutionMode::Bursty | ExecutionMode::Throttled => {
+                TICK_MS * active_cores as u64 * config.utilization / 100
+            }
+            _ => TICK_MS * active_cores as u64,
+        };
+        let ops = (rate * busy_ms as f64 / 1000.0) as u64;
+
+        if config.workload == Workload::Stream {
+            signal.count_bytes(&state, (STREAM_BYTES_PER_SEC * noise * busy_ms as f64 / 1000.0) as u64);
+            continue;
+        }
+        if mode == ExecutionMode::Bursty {
+            state.burst_operations.fetch_add(ops, Ordering::Relaxed);
+            state.burst_time_ms.fetch_add(busy_ms, Ordering::Relaxed);
+        }
+        signal.count_ops(&state, ops);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn is_prime_matches_known_primes() {
+        let primes: Vec<u64> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(1_000_003));
+        assert!(!is_prime(1_000_001));
+    }
+
+    #[test]
+    fn stream_kernels_match_stream_and_count_bytes() {
+        // 1 MiB arrays of 131072 doubles each
+        let mut arrays = StreamArrays::new(1).unwrap();
+        let moved: Vec<u64> = (0..4).map(|_| arrays.step()).collect();
+        assert_eq!(moved, [2 << 20, 2 << 20, 3 << 20, 3 << 20]);
+        // a = 1, b = 2, c = 0 -> copy c = 1, scale b = 3, add c = 4, triad a = 3 + 3 * 4
+        assert!(arrays.a.iter().all(|&a| a == 15.0));
+        assert!(arrays.b.iter().all(|&b| b == 3.0) && arrays.c.iter().all(|&c| c == 4.0));
+        assert_eq!((arrays.a.len(), arrays.next), (131_072, 0));
+    }
+
+    #[test]
+    fn burst_config_rejects_invalid_options() {
+        let options = RunOptions {
+            burst_mean_secs: Some(0.0),
+            ..RunOptions::default()
+        };
+        assert!(BurstConfig::from_options(&options).is_err());
+
+        let options = RunOptions {
+            burst_min_secs: Some(2.0),
+            burst_max_secs: Some(1.0),
+            ..RunOptions::default()
+        };
+        assert!(BurstConfig::from_options(&options).is_err());
+
+        let options = RunOptions {
+            burst_distribution: Some(BurstDistribution::Fixed),
+            burst_mean_secs: Some(1.0),
+            burst_min_secs: Some(5.0),
+            burst_max_secs: Some(10.0),
+            ..RunOptions::default()
+        };
+        let errors = BurstConfig::from_options(&options).unwrap_err();
+        assert_eq!(errors[0].field, "burst_mean_secs");
+        // Uniform durations don't use the mean
+        let options = RunOptions {
+            burst_distribution: Some(BurstDistribution::Uniform),
+            ..options
+        };
+        assert!(BurstConfig::from_options(&options).is_ok());
+    }
+
+    #[test]
+    fn burst_samples_stay_within_bounds() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        for distribution in [BurstDistribution::Exponential, BurstDistribution::Uniform, BurstDistribution::Fixed] {
+            let config = BurstConfig::from_options(&RunOptions {
+                burst_distribution: Some(distribution),
+                burst_min_secs: Some(0.1),
+                burst_max_secs: Some(0.5),
+                ..RunOptions::default()
+            })
+            .unwrap();
+            for _ in 0..1000 {
+                let secs = config.sample_secs(&mut rng);
+                assert!((0.1..=0.5).contains(&secs), "{:?} sampled {}", distribution, secs);
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_sources_are_systematic() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let config = BurstConfig::from_options(&RunOptions {
+            burst_source: Some(BurstSource::LowDiscrepancy),
+            burst_distribution: Some(BurstDistribution::Uniform),
+            burst_min_secs: Some(0.1),
+            burst_max_secs: Some(0.5),
+            ..RunOptions::default()
+        })
+        .unwrap();
+        let mut sampler = BurstSampler::new(config.clone());
+        let mut durations: Vec<f64> = (0..63).map(|_| sampler.next_secs(&mut rng)).collect();
+        assert!((durations[0] - 0.3).abs() < 1e-9 && (durations[1] - 0.2).abs() < 1e-9);
+        // 63 points split [0.1, 0.5] into 64 equal gaps
+        durations.sort_by(f64::total_cmp);
+        for pair in durations.windows(2) {
+            assert!((pair[1] - pair[0] - 0.4 / 64.0).abs() < 1e-9);
+        }
+
+        let exponential = BurstConfig { distribution: BurstDistribution::Exponential, ..config };
+        assert!((exponential.quantile_secs(0.0) - 0.1).abs() < 1e-9);
+        assert!((exponential.quantile_secs(1.0 - 1e-12) - 0.5).abs() < 1e-6);
+
+        let options = RunOptions {
+            burst_schedule: Some(vec![0.2, 1.5, 0.7]),
+            ..RunOptions::default()
+        };
+        let mut sampler = BurstSampler::new(BurstConfig::from_options(&options).unwrap());
+        let played: Vec<f64> = (0..5).map(|_| sampler.next_secs(&mut rng)).collect();
+        assert_eq!(played, vec![0.2, 1.5, 0.7, 0.2, 1.5]);
+
+        for invalid in [
+            RunOptions { burst_source: Some(BurstSource::Schedule), ..RunOptions::default() },
+            RunOptions { burst_schedule: Some(vec![0.2, -1.0]), ..RunOptions::default() },
+            RunOptions {
+                burst_source: Some(BurstSource::LowDiscrepancy),
+                burst_schedule: Some(vec![0.2]),
+                ..RunOptions::default()
+            },
+        ] {
+            assert!(BurstConfig::from_options(&invalid).is_err(), "accepted {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn batch_sizer_splits_bursts_and_shrinks_the_tail() {
+        let mut sizer = BatchSizer::new(20_000);
+        // Nothing measured yet: one full worker
+        assert_eq!(sizer.next_batch_ops(1000.0, 1000.0), 20_000);
+
+        sizer.record(1000, Duration::from_millis(10));
+        assert_eq!(sizer.next_batch_ops(1000.0, 1000.0), 5000);
+        // Short bursts still get batches of at least MIN_BATCH_MS
+        assert_eq!(sizer.next_batch_ops(40.0, 40.0), 500);
+        // The last batch only fills the time left
+        assert_eq!(sizer.next_batch_ops(1000.0, 2.0), 200);
+        assert_eq!(sizer.next_batch_ops(1000.0, 0.0), 1);
+        // Never more than one full worker
+        assert_eq!(sizer.next_batch_ops(100_000.0, 100_000.0), 20_000);
+    }
+
+    #[test]
+    fn fleet_schedule_is_deterministic_and_honors_utilization() {
+        let sync = FleetSync {
+            seed: 42,
+            epoch_ms: 1_000_000,
+            phase_offset_ms: 0,
+        };
+        let mut a = FleetSchedule::new(sync, BurstConfig::default(), 25);
+        let mut b = FleetSchedule::new(sync, BurstConfig::default(), 25);
+
+        for now_ms in (1_000_000..1_060_000).step_by(700) {
+            let cycle = a.current_cycle(now_ms);
+            assert_eq!(cycle, b.current_cycle(now_ms));
+
+            let (burst_start, burst_end, cycle_end) = cycle;
+            assert!(burst_start <= now_ms && now_ms < cycle_end);
+            // Idle time is three times the burst time at 25%, up to integer rounding
+            assert_eq!(cycle_end - burst_end, (burst_end - burst_start) * 3);
+        }
+    }
+
+    #[test]
+    fn fleet_schedule_jumps_to_the_current_frame() {
+        let sync = FleetSync {
+            seed: 7,
+            epoch_ms: 0,
+            phase_offset_ms: 0,
+        };
+        let now_ms = 1_700_000_000_000;
+        // Stepping through from an earlier time and joining late give the same cycles
+        let mut early = FleetSchedule::new(sync, BurstConfig::default(), 50);
+        early.current_cycle(now_ms - 90_000);
+        let mut late = FleetSchedule::new(sync, BurstConfig::default(), 50);
+        for now_ms in (now_ms..now_ms + 120_000).step_by(333) {
+            let cycle = late.current_cycle(now_ms);
+            assert_eq!(cycle, early.current_cycle(now_ms));
+            assert!(cycle.0 <= now_ms && now_ms < cycle.2);
+        }
+
+        let overflowing = FleetSync {
+            epoch_ms: u64::MAX,
+            phase_offset_ms: 1,
+            ..sync
+        };
+        let (burst_start, _, _) = FleetSchedule::new(overflowing, BurstConfig::default(), 50).current_cycle(now_ms);
+        assert_eq!(burst_start, u64::MAX);
+    }
+}